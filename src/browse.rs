@@ -0,0 +1,267 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::IsTerminal;
+
+use crate::config::{canonical_language, Template, TemplateFilter};
+use crate::manager::TemplateManager;
+use crate::ui::is_interactive;
+use crate::git::short_sha;
+use crate::utils::format_duration;
+
+const HELP: &str = "↑/↓ move  / search  l language  t tag  Esc clear  Enter create  q quit";
+
+/// State of the `browse` screen: every enabled template, the active filters and the
+/// templates matching them
+struct Browser<'a> {
+    manager: &'a TemplateManager,
+    templates: Vec<&'a Template>,
+    languages: Vec<String>,
+    tags: Vec<String>,
+    filter: TemplateFilter,
+    /// Whether keys are typed into the search query
+    searching: bool,
+    visible: Vec<&'a Template>,
+    list: ListState,
+}
+
+impl<'a> Browser<'a> {
+    fn new(manager: &'a TemplateManager, language: Option<&str>) -> Self {
+        let mut templates: Vec<&Template> = manager
+            .config
+            .templates
+            .iter()
+            .filter(|t| manager.is_template_enabled(t))
+            .collect();
+        manager.sort_for_picker(&mut templates);
+        
+        let mut languages: Vec<String> = templates.iter().map(|t| canonical_language(&t.language)).collect();
+        languages.sort();
+        languages.dedup();
+        let mut tags: Vec<String> = templates.iter().flat_map(|t| t.tags.iter().cloned()).collect();
+        tags.sort();
+        tags.dedup();
+        
+        let mut browser = Self {
+            manager,
+            templates,
+            languages,
+            tags,
+            filter: TemplateFilter {
+                language: language.map(str::to_string),
+                ..TemplateFilter::default()
+            },
+            searching: false,
+            visible: Vec::new(),
+            list: ListState::default(),
+        };
+        browser.apply_filter();
+        browser
+    }
+    
+    fn apply_filter(&mut self) {
+        self.visible = self
+            .templates
+            .iter()
+            .copied()
+            .filter(|t| self.filter.matches(t))
+            .collect();
+        self.list.select(if self.visible.is_empty() { None } else { Some(0) });
+    }
+    
+    fn selected(&self) -> Option<&'a Template> {
+        self.list.selected().and_then(|index| self.visible.get(index).copied())
+    }
+    
+    /// Handle a key, returning the template to create a project from or `Some(None)` to quit
+    fn handle_key(&mut self, code: KeyCode) -> Option<Option<&'a Template>> {
+        if self.searching {
+            match code {
+                KeyCode::Enter | KeyCode::Esc => self.searching = false,
+                KeyCode::Backspace => {
+                    let mut query = self.filter.query.take().unwrap_or_default();
+                    query.pop();
+                    self.filter.query = Some(query).filter(|q| !q.is_empty());
+                    self.apply_filter();
+                }
+                KeyCode::Char(c) => {
+                    self.filter.query.get_or_insert_with(String::new).push(c);
+                    self.apply_filter();
+                }
+                _ => {}
+            }
+            return None;
+        }
+        
+        match code {
+            KeyCode::Char('q') => return Some(None),
+            KeyCode::Enter => return self.selected().map(Some),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Char('l') => {
+                self.filter.language = next_choice(&self.languages, self.filter.language.as_deref());
+                self.apply_filter();
+            }
+            KeyCode::Char('t') => {
+                self.filter.tag = next_choice(&self.tags, self.filter.tag.as_deref());
+                self.apply_filter();
+            }
+            KeyCode::Esc => {
+                self.filter = TemplateFilter::default();
+                self.apply_filter();
+            }
+            _ => {}
+        }
+        None
+    }
+    
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<&'a Template>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if let Some(picked) = self.handle_key(key.code) {
+                    return Ok(picked);
+                }
+            }
+        }
+    }
+    
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] = Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(main);
+        
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|t| {
+                let marker = match self.manager.template_status(t) {
+                    "cached" => Span::styled("● ", Style::default().fg(Color::Green)),
+                    _ => Span::styled("○ ", Style::default().fg(Color::DarkGray)),
+                };
+                ListItem::new(Line::from(vec![
+                    marker,
+                    Span::styled(t.id.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(format!("  {}", t.name.localized())),
+                ]))
+            })
+            .collect();
+        let title = format!(" Templates ({}/{}) ", self.visible.len(), self.templates.len());
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().bg(Color::Blue).fg(Color::White))
+            .highlight_symbol("▶ ");
+        frame.render_stateful_widget(list, left, &mut self.list);
+        
+        let details = match self.selected() {
+            Some(template) => self.details(template),
+            None => vec![Line::from("No templates match the filters, press Esc to clear them")],
+        };
+        frame.render_widget(
+            Paragraph::new(details)
+                .block(Block::default().borders(Borders::ALL).title(" Details "))
+                .wrap(Wrap { trim: false }),
+            right,
+        );
+        
+        frame.render_widget(Paragraph::new(self.status_line()), status);
+    }
+    
+    fn details(&self, template: &Template) -> Vec<Line<'static>> {
+        let field = |label: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{:<12}", label), Style::default().fg(Color::Cyan)),
+                Span::raw(value),
+            ])
+        };
+        let mut lines = vec![
+            Line::from(Span::styled(
+                template.name.localized().to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Line::from(template.description.localized().to_string()),
+            Line::from(""),
+            field("ID", template.qualified_id()),
+            field("Language", template.language.clone()),
+        ];
+        if let Some(category) = &template.category {
+            lines.push(field("Category", category.clone()));
+        }
+        lines.push(field("Tags", template.tags.join(", ")));
+        lines.push(field("Repository", template.repo.clone()));
+        lines.push(field("Path", template.path.clone()));
+        if let Some(branch) = &template.branch {
+            lines.push(field("Branch", branch.clone()));
+        }
+        if !template.dependencies.is_empty() {
+            lines.push(field("Depends on", template.dependencies.join(", ")));
+        }
+        
+        lines.push(Line::from(""));
+        match self.manager.template_status(template) {
+            "cached" => {
+                let age = self
+                    .manager
+                    .cache_age(template)
+                    .map(|age| format!(", fetched {} ago", format_duration(age)))
+                    .unwrap_or_default();
+                lines.push(field("Cache", format!("cached{}", age)));
+                if let Some(version) = self.manager.cached_version(template) {
+                    lines.push(field("Version", version));
+                }
+                if let Some(commit) = self.manager.cached_commit(template) {
+                    lines.push(field("Commit", short_sha(&commit).to_string()));
+                }
+            }
+            _ => lines.push(field("Cache", "not cached, downloaded on create".to_string())),
+        }
+        lines
+    }
+    
+    fn status_line(&self) -> Line<'static> {
+        if self.searching {
+            return Line::from(format!("/{}▏", self.filter.query.as_deref().unwrap_or_default()));
+        }
+        let filters = self.filter.describe();
+        if filters.is_empty() {
+            Line::from(HELP)
+        } else {
+            Line::from(format!("{}  |  {}", filters, HELP))
+        }
+    }
+}
+
+/// Value after `current` in `choices`, wrapping around to no filter after the last one
+fn next_choice(choices: &[String], current: Option<&str>) -> Option<String> {
+    let next = match current.and_then(|c| choices.iter().position(|choice| choice == c)) {
+        Some(index) => index + 1,
+        None if current.is_some() => return None,
+        None => 0,
+    };
+    choices.get(next).cloned()
+}
+
+/// Browse the templates full-screen and return the one picked with Enter, `None` when
+/// the browser is closed without picking one
+pub fn browse_templates<'a>(manager: &'a TemplateManager, language: Option<&str>) -> Result<Option<&'a Template>> {
+    if !is_interactive() || !std::io::stdout().is_terminal() {
+        anyhow::bail!("browse needs an interactive terminal, use 'template list' or 'template search' instead");
+    }
+    if manager.config.templates.is_empty() {
+        anyhow::bail!("No templates available. Add templates first with 'template add'");
+    }
+    
+    let mut browser = Browser::new(manager, language);
+    let mut terminal = ratatui::init();
+    let picked = browser.run(&mut terminal);
+    // 无论是否出错都要恢复终端
+    ratatui::restore();
+    picked
+}
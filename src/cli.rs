@@ -1,13 +1,46 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, Parser, Subcommand};
+use clap_complete::engine::ArgValueCandidates;
+use clap_complete::Shell;
+
+use crate::completions::{repo_candidates, template_candidates};
+use crate::manager::DEFAULT_CONCURRENCY;
 
 #[derive(Parser)]
 #[command(name = "mammoth-cli")]
 #[command(about = "Mammoth - A powerful frontend project scaffolding CLI tool")]
 #[command(version)]
 pub struct Cli {
-    /// Enable verbose output
-    #[arg(short, long)]
-    pub verbose: bool,
+    /// Log git commands, file operations and timings to stderr, -vv for debug detail
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+    
+    /// Only print errors: no status messages, warnings or progress
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+    
+    /// CI mode: no progress bars or prompts, one timestamped line per step (auto-detected from CI env vars)
+    #[arg(long, global = true)]
+    pub ci: bool,
+    
+    /// Screen-reader friendly output: no emoji, colors or progress bars
+    #[arg(long, global = true)]
+    pub plain: bool,
+    
+    /// Never prompt: take defaults and fail with a list of missing required inputs
+    #[arg(long, visible_alias = "non-interactive", global = true)]
+    pub yes: bool,
+    
+    /// Take the default answer of prompts left unanswered this long, e.g. "30s"
+    #[arg(long, global = true)]
+    pub prompt_timeout: Option<String>,
+    
+    /// Never touch the network: only cached templates and local repositories are usable
+    #[arg(long, global = true)]
+    pub offline: bool,
+    
+    /// Print how long each phase (config load, clone, checkout, copy, render, ...) took
+    #[arg(long, global = true)]
+    pub timings: bool,
     
     #[command(subcommand)]
     pub command: Option<Commands>,
@@ -18,9 +51,13 @@ pub enum Commands {
     /// Create a new project
     New {
         /// Template ID
-        #[arg(short, long)]
+        #[arg(short, long, add = ArgValueCandidates::new(template_candidates))]
         template: Option<String>,
         
+        /// Addon templates applied over the template in order, comma-separated, e.g. eslint,tailwind,docker
+        #[arg(long = "with")]
+        with: Option<String>,
+        
         /// Project name
         #[arg(short, long)]
         name: Option<String>,
@@ -28,8 +65,60 @@ pub enum Commands {
         /// Output directory
         #[arg(short, long, default_value = ".")]
         output: String,
+        
+        /// Fetch the template fresh into a temp dir, bypassing the cache
+        #[arg(long)]
+        no_cache: bool,
+        
+        /// Only offer templates of this language
+        #[arg(short, long)]
+        language: Option<String>,
+        
+        /// Update dependency ranges in package.json to the newest versions on npm
+        #[arg(long)]
+        latest_deps: bool,
+        
+        /// Add community files: comma-separated codeowners, contributing, changelog or "all"
+        #[arg(long)]
+        community: Option<String>,
+        
+        /// Organization or team used as owner in community files, e.g. "@acme/frontend"
+        #[arg(long)]
+        org: Option<String>,
+        
+        /// Insert an SPDX license header (e.g. MIT) into .ts/.js/.vue/.css source files
+        #[arg(long)]
+        license: Option<String>,
+        
+        /// Keep the template's git history in the new project (uses git subtree split)
+        #[arg(long)]
+        keep_history: bool,
+        
+        /// Print a JSON summary of the generated project
+        #[arg(long)]
+        json: bool,
+        
+        /// YAML or JSON file answering the prompts (name, author, description, template variables)
+        #[arg(long)]
+        values: Option<String>,
+        
+        /// Install dependencies with the package manager detected from the template
+        #[arg(long, conflicts_with = "no_install")]
+        install: bool,
+        
+        /// Do not install dependencies and do not ask
+        #[arg(long)]
+        no_install: bool,
+        
+        /// Generate into a non-empty project directory, overwriting files that differ
+        #[arg(long, conflicts_with = "merge")]
+        force: bool,
+        
+        /// Generate into a non-empty project directory, only adding missing files
+        #[arg(long)]
+        merge: bool,
     },
-    /// Clean configuration and cache
+    /// Clean configuration and cache (see `cache clean` to evict a single template)
     Clean {
         /// Also remove configuration file
         #[arg(short, long)]
@@ -38,6 +127,24 @@ pub enum Commands {
         /// Skip confirmation
         #[arg(short, long)]
         force: bool,
+        
+        /// Move files to the OS trash (default on interactive runs)
+        #[arg(long, conflicts_with = "permanent")]
+        trash: bool,
+        
+        /// Delete files permanently instead of moving them to the trash
+        #[arg(long)]
+        permanent: bool,
+    },
+    /// Browse templates full-screen and create a project from the selected one
+    Browse {
+        /// Start with only templates of this language
+        #[arg(short, long)]
+        language: Option<String>,
+        
+        /// Output directory of the created project
+        #[arg(short, long, default_value = ".")]
+        output: String,
     },
     /// Show configuration information
     Info {
@@ -45,6 +152,118 @@ pub enum Commands {
         #[arg(short, long)]
         json: bool,
     },
+    /// Show paths, tool versions and platform details for bug reports
+    Env {
+        /// Show as JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Diagnose git, the config file, the cache directory and repository access
+    Doctor {
+        /// Print the checks as a JSON array
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate the script for
+        #[arg(value_enum)]
+        shell: Shell,
+        
+        /// Script that also completes template IDs and repository names from the config
+        #[arg(long)]
+        dynamic: bool,
+    },
+    /// Show differences between a project and its template
+    Diff {
+        /// Template ID
+        #[arg(add = ArgValueCandidates::new(template_candidates))]
+        template: String,
+        
+        /// Project directory
+        #[arg(short, long, default_value = ".")]
+        dir: String,
+        
+        /// Show changed files with insertion/deletion counts
+        #[arg(long, conflicts_with = "name_only")]
+        stat: bool,
+        
+        /// Show changed file names only
+        #[arg(long)]
+        name_only: bool,
+    },
+    /// Apply the latest template files to an existing project
+    Upgrade {
+        /// Template ID
+        #[arg(add = ArgValueCandidates::new(template_candidates))]
+        template: String,
+        
+        /// Project directory
+        #[arg(short, long, default_value = ".")]
+        dir: String,
+        
+        /// Conflict policy: prompt (default), mine, theirs or new
+        #[arg(long, default_value = "prompt")]
+        on_conflict: String,
+    },
+    /// Pull template changes since the revisions in mammoth.lock into a project with a three-way merge
+    Update {
+        /// Project directory
+        #[arg(default_value = ".")]
+        dir: String,
+        
+        /// Only report what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Recreate a project exactly from its mammoth.lock
+    Regenerate {
+        /// Project directory or lock file
+        #[arg(default_value = ".")]
+        lock: String,
+        
+        /// Output directory of the recreated project
+        #[arg(short, long, default_value = ".")]
+        output: String,
+        
+        /// Write into a non-empty project directory, overwriting files that differ
+        #[arg(long)]
+        force: bool,
+    },
+    /// Turn an existing project into a template
+    Templatize {
+        /// Project directory
+        #[arg(default_value = ".")]
+        dir: String,
+        
+        /// Template directory to create (defaults to <project>-template)
+        #[arg(short, long)]
+        output: Option<String>,
+        
+        /// Register the template against this repository
+        #[arg(long)]
+        register: Option<String>,
+        
+        /// Template ID used when registering (defaults to the package name)
+        #[arg(long, requires = "register")]
+        id: Option<String>,
+        
+        /// Template path inside the repository (defaults to the ID)
+        #[arg(long, requires = "register")]
+        path: Option<String>,
+        
+        /// Propose template variables for the project's name, author and repeated literals
+        #[arg(long)]
+        extract_vars: bool,
+    },
+    /// Revert the last configuration change
+    Undo,
+    /// Show the log of state-changing operations
+    Log {
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
     /// Template management
     Template {
         #[command(subcommand)]
@@ -60,6 +279,52 @@ pub enum Commands {
         #[command(subcommand)]
         command: ConfigCommands,
     },
+    /// Cache inspection and pruning
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+    /// Remote template catalogs published by an organization
+    Registry {
+        #[command(subcommand)]
+        command: RegistryCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RegistryCommands {
+    /// Add a registry index
+    Add {
+        /// Registry name
+        name: String,
+        
+        /// http(s) URL or path of a JSON or TOML index with `repos` and `templates`
+        url: String,
+        
+        /// Sync the registry right away
+        #[arg(short, long)]
+        sync: bool,
+    },
+    /// Pull registry indexes and merge their repositories and templates into the config
+    Sync {
+        /// Registry to sync, all when omitted
+        name: Option<String>,
+    },
+    /// List registries and when they were last synced
+    List {
+        /// Print the registries as JSON
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Remove a registry
+    Remove {
+        /// Registry name
+        name: String,
+        
+        /// Also remove the repositories and templates it added
+        #[arg(long)]
+        prune: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -69,21 +334,98 @@ pub enum TemplateCommands {
         /// Show detailed information
         #[arg(short, long)]
         verbose: bool,
+        
+        /// Only list templates of this language
+        #[arg(short, long)]
+        language: Option<String>,
+        
+        /// Only list templates with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+        
+        /// Only list templates of this repository
+        #[arg(short, long, add = ArgValueCandidates::new(repo_candidates))]
+        repo: Option<String>,
+        
+        /// Fetch GitHub stars and last commit dates (set GITHUB_TOKEN for higher rate limits)
+        #[arg(long, requires = "verbose")]
+        popularity: bool,
+        
+        /// Print the templates with their cache status as a JSON array
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Search templates by ID, name, description and tags
+    Search {
+        /// Text to search for (case-insensitive)
+        query: String,
+        
+        /// Show detailed information
+        #[arg(short, long)]
+        verbose: bool,
+        
+        /// Only search templates of this language
+        #[arg(short, long)]
+        language: Option<String>,
+        
+        /// Only search templates with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+        
+        /// Only search templates of this repository
+        #[arg(short, long, add = ArgValueCandidates::new(repo_candidates))]
+        repo: Option<String>,
+        
+        /// Print the matching templates as a JSON array
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Propose template variables for literals in a template directory
+    ExtractVars {
+        /// Template or project directory
+        #[arg(default_value = ".")]
+        dir: String,
+    },
+    /// Create a skeleton template with a manifest, example placeholders and an author README
+    Init {
+        /// Directory to create the template in, must be empty
+        dir: String,
+        
+        /// Write mammoth.toml instead of mammoth.json
+        #[arg(long)]
+        toml: bool,
     },
     /// Download/update a specific template
     Download {
         /// Template ID
-        template_id: String,
+        #[arg(required_unless_present = "select", add = ArgValueCandidates::new(template_candidates))]
+        template_id: Option<String>,
+        
+        /// Pick the templates to download from a checklist
+        #[arg(long, conflicts_with_all = ["template_id", "no_cache"])]
+        select: bool,
         
         /// Force update
         #[arg(short, long)]
         force: bool,
+        
+        /// Ignore the existing cache and re-fetch with a fresh clone
+        #[arg(long)]
+        no_cache: bool,
+        
+        /// Pin the template to this tag or full commit SHA before downloading, empty to unpin
+        #[arg(long = "ref", conflicts_with = "select")]
+        git_ref: Option<String>,
     },
     /// Download/update all templates
     DownloadAll {
         /// Force update
         #[arg(short, long)]
         force: bool,
+        
+        /// Number of templates downloaded at the same time
+        #[arg(short, long, default_value_t = DEFAULT_CONCURRENCY)]
+        concurrency: usize,
     },
     /// Add a new template
     Add {
@@ -95,7 +437,7 @@ pub enum TemplateCommands {
         name: String,
         
         /// Repository name
-        #[arg(short, long)]
+        #[arg(short, long, add = ArgValueCandidates::new(repo_candidates))]
         repo: String,
         
         /// Template path in repository
@@ -113,12 +455,195 @@ pub enum TemplateCommands {
         /// Tags (comma-separated)
         #[arg(short, long)]
         tags: Option<String>,
+        
+        /// Branch overriding the repository branch
+        #[arg(short, long)]
+        branch: Option<String>,
+        
+        /// Pin the template to a tag or full commit SHA instead of the branch tip
+        #[arg(long = "ref")]
+        git_ref: Option<String>,
+        
+        /// Templates applied before this one (comma-separated IDs)
+        #[arg(long)]
+        depends_on: Option<String>,
+        
+        /// Category used to group listings, e.g. "Apps"
+        #[arg(short, long)]
+        category: Option<String>,
+        
+        /// Display color, a color name or "#rrggbb"
+        #[arg(long)]
+        color: Option<String>,
+        
+        /// Emoji/icon shown before the template ID
+        #[arg(long)]
+        icon: Option<String>,
+        
+        /// Download the template into the cache right away
+        #[arg(long)]
+        download: bool,
+    },
+    /// Update fields of an existing template
+    Update {
+        /// Template ID
+        #[arg(add = ArgValueCandidates::new(template_candidates))]
+        template_id: String,
+        
+        /// Template name
+        #[arg(short, long)]
+        name: Option<String>,
+        
+        /// Repository name
+        #[arg(short, long, add = ArgValueCandidates::new(repo_candidates))]
+        repo: Option<String>,
+        
+        /// Template path in repository
+        #[arg(short, long)]
+        path: Option<String>,
+        
+        /// Template description
+        #[arg(short, long)]
+        description: Option<String>,
+        
+        /// Language
+        #[arg(short, long)]
+        language: Option<String>,
+        
+        /// Tags (comma-separated)
+        #[arg(short, long)]
+        tags: Option<String>,
+        
+        /// Branch overriding the repository branch, empty to use the repository branch
+        #[arg(short, long)]
+        branch: Option<String>,
+        
+        /// Tag or full commit SHA to pin the template to, empty to follow the branch again
+        #[arg(long = "ref")]
+        git_ref: Option<String>,
+        
+        /// Templates applied before this one (comma-separated IDs, empty to clear)
+        #[arg(long)]
+        depends_on: Option<String>,
+        
+        /// Category used to group listings, empty to clear
+        #[arg(short, long)]
+        category: Option<String>,
+        
+        /// Display color, a color name or "#rrggbb"
+        #[arg(long)]
+        color: Option<String>,
+        
+        /// Emoji/icon shown before the template ID
+        #[arg(long)]
+        icon: Option<String>,
+    },
+    /// Show details and cache status of a template
+    Show {
+        /// Template ID
+        #[arg(add = ArgValueCandidates::new(template_candidates))]
+        template_id: String,
+    },
+    /// Report cached templates that are behind their upstream branch
+    Outdated {
+        /// Print one JSON object per cached template with full commit ids
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Show upstream changes to a template since it was cached
+    Changelog {
+        /// Template ID
+        #[arg(add = ArgValueCandidates::new(template_candidates))]
+        template_id: String,
+    },
+    /// Hide a template from the picker and block it in `new`, keeping its config and cache
+    Disable {
+        /// Template ID
+        #[arg(add = ArgValueCandidates::new(template_candidates))]
+        template_id: String,
+    },
+    /// Re-enable a disabled template
+    Enable {
+        /// Template ID
+        #[arg(add = ArgValueCandidates::new(template_candidates))]
+        template_id: String,
+    },
+    /// Copy a template entry to a new ID, e.g. for a variant on another path or branch
+    Duplicate {
+        /// Template ID to copy
+        #[arg(add = ArgValueCandidates::new(template_candidates))]
+        template_id: String,
+        
+        /// ID of the copy
+        new_id: String,
+        
+        /// Template path in repository for the copy
+        #[arg(short, long)]
+        path: Option<String>,
+        
+        /// Branch for the copy, empty to use the repository branch
+        #[arg(short, long)]
+        branch: Option<String>,
+        
+        /// Also copy the cached template files when path and branch are unchanged
+        #[arg(long)]
+        with_cache: bool,
     },
     /// Remove a template
     Remove {
         /// Template ID
+        #[arg(add = ArgValueCandidates::new(template_candidates))]
         template_id: String,
     },
+    /// Check a template directory or cached template for broken placeholders, undeclared
+    /// variables, invalid manifest fields, absolute paths and oversized files
+    Lint {
+        /// Template directory or template ID
+        #[arg(default_value = ".", add = ArgValueCandidates::new(template_candidates))]
+        target: String,
+        
+        /// Largest allowed file, e.g. "512KiB" or "2MB" (default 1MiB)
+        #[arg(long)]
+        max_file_size: Option<String>,
+        
+        /// Print the findings as JSON
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Lint a local template directory and publish it to a repository or registry
+    Publish {
+        /// Template directory
+        #[arg(default_value = ".")]
+        dir: String,
+        
+        /// Push to a new branch of this configured repository for a pull request
+        #[arg(short, long, conflicts_with = "registry_url", add = ArgValueCandidates::new(repo_candidates))]
+        repo: Option<String>,
+        
+        /// Template path inside the repository (defaults to the directory name)
+        #[arg(short, long, requires = "repo")]
+        path: Option<String>,
+        
+        /// Branch to push (defaults to publish/<path>-<timestamp>)
+        #[arg(short, long, requires = "repo")]
+        branch: Option<String>,
+        
+        /// Upload a .tar.gz of the template to this registry endpoint
+        #[arg(long)]
+        registry_url: Option<String>,
+        
+        /// Bearer token for the registry (defaults to MAMMOTH_REGISTRY_TOKEN)
+        #[arg(long, requires = "registry_url")]
+        token: Option<String>,
+        
+        /// Largest allowed file for the lint, e.g. "2MB" (default 1MiB)
+        #[arg(long)]
+        max_file_size: Option<String>,
+        
+        /// Only run the lint, do not push or upload
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -128,7 +653,7 @@ pub enum RepoCommands {
         /// Repository name
         repo_name: String,
         
-        /// Repository URL
+        /// Repository URL, a `.tar.gz`/`.zip` archive URL, or a local directory (`file:///path` or an absolute path)
         #[arg(short, long)]
         url: String,
         
@@ -140,17 +665,110 @@ pub enum RepoCommands {
         #[arg(long)]
         username: Option<String>,
         
-        /// Authentication token for private repository
-        #[arg(long)]
+        /// Authentication token for private repository (HTTPS URLs)
+        #[arg(long, visible_alias = "token")]
         auth_token: Option<String>,
+        
+        /// Private key for SSH URLs, instead of the SSH agent and default keys
+        #[arg(long)]
+        ssh_key: Option<String>,
+        
+        /// Directory prepended to the path of every template in the repository
+        #[arg(long)]
+        path_prefix: Option<String>,
+    },
+    /// Update fields of an existing repository
+    Update {
+        /// Repository name
+        #[arg(add = ArgValueCandidates::new(repo_candidates))]
+        repo_name: String,
+        
+        /// Repository URL
+        #[arg(short, long)]
+        url: Option<String>,
+        
+        /// Branch
+        #[arg(short, long)]
+        branch: Option<String>,
+        
+        /// Directory prepended to template paths, empty to clear
+        #[arg(long)]
+        path_prefix: Option<String>,
+        
+        /// Rough clone size checked against free disk space, e.g. "300MB", empty to clear
+        #[arg(long)]
+        size_hint: Option<String>,
+        
+        /// Hosting service for self-hosted servers (github, gitlab, bitbucket, gitea, azure), empty to detect from the URL
+        #[arg(long)]
+        provider: Option<String>,
+        
+        /// Private key for SSH URLs, empty to use the SSH agent and default keys again
+        #[arg(long)]
+        ssh_key: Option<String>,
+    },
+    /// Store the token of a private repository in the OS keyring instead of the config file
+    Auth {
+        /// Repository name
+        #[arg(add = ArgValueCandidates::new(repo_candidates))]
+        repo_name: String,
+        
+        /// Username sent with the token, empty for the provider default
+        #[arg(long)]
+        username: Option<String>,
+        
+        /// Read the token from stdin instead of prompting, e.g. in scripts
+        #[arg(long, conflicts_with = "remove")]
+        token_stdin: bool,
+        
+        /// Remove the stored token from the keyring
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Hide and block all templates of a repository, e.g. during a migration
+    Disable {
+        /// Repository name
+        #[arg(add = ArgValueCandidates::new(repo_candidates))]
+        repo_name: String,
+    },
+    /// Re-enable a disabled repository
+    Enable {
+        /// Repository name
+        #[arg(add = ArgValueCandidates::new(repo_candidates))]
+        repo_name: String,
     },
     /// Remove a repository
     Remove {
         /// Repository name
+        #[arg(add = ArgValueCandidates::new(repo_candidates))]
         repo_name: String,
     },
     /// List all repositories
-    List,
+    List {
+        /// Print the repositories as a JSON array, tokens redacted
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Update the cached templates of a repository
+    Sync {
+        /// Repository name
+        #[arg(add = ArgValueCandidates::new(repo_candidates))]
+        repo_name: String,
+        
+        /// Also download templates that are not cached yet
+        #[arg(long)]
+        download: bool,
+    },
+    /// Check that repositories are reachable, accept their credentials and have their branch
+    Verify {
+        /// Repository name
+        #[arg(required_unless_present = "all", add = ArgValueCandidates::new(repo_candidates))]
+        repo_name: Option<String>,
+        
+        /// Check every configured repository concurrently
+        #[arg(long, conflicts_with = "repo_name")]
+        all: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -164,10 +782,26 @@ pub enum ConfigCommands {
         /// Include cache information
         #[arg(short, long)]
         include_cache: bool,
+        
+        /// Export authentication tokens in clear text instead of redacting them
+        #[arg(long)]
+        reveal_secrets: bool,
+        
+        /// Export only "repos" or "templates"
+        #[arg(long)]
+        only: Option<String>,
+        
+        /// Export only this repository and its templates
+        #[arg(long, add = ArgValueCandidates::new(repo_candidates))]
+        repo: Option<String>,
+        
+        /// Export only templates with this tag and the repositories they use
+        #[arg(long)]
+        tag: Option<String>,
     },
     /// Import configuration from file
     Import {
-        /// Input file path
+        /// Input file path or http(s) URL
         #[arg(short, long)]
         file: String,
         
@@ -178,10 +812,77 @@ pub enum ConfigCommands {
         /// Skip validation
         #[arg(short, long)]
         skip_validation: bool,
+        
+        /// Expected SHA-256 of the file, the import is refused if it does not match
+        #[arg(long)]
+        sha256: Option<String>,
     },
     /// Validate configuration file
     Validate {
         /// Configuration file path
         file: String,
     },
-} 
\ No newline at end of file
+    /// Convert a JSON config file to TOML next to it, or bring your own config to the current version
+    Migrate {
+        /// JSON config file, e.g. a shared `extends` layer or an export
+        file: Option<String>,
+    },
+    /// Change a setting, e.g. `config set package_manager pnpm`
+    Set {
+        /// git_path, git_backend, picker_order, auto_download or package_manager
+        key: String,
+        
+        /// New value, empty to restore the default
+        value: String,
+    },
+} 
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Show disk usage, fetch time and last use of every cached template and clone
+    Stats {
+        /// Print the entries as a JSON array
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Prune entries of removed templates, unused entries and the least recently used ones
+    Gc {
+        /// Remove least recently used entries until the cache is at most this big, e.g. "2GB"
+        #[arg(long)]
+        max_size: Option<String>,
+        
+        /// Remove entries not used or refreshed for this long, e.g. "90d"
+        #[arg(long)]
+        unused_for: Option<String>,
+        
+        /// Only list what would be removed
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// Move removed entries to the OS trash (default on interactive runs)
+        #[arg(long, conflicts_with = "permanent")]
+        trash: bool,
+        
+        /// Delete removed entries permanently instead of moving them to the trash
+        #[arg(long)]
+        permanent: bool,
+    },
+    /// Evict the cache of one template or repository, keeping everything else
+    Clean {
+        /// Template ID, or repo/id
+        #[arg(required_unless_present = "repo", add = ArgValueCandidates::new(template_candidates))]
+        template_id: Option<String>,
+        
+        /// Evict every template of this repository and its clone
+        #[arg(short, long, conflicts_with = "template_id", add = ArgValueCandidates::new(repo_candidates))]
+        repo: Option<String>,
+        
+        /// Move removed entries to the OS trash (default on interactive runs)
+        #[arg(long, conflicts_with = "permanent")]
+        trash: bool,
+        
+        /// Delete removed entries permanently instead of moving them to the trash
+        #[arg(long)]
+        permanent: bool,
+    },
+}
@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    ArrayExpression, BindingPatternKind, Declaration, Expression, ObjectExpression,
+    ObjectPropertyKind, Statement,
+};
+use oxc_parser::Parser;
+use oxc_span::{GetSpan, SourceType};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Structured edit of a JS/TS source file declared in a template manifest, e.g.
+/// registering a Vite plugin:
+/// `{"file": "vite.config.ts", "imports": ["import x from 'x'"], "array": "plugins", "elements": ["x()"]}`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CodePatch {
+    /// File relative to the project root
+    pub file: String,
+    /// Import statements added after the existing imports, skipped if already present
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<String>,
+    /// Array to extend: a dotted property path in the exported config object
+    /// (e.g. "plugins" or "build.rollupOptions.external"), or a top-level variable such as "routes"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub array: Option<String>,
+    /// Source of the elements appended to `array`, skipped if already present
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub elements: Vec<String>,
+}
+
+/// Text inserted at a byte offset of the original source
+struct Insertion {
+    offset: usize,
+    text: String,
+}
+
+/// Apply a code patch to a file in `project_path`. Insert positions come from the parsed
+/// AST, so the patch keeps working when the surrounding code is reformatted or extended.
+pub fn apply_code_patch(project_path: &Path, patch: &CodePatch) -> Result<()> {
+    let path = project_path.join(&patch.file);
+    let source = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {} for patching", patch.file))?;
+    
+    let allocator = Allocator::default();
+    let source_type = SourceType::from_path(&path)
+        .map_err(|_| anyhow::anyhow!("Unsupported file type for patching: {}", patch.file))?;
+    let parsed = Parser::new(&allocator, &source, source_type).parse();
+    if !parsed.errors.is_empty() {
+        anyhow::bail!(
+            "Failed to parse {}: {}",
+            patch.file,
+            parsed
+                .errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+    let body = &parsed.program.body;
+    
+    let mut insertions = Vec::new();
+    
+    // 在最后一个 import 之后插入新的 import
+    let missing_imports: Vec<&String> = patch
+        .imports
+        .iter()
+        .filter(|import| !source.contains(import.trim_end_matches(';')))
+        .collect();
+    if !missing_imports.is_empty() {
+        let last_import_end = body
+            .iter()
+            .filter(|stmt| matches!(stmt, Statement::ImportDeclaration(_)))
+            .map(|stmt| stmt.span().end as usize)
+            .max();
+        let imports = missing_imports
+            .iter()
+            .map(|import| import.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        insertions.push(match last_import_end {
+            Some(offset) => Insertion {
+                offset,
+                text: format!("\n{}", imports),
+            },
+            None => Insertion {
+                offset: 0,
+                text: format!("{}\n", imports),
+            },
+        });
+    }
+    
+    if let Some(array_path) = &patch.array {
+        let array = find_array(body, array_path).ok_or_else(|| {
+            anyhow::anyhow!("Array '{}' not found in {}", array_path, patch.file)
+        })?;
+        if let Some(insertion) = append_elements(&source, array, &patch.elements) {
+            insertions.push(insertion);
+        }
+    }
+    
+    if insertions.is_empty() {
+        return Ok(());
+    }
+    
+    // 从后往前插入，保证前面的偏移量仍然有效
+    insertions.sort_by(|a, b| b.offset.cmp(&a.offset));
+    let mut patched = source.clone();
+    for insertion in insertions {
+        patched.insert_str(insertion.offset, &insertion.text);
+    }
+    fs::write(&path, patched).with_context(|| format!("Failed to write {}", patch.file))?;
+    
+    Ok(())
+}
+
+/// Insertion appending `elements` to `array`, following its single- or multi-line layout
+fn append_elements(source: &str, array: &ArrayExpression, elements: &[String]) -> Option<Insertion> {
+    let array_source = &source[array.span.start as usize..array.span.end as usize];
+    // 按元素源码比较，`react()` 不能因为已有 `preact()` 而被跳过
+    let existing: Vec<&str> = array
+        .elements
+        .iter()
+        .map(|element| source[element.span().start as usize..element.span().end as usize].trim())
+        .collect();
+    let missing: Vec<&str> = elements
+        .iter()
+        .map(|element| element.as_str())
+        .filter(|element| !existing.contains(&element.trim()))
+        .collect();
+    if missing.is_empty() {
+        return None;
+    }
+    
+    let last = match array.elements.last() {
+        Some(last) => last.span().end as usize,
+        None => {
+            return Some(Insertion {
+                offset: array.span.start as usize + 1,
+                text: missing.join(", "),
+            })
+        }
+    };
+    
+    let text = if array_source.contains('\n') {
+        // 多行数组沿用最后一个元素的缩进
+        let line_start = source[..last].rfind('\n').map_or(0, |i| i + 1);
+        let indent: String = source[line_start..]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        missing
+            .iter()
+            .map(|element| format!(",\n{}{}", indent, element))
+            .collect()
+    } else {
+        missing.iter().map(|element| format!(", {}", element)).collect()
+    };
+    Some(Insertion { offset: last, text })
+}
+
+/// Array at `array_path` in the exported config object, or a top-level array variable
+fn find_array<'a>(body: &'a [Statement<'a>], array_path: &str) -> Option<&'a ArrayExpression<'a>> {
+    for stmt in body {
+        let found = match stmt {
+            Statement::ExportDefaultDeclaration(decl) => decl
+                .declaration
+                .as_expression()
+                .and_then(config_object)
+                .and_then(|object| array_in_object(object, array_path)),
+            // CommonJS: module.exports = { ... }
+            Statement::ExpressionStatement(stmt) => match &stmt.expression {
+                Expression::AssignmentExpression(assign) => config_object(&assign.right)
+                    .and_then(|object| array_in_object(object, array_path)),
+                _ => None,
+            },
+            Statement::VariableDeclaration(decl) => array_variable(decl, array_path),
+            Statement::ExportNamedDeclaration(decl) => match &decl.declaration {
+                Some(Declaration::VariableDeclaration(decl)) => array_variable(decl, array_path),
+                _ => None,
+            },
+            _ => None,
+        };
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+fn array_variable<'a>(
+    decl: &'a oxc_ast::ast::VariableDeclaration<'a>,
+    name: &str,
+) -> Option<&'a ArrayExpression<'a>> {
+    decl.declarations.iter().find_map(|declarator| {
+        let is_named = matches!(
+            &declarator.id.kind,
+            BindingPatternKind::BindingIdentifier(ident) if ident.name == name
+        );
+        match (&declarator.init, is_named) {
+            (Some(init), true) => as_array(init),
+            _ => None,
+        }
+    })
+}
+
+/// Object literal of a config export: `{...}`, `defineConfig({...})` or `{...} satisfies Config`
+fn config_object<'a>(expression: &'a Expression<'a>) -> Option<&'a ObjectExpression<'a>> {
+    match expression {
+        Expression::ObjectExpression(object) => Some(object),
+        Expression::CallExpression(call) => call
+            .arguments
+            .first()
+            .and_then(|argument| argument.as_expression())
+            .and_then(config_object),
+        Expression::TSSatisfiesExpression(e) => config_object(&e.expression),
+        Expression::TSAsExpression(e) => config_object(&e.expression),
+        Expression::ParenthesizedExpression(e) => config_object(&e.expression),
+        _ => None,
+    }
+}
+
+fn as_array<'a>(expression: &'a Expression<'a>) -> Option<&'a ArrayExpression<'a>> {
+    match expression {
+        Expression::ArrayExpression(array) => Some(array),
+        Expression::TSSatisfiesExpression(e) => as_array(&e.expression),
+        Expression::TSAsExpression(e) => as_array(&e.expression),
+        _ => None,
+    }
+}
+
+fn array_in_object<'a>(object: &'a ObjectExpression<'a>, path: &str) -> Option<&'a ArrayExpression<'a>> {
+    let (key, rest) = match path.split_once('.') {
+        Some((key, rest)) => (key, Some(rest)),
+        None => (path, None),
+    };
+    
+    let value = object.properties.iter().find_map(|property| match property {
+        ObjectPropertyKind::ObjectProperty(property)
+            if property.key.static_name().is_some_and(|name| name == key) =>
+        {
+            Some(&property.value)
+        }
+        _ => None,
+    })?;
+    
+    match rest {
+        Some(rest) => config_object(value).and_then(|object| array_in_object(object, rest)),
+        None => as_array(value),
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::utils::interpolate;
+
+/// Community files that can be generated: (name, path in the project, content)
+pub const COMMUNITY_FILES: [(&str, &str, &str); 3] = [
+    ("codeowners", ".github/CODEOWNERS", CODEOWNERS),
+    ("contributing", "CONTRIBUTING.md", CONTRIBUTING),
+    ("changelog", "CHANGELOG.md", CHANGELOG),
+];
+
+const CODEOWNERS: &str = "# Default owners of everything in the repository\n* {{owner}}\n";
+
+const CONTRIBUTING: &str = r#"# Contributing to {{name}}
+
+Thanks for taking the time to contribute!
+
+## Development
+
+1. Fork the repository and create a branch from `main`.
+2. Install dependencies with `npm install`.
+3. Start the dev server with `npm run dev`.
+
+## Pull Requests
+
+- Keep changes focused and explain the motivation in the description.
+- Add an entry to `CHANGELOG.md` under "Unreleased".
+
+Questions? Reach out to {{owner}}.
+"#;
+
+const CHANGELOG: &str = r#"# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+
+### Added
+
+- Project scaffolded from the {{template}} template ({{date}}).
+"#;
+
+/// Parse a comma-separated list of community file names, "all" selects every file
+pub fn parse_community_files(list: &str) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for name in list.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()) {
+        if name == "all" {
+            return Ok(COMMUNITY_FILES.iter().map(|(name, _, _)| name.to_string()).collect());
+        }
+        if !COMMUNITY_FILES.iter().any(|(known, _, _)| *known == name) {
+            let known: Vec<&str> = COMMUNITY_FILES.iter().map(|(name, _, _)| *name).collect();
+            anyhow::bail!(
+                "Unknown community file '{}', expected one of: {}, all",
+                name,
+                known.join(", ")
+            );
+        }
+        if !files.contains(&name) {
+            files.push(name);
+        }
+    }
+    Ok(files)
+}
+
+/// Write the selected community files into the project. Files the template already
+/// ships are left alone. `{{owner}}` is the organization, falling back to the author.
+/// Returns the paths written.
+pub fn write_community_files(
+    project_path: &Path,
+    files: &[String],
+    vars: &HashMap<String, String>,
+    org: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut vars = vars.clone();
+    let author = vars.get("author").cloned().unwrap_or_default();
+    vars.insert("owner".to_string(), org.unwrap_or(&author).to_string());
+    vars.insert("org".to_string(), org.unwrap_or_default().to_string());
+    vars.insert(
+        "date".to_string(),
+        chrono::Local::now().format("%Y-%m-%d").to_string(),
+    );
+    
+    let mut written = Vec::new();
+    for (name, path, content) in COMMUNITY_FILES {
+        if !files.iter().any(|f| f == name) {
+            continue;
+        }
+        let target = project_path.join(path);
+        if target.exists() {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, interpolate(content, &vars))
+            .with_context(|| format!("Failed to write {}", path))?;
+        written.push(path.to_string());
+    }
+    
+    Ok(written)
+}
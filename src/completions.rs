@@ -0,0 +1,61 @@
+use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{Bash, Elvish, EnvCompleter, Fish, Powershell, Zsh};
+use clap_complete::Shell;
+use std::io;
+
+use crate::cli::Cli;
+use crate::manager::TemplateManager;
+
+const BIN_NAME: &str = "mammoth-cli";
+
+/// Environment variable that switches the binary into completion mode
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Print the completion script for `shell`. The static script knows commands and flags;
+/// the dynamic one calls back into mammoth-cli on every TAB and also completes template
+/// IDs and repository names from the config.
+pub fn print_completions(shell: Shell, dynamic: bool) -> Result<()> {
+    let mut stdout = io::stdout();
+    if !dynamic {
+        clap_complete::generate(shell, &mut Cli::command(), BIN_NAME, &mut stdout);
+        return Ok(());
+    }
+    
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+        Shell::PowerShell => &Powershell,
+        Shell::Elvish => &Elvish,
+        _ => anyhow::bail!("Dynamic completions are not supported for {}", shell),
+    };
+    completer.write_registration(COMPLETE_VAR, BIN_NAME, BIN_NAME, BIN_NAME, &mut stdout)?;
+    Ok(())
+}
+
+/// Template IDs with their names, for dynamic completion
+pub fn template_candidates() -> Vec<CompletionCandidate> {
+    // 补全时不能报错，配置读取失败就不提供候选项
+    let Ok(config) = TemplateManager::load_local_config() else {
+        return Vec::new();
+    };
+    config
+        .templates
+        .iter()
+        .map(|t| CompletionCandidate::new(&t.id).help(Some(t.name.localized().to_string().into())))
+        .collect()
+}
+
+/// Repository names with their URLs, for dynamic completion
+pub fn repo_candidates() -> Vec<CompletionCandidate> {
+    let Ok(config) = TemplateManager::load_local_config() else {
+        return Vec::new();
+    };
+    config
+        .repos
+        .iter()
+        .map(|r| CompletionCandidate::new(&r.name).help(Some(r.url.clone().into())))
+        .collect()
+}
@@ -1,6 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::ui::current_locale;
+use crate::git::GitBackend;
+use crate::package_manager::PackageManager;
+use crate::utils::parse_duration;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Repo {
     pub name: String,
     pub url: String,
@@ -11,30 +18,1028 @@ pub struct Repo {
     /// Optional username for private repositories
     #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
+    /// Key of the OS keyring entry holding the token, set by `repo auth`; used when
+    /// `auth_token` is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyring: Option<String>,
+    /// Private key used for SSH URLs instead of the SSH agent and default keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<String>,
+    /// Directory prepended to the path of every template in this repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+    /// Templates from a disabled repository are hidden and blocked
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub disabled: bool,
+    /// Rough clone size, e.g. "300MB", checked against free disk space before cloning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_hint: Option<String>,
+    /// Hosting service ("github", "gitlab", "bitbucket", "gitea", "azure"), detected
+    /// from the URL when unset; needed for self-hosted servers on custom domains
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Credentials used by every repository on a git host that has none of its own
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct HostAuth {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_token: Option<String>,
+}
+
+impl HostAuth {
+    pub fn has_redacted_token(&self) -> bool {
+        self.auth_token.as_deref() == Some(REDACTED_SECRET)
+    }
+}
+
+/// Text given either as a plain string or as a map of locale → text,
+/// e.g. `{"en": "Admin starter", "zh-CN": "后台模板"}`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum LocalizedText {
+    Plain(String),
+    Localized(BTreeMap<String, String>),
+}
+
+/// Locale key used when no entry matches the CLI locale
+pub const DEFAULT_LOCALE: &str = "default";
+
+impl LocalizedText {
+    /// Entry for the CLI locale: exact match, then language ("zh" for "zh-CN"),
+    /// then "default", then "en", then the first entry
+    pub fn localized(&self) -> &str {
+        match self {
+            LocalizedText::Plain(text) => text,
+            LocalizedText::Localized(entries) => {
+                let locale = current_locale();
+                let language = locale.split('-').next().unwrap_or_default();
+                [locale, language, DEFAULT_LOCALE, "en"]
+                    .iter()
+                    .find_map(|key| entries.get(*key))
+                    .or_else(|| entries.values().next())
+                    .map(String::as_str)
+                    .unwrap_or_default()
+            }
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.localized().is_empty()
+    }
+}
+
+impl fmt::Display for LocalizedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.localized())
+    }
+}
+
+impl From<String> for LocalizedText {
+    fn from(text: String) -> Self {
+        LocalizedText::Plain(text)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Template {
     pub id: String,
-    pub name: String,
+    pub name: LocalizedText,
     pub repo: String,
     pub path: String,
-    pub description: String,
+    pub description: LocalizedText,
     pub language: String,
     pub tags: Vec<String>,
+    /// Branch overriding the repository branch for this template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Tag or full commit SHA the template is pinned to, checked out instead of the branch tip
+    #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    /// IDs of templates/fragments applied before this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+    /// Group shown in listings and the picker, e.g. "Apps" or "Libraries"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Display color, a color name or "#rrggbb"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Emoji/icon shown before the template ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Hidden from the picker and blocked in `new`, config and cache are kept
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub disabled: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Template {
+    /// Branch to download the template from
+    pub fn effective_branch<'a>(&'a self, repo: &'a Repo) -> &'a str {
+        self.branch.as_deref().unwrap_or(&repo.branch)
+    }
+    
+    /// Revision to download: the pinned ref, or else the branch
+    pub fn effective_ref<'a>(&'a self, repo: &'a Repo) -> &'a str {
+        self.git_ref.as_deref().unwrap_or_else(|| self.effective_branch(repo))
+    }
+    
+    /// Template path inside the repository, including the repository `path_prefix`
+    pub fn repo_path(&self, repo: &Repo) -> String {
+        match repo.path_prefix.as_deref().map(|p| p.trim_matches('/')) {
+            Some(prefix) if !prefix.is_empty() => {
+                format!("{}/{}", prefix, self.path.trim_start_matches('/'))
+            }
+            _ => self.path.clone(),
+        }
+    }
+    
+    /// ID qualified with the repository, e.g. "aio-templates/admin"
+    pub fn qualified_id(&self) -> String {
+        format!("{}/{}", self.repo, self.id)
+    }
+    
+    /// Whether `reference`, a bare "id" or a qualified "repo/id", addresses this template
+    pub fn matches_reference(&self, reference: &str) -> bool {
+        match reference.split_once('/') {
+            Some((repo, id)) => self.repo == repo && self.id == id,
+            None => self.id == reference,
+        }
+    }
+    
+    pub fn matches_language(&self, language: &str) -> bool {
+        canonical_language(&self.language) == canonical_language(language)
+    }
+    
+    /// Case-insensitive substring match on the ID, name and description in every locale,
+    /// and the tags
+    pub fn matches_query(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        let texts = |text: &LocalizedText| -> Vec<String> {
+            match text {
+                LocalizedText::Plain(text) => vec![text.clone()],
+                LocalizedText::Localized(entries) => entries.values().cloned().collect(),
+            }
+        };
+        std::iter::once(self.id.clone())
+            .chain(texts(&self.name))
+            .chain(texts(&self.description))
+            .chain(self.tags.iter().cloned())
+            .any(|text| text.to_lowercase().contains(&query))
+    }
+}
+
+/// Header used for templates without a category
+pub const UNCATEGORIZED: &str = "Other";
+
+/// Group templates by category, keeping config order and putting uncategorized ones last
+pub fn group_by_category<'a>(templates: &[&'a Template]) -> Vec<(String, Vec<&'a Template>)> {
+    let mut groups: Vec<(String, Vec<&'a Template>)> = Vec::new();
+    let mut uncategorized = Vec::new();
+    for &template in templates {
+        match &template.category {
+            Some(category) => match groups.iter_mut().find(|(name, _)| name == category) {
+                Some((_, members)) => members.push(template),
+                None => groups.push((category.clone(), vec![template])),
+            },
+            None => uncategorized.push(template),
+        }
+    }
+    if !uncategorized.is_empty() {
+        groups.push((UNCATEGORIZED.to_string(), uncategorized));
+    }
+    groups
+}
+
+/// Languages accepted without listing them under `languages` in the config
+pub const KNOWN_LANGUAGES: [&str; 12] = [
+    "vue",
+    "react",
+    "svelte",
+    "angular",
+    "solid",
+    "javascript",
+    "typescript",
+    "node",
+    "rust",
+    "go",
+    "python",
+    "other",
+];
+
+/// Common spellings and their canonical language
+const LANGUAGE_ALIASES: [(&str, &str); 14] = [
+    ("vue2", "vue"),
+    ("vue3", "vue"),
+    ("vuejs", "vue"),
+    ("vue.js", "vue"),
+    ("reactjs", "react"),
+    ("react.js", "react"),
+    ("sveltekit", "svelte"),
+    ("solidjs", "solid"),
+    ("js", "javascript"),
+    ("ts", "typescript"),
+    ("nodejs", "node"),
+    ("node.js", "node"),
+    ("golang", "go"),
+    ("py", "python"),
+];
+
+/// Lowercased language with aliases resolved, without checking that it is known
+pub fn canonical_language(language: &str) -> String {
+    let language = language.trim().to_lowercase();
+    LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == language)
+        .map(|(_, canonical)| canonical.to_string())
+        .unwrap_or(language)
+}
+
+/// Canonical form of `language`, failing if it is neither built in nor in `custom`
+pub fn normalize_language(language: &str, custom: &[String]) -> anyhow::Result<String> {
+    let canonical = canonical_language(language);
+    let known = KNOWN_LANGUAGES.contains(&canonical.as_str())
+        || custom.iter().any(|c| canonical_language(c) == canonical);
+    if !known {
+        anyhow::bail!(
+            "Unknown language '{}'. Known languages: {}. Add custom ones under \"languages\" in the config",
+            language,
+            KNOWN_LANGUAGES
+                .iter()
+                .map(|l| l.to_string())
+                .chain(custom.iter().cloned())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(canonical)
+}
+
+/// Field changes for `repo update`, `None` leaves a field untouched and an empty
+/// string clears an optional one
+#[derive(Debug, Clone, Default)]
+pub struct RepoUpdate {
+    pub url: Option<String>,
+    pub branch: Option<String>,
+    pub path_prefix: Option<String>,
+    pub size_hint: Option<String>,
+    pub provider: Option<String>,
+    pub ssh_key: Option<String>,
+}
+
+/// Field changes for `template update`, `None` leaves a field untouched
+#[derive(Debug, Clone, Default)]
+pub struct TemplateUpdate {
+    pub name: Option<String>,
+    pub repo: Option<String>,
+    pub path: Option<String>,
+    pub description: Option<String>,
+    pub language: Option<String>,
+    pub tags: Option<Vec<String>>,
+    /// An empty branch resets the template to the repository branch
+    pub branch: Option<String>,
+    /// An empty ref unpins the template
+    pub git_ref: Option<String>,
+    pub dependencies: Option<Vec<String>>,
+    /// An empty category removes the template from its group
+    pub category: Option<String>,
+    /// Empty color or icon falls back to the template manifest
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CacheSettings {
+    /// Freshness window for cached templates, e.g. "12h" or "7d"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+    /// Freshness window in whole days, used when `max_age` is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    /// Re-fetch templates older than `max_age` instead of only warning
+    #[serde(default = "default_true")]
+    pub auto_refresh: bool,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            max_age_days: None,
+            auto_refresh: true,
+        }
+    }
+}
+
+impl CacheSettings {
+    pub fn is_default(&self) -> bool {
+        !self.has_max_age() && self.auto_refresh
+    }
+    
+    pub fn has_max_age(&self) -> bool {
+        self.max_age.is_some() || self.max_age_days.is_some()
+    }
+    
+    /// Freshness window from `max_age`, else `max_age_days`
+    pub fn max_age(&self) -> anyhow::Result<Option<std::time::Duration>> {
+        match (&self.max_age, self.max_age_days) {
+            (Some(max_age), _) => parse_duration(max_age).map(Some),
+            (None, Some(days)) => days
+                .checked_mul(24 * 60 * 60)
+                .map(|seconds| Some(std::time::Duration::from_secs(seconds)))
+                .ok_or_else(|| anyhow::anyhow!("cache.max_age_days {} is too large", days)),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// Output colors, a preset with optional per-role overrides
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ThemeSettings {
+    /// "default", "light", "high-contrast" or "monochrome"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<String>,
+}
+
+impl ThemeSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Runtime settings of the CLI itself
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Settings {
+    /// Git executable to run instead of `git` from PATH, overridden by `MAMMOTH_GIT`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_path: Option<String>,
+    /// "git" (default), "gix" or "git2" to fetch templates without a git executable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_backend: Option<String>,
+    /// Order of the `new` template picker: "usage" (default, most used first) or "alphabetical"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub picker_order: Option<String>,
+    /// Download templates into the cache as soon as they are added
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub auto_download: bool,
+    /// "npm", "pnpm", "yarn" or "bun": used for installing and next steps instead of
+    /// the template's own package manager
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_manager: Option<String>,
+}
+
+/// Keys accepted by `config set`
+pub const SETTING_KEYS: [&str; 5] = ["git_path", "git_backend", "picker_order", "auto_download", "package_manager"];
+
+impl Settings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+    
+    /// Set one setting from its text value, an empty value restores the default
+    pub fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        let key = key.strip_prefix("settings.").unwrap_or(key);
+        let text = (!value.is_empty()).then(|| value.to_string());
+        match key {
+            "git_path" => self.git_path = text,
+            "git_backend" => {
+                if let Some(backend) = &text {
+                    backend.parse::<GitBackend>()?;
+                }
+                self.git_backend = text;
+            }
+            "picker_order" => {
+                if let Some(order) = &text {
+                    validate_picker_order(order)?;
+                }
+                self.picker_order = text;
+            }
+            "auto_download" => {
+                self.auto_download = match value {
+                    "" => false,
+                    _ => value
+                        .parse()
+                        .map_err(|_| anyhow::anyhow!("Invalid value for auto_download: {}. Use 'true' or 'false'", value))?,
+                };
+            }
+            "package_manager" => {
+                self.package_manager = match text {
+                    Some(name) => Some(name.parse::<PackageManager>()?.to_string()),
+                    None => None,
+                };
+            }
+            _ => anyhow::bail!("Unknown setting: {}. Use one of: {}", key, SETTING_KEYS.join(", ")),
+        }
+        Ok(())
+    }
+    
+    /// The preferred package manager, if one is set
+    pub fn package_manager(&self) -> anyhow::Result<Option<PackageManager>> {
+        self.package_manager.as_deref().map(str::parse).transpose()
+    }
+}
+
+pub fn validate_picker_order(order: &str) -> anyhow::Result<()> {
+    if !matches!(order, "usage" | "alphabetical") {
+        anyhow::bail!("Invalid picker order: {}. Use 'usage' or 'alphabetical'", order);
+    }
+    Ok(())
+}
+
+/// Defaults applied to every generated project
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ProjectDefaults {
+    /// Default answer of the author prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Default answer of the author email prompt, git's `user.email` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Author homepage written to the package.json `author` object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Organization or team owning generated projects, e.g. "@acme/frontend"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+    /// Community files added to every project: "codeowners", "contributing", "changelog"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub community_files: Vec<String>,
+}
+
+impl ProjectDefaults {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+pub fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Remote index of repositories and templates, e.g. an organization's official catalog
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Registry {
+    pub name: String,
+    /// URL or path of a JSON or TOML file with `repos` and `templates`
+    pub url: String,
+    /// Unix time of the last `registry sync`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced_at: Option<u64>,
+    /// Repositories the last sync added, removed when the index drops them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repos: Vec<String>,
+    /// Qualified IDs of the templates the last sync added
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub templates: Vec<String>,
+}
+
+/// Format version written to `config_version`. Version 1 is the JSON `templates.json`
+/// without the field, version 2 the TOML `config.toml`.
+pub const CONFIG_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Config {
+    /// Format version of the file, see `CONFIG_VERSION`
+    #[serde(default)]
+    pub config_version: u32,
+    /// Config files or URLs layered underneath this config, later entries win
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
     pub repos: Vec<Repo>,
     pub templates: Vec<Template>,
+    /// Languages accepted in addition to `KNOWN_LANGUAGES`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    #[serde(default, skip_serializing_if = "CacheSettings::is_default")]
+    pub cache: CacheSettings,
+    #[serde(default, skip_serializing_if = "ThemeSettings::is_default")]
+    pub theme: ThemeSettings,
+    /// Time prompts wait before taking their default answer, e.g. "30s"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_timeout: Option<String>,
+    #[serde(default, skip_serializing_if = "ProjectDefaults::is_default")]
+    pub defaults: ProjectDefaults,
+    #[serde(default, skip_serializing_if = "Settings::is_default")]
+    pub settings: Settings,
+    /// Credentials per git host, e.g. "gitlab.company.com"
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hosts: BTreeMap<String, HostAuth>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<Registry>,
+}
+
+/// Subset of the config selected by `config export`
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    /// Export only "repos" or "templates"
+    pub only: Option<String>,
+    /// Keep only this repository and its templates
+    pub repo: Option<String>,
+    /// Keep only templates with this tag and the repositories they use
+    pub tag: Option<String>,
+}
+
+impl ExportFilter {
+    pub fn apply(&self, config: &Config) -> anyhow::Result<Config> {
+        let mut filtered = config.clone();
+        
+        if let Some(repo) = &self.repo {
+            filtered.repos.retain(|r| &r.name == repo);
+            filtered.templates.retain(|t| &t.repo == repo);
+        }
+        if let Some(tag) = &self.tag {
+            filtered.templates.retain(|t| t.tags.contains(tag));
+            let used: Vec<String> = filtered.templates.iter().map(|t| t.repo.clone()).collect();
+            filtered.repos.retain(|r| used.contains(&r.name));
+        }
+        
+        match self.only.as_deref().map(str::to_lowercase).as_deref() {
+            None => {}
+            Some("repos") => filtered.templates.clear(),
+            Some("templates") => filtered.repos.clear(),
+            Some(other) => anyhow::bail!("Invalid export filter: {}. Use 'repos' or 'templates'", other),
+        }
+        
+        Ok(filtered)
+    }
+}
+
+/// Templates shown by `template list` and `template search`; all given filters must match
+#[derive(Debug, Clone, Default)]
+pub struct TemplateFilter {
+    /// Text searched in ID, name, description and tags
+    pub query: Option<String>,
+    pub tag: Option<String>,
+    pub language: Option<String>,
+    pub repo: Option<String>,
+}
+
+impl TemplateFilter {
+    pub fn matches(&self, template: &Template) -> bool {
+        self.query.as_deref().is_none_or(|q| template.matches_query(q))
+            && self
+                .tag
+                .as_deref()
+                .is_none_or(|tag| template.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            && self.language.as_deref().is_none_or(|l| template.matches_language(l))
+            && self.repo.as_deref().is_none_or(|repo| template.repo == repo)
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.query.is_none() && self.tag.is_none() && self.language.is_none() && self.repo.is_none()
+    }
+    
+    /// The active filters, e.g. `"react" tag=ui` for the empty-result message
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(query) = &self.query {
+            parts.push(format!("\"{}\"", query));
+        }
+        for (name, value) in [("tag", &self.tag), ("language", &self.language), ("repo", &self.repo)] {
+            if let Some(value) = value {
+                parts.push(format!("{}={}", name, value));
+            }
+        }
+        parts.join(" ")
+    }
+}
+
+/// Placeholder written instead of credentials in printed or exported config
+pub const REDACTED_SECRET: &str = "<redacted>";
+
+impl Repo {
+    pub fn has_redacted_token(&self) -> bool {
+        self.auth_token.as_deref() == Some(REDACTED_SECRET)
+    }
+}
+
+impl Config {
+    /// Copy of the config with credential fields replaced by a placeholder
+    pub fn redacted(&self) -> Config {
+        let mut config = self.clone();
+        for repo in &mut config.repos {
+            if repo.auth_token.is_some() {
+                repo.auth_token = Some(REDACTED_SECRET.to_string());
+            }
+        }
+        for auth in config.hosts.values_mut() {
+            if auth.auth_token.is_some() {
+                auth.auth_token = Some(REDACTED_SECRET.to_string());
+            }
+        }
+        config
+    }
+    
+    /// Layer `other` on top of this config, replacing repos and templates with the same key
+    pub fn overlay(&mut self, other: Config) {
+        for repo in other.repos {
+            match self.repos.iter_mut().find(|r| r.name == repo.name) {
+                Some(existing) => *existing = repo,
+                None => self.repos.push(repo),
+            }
+        }
+        for template in other.templates {
+            match self
+                .templates
+                .iter_mut()
+                .find(|t| t.id == template.id && t.repo == template.repo)
+            {
+                Some(existing) => *existing = template,
+                None => self.templates.push(template),
+            }
+        }
+        for language in other.languages {
+            if !self.languages.contains(&language) {
+                self.languages.push(language);
+            }
+        }
+        if !other.cache.is_default() {
+            self.cache = other.cache;
+        }
+        if !other.theme.is_default() {
+            self.theme = other.theme;
+        }
+        if other.prompt_timeout.is_some() {
+            self.prompt_timeout = other.prompt_timeout;
+        }
+        if !other.defaults.is_default() {
+            self.defaults = other.defaults;
+        }
+        if !other.settings.is_default() {
+            self.settings = other.settings;
+        }
+        self.hosts.extend(other.hosts);
+        for registry in other.registries {
+            match self.registries.iter_mut().find(|r| r.name == registry.name) {
+                Some(existing) => *existing = registry,
+                None => self.registries.push(registry),
+            }
+        }
+    }
+    
+    /// Index of the template addressed by `reference`; bare IDs must be unambiguous
+    pub fn template_index(&self, reference: &str) -> anyhow::Result<usize> {
+        let matches: Vec<usize> = self
+            .templates
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.matches_reference(reference))
+            .map(|(index, _)| index)
+            .collect();
+        
+        match matches.as_slice() {
+            [] => anyhow::bail!("Template '{}' not found", reference),
+            [index] => Ok(*index),
+            _ => anyhow::bail!(
+                "Template ID '{}' is ambiguous, use one of: {}",
+                reference,
+                matches
+                    .iter()
+                    .map(|&index| self.templates[index].qualified_id())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+    
+    /// Canonical form of `language` if it is built in or configured
+    pub fn normalize_language(&self, language: &str) -> anyhow::Result<String> {
+        normalize_language(language, &self.languages)
+    }
+    
+    /// Drop entries that are unchanged from `base`, leaving only what this layer adds
+    /// Parse a config file in TOML or, for version 1 files and exports, JSON (detected
+    /// from the content) and migrate it to `CONFIG_VERSION`
+    pub fn parse(content: &str) -> anyhow::Result<Config> {
+        let mut config: Config = if content.trim_start().starts_with('{') {
+            serde_json::from_str(content)?
+        } else {
+            toml::from_str(content)?
+        };
+        if config.config_version > CONFIG_VERSION {
+            anyhow::bail!(
+                "Config version {} is newer than this mammoth-cli understands ({}), upgrade mammoth-cli",
+                config.config_version,
+                CONFIG_VERSION
+            );
+        }
+        config.migrate();
+        Ok(config)
+    }
+    
+    /// Upgrade the content of an older `config_version` in place
+    pub fn migrate(&mut self) {
+        // 1 -> 2 只改变了文件格式，字段保持不变
+        self.config_version = CONFIG_VERSION;
+    }
+    
+    /// The config as a TOML document
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        let mut config = self.clone();
+        config.config_version = CONFIG_VERSION;
+        Ok(toml::to_string_pretty(&config)?)
+    }
+    
+    pub fn without_inherited(&self, base: &Config) -> Config {
+        let mut local = self.clone();
+        local.repos.retain(|repo| !base.repos.contains(repo));
+        local.templates.retain(|template| !base.templates.contains(template));
+        local.languages.retain(|language| !base.languages.contains(language));
+        if local.cache == base.cache {
+            local.cache = CacheSettings::default();
+        }
+        if local.theme == base.theme {
+            local.theme = ThemeSettings::default();
+        }
+        if local.prompt_timeout == base.prompt_timeout {
+            local.prompt_timeout = None;
+        }
+        if local.defaults == base.defaults {
+            local.defaults = ProjectDefaults::default();
+        }
+        if local.settings == base.settings {
+            local.settings = Settings::default();
+        }
+        local.hosts.retain(|host, auth| base.hosts.get(host) != Some(auth));
+        local.registries.retain(|registry| !base.registries.contains(registry));
+        local
+    }
+    
+    /// The sections a project-local config may set: repositories, templates, languages
+    /// and project defaults. Settings such as the git binary, host credentials,
+    /// registries, cache, theme and prompt timeout stay with the user's own config, so a
+    /// cloned repository cannot change how mammoth runs. Also returns the names of the
+    /// sections that were dropped.
+    pub fn project_layer(self) -> (Config, Vec<&'static str>) {
+        let mut ignored = Vec::new();
+        if !self.settings.is_default() {
+            ignored.push("settings");
+        }
+        if !self.hosts.is_empty() {
+            ignored.push("hosts");
+        }
+        if !self.registries.is_empty() {
+            ignored.push("registries");
+        }
+        if !self.cache.is_default() {
+            ignored.push("cache");
+        }
+        if !self.theme.is_default() {
+            ignored.push("theme");
+        }
+        if self.prompt_timeout.is_some() {
+            ignored.push("prompt_timeout");
+        }
+        let config = Config {
+            repos: self.repos,
+            templates: self.templates,
+            languages: self.languages,
+            defaults: self.defaults,
+            ..Config::default()
+        };
+        (config, ignored)
+    }
+    
+    /// Entries of this config that `layer` replaces when overlaid on it
+    pub fn shadowed_by(&self, layer: &Config) -> Config {
+        Config {
+            repos: self
+                .repos
+                .iter()
+                .filter(|repo| layer.repos.iter().any(|r| r.name == repo.name))
+                .cloned()
+                .collect(),
+            templates: self
+                .templates
+                .iter()
+                .filter(|template| layer.templates.iter().any(|t| t.id == template.id && t.repo == template.repo))
+                .cloned()
+                .collect(),
+            cache: if layer.cache.is_default() { CacheSettings::default() } else { self.cache.clone() },
+            theme: if layer.theme.is_default() { ThemeSettings::default() } else { self.theme.clone() },
+            prompt_timeout: layer.prompt_timeout.as_ref().and(self.prompt_timeout.clone()),
+            defaults: if layer.defaults.is_default() { ProjectDefaults::default() } else { self.defaults.clone() },
+            settings: if layer.settings.is_default() { Settings::default() } else { self.settings.clone() },
+            hosts: self
+                .hosts
+                .iter()
+                .filter(|(host, _)| layer.hosts.contains_key(*host))
+                .map(|(host, auth)| (host.clone(), auth.clone()))
+                .collect(),
+            registries: self
+                .registries
+                .iter()
+                .filter(|registry| layer.registries.iter().any(|r| r.name == registry.name))
+                .cloned()
+                .collect(),
+            ..Config::default()
+        }
+    }
+}
+
+/// Snapshot of the config file taken before a mutation, used by `undo`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JournalEntry {
+    pub timestamp: u64,
+    /// Operation that replaced this state, e.g. "template remove admin"
+    pub operation: String,
+    /// Config file content before the operation, `None` if there was no file
+    pub previous: Option<String>,
+}
+
+/// One line of the operation audit log
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    /// RFC 3339 timestamp
+    pub time: String,
+    pub user: String,
+    pub operation: String,
+}
+
+/// How often and when a template was last used to generate a project
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TemplateUsage {
+    pub count: u32,
+    /// Unix timestamp of the last generated project
+    pub last_used: u64,
+}
+
+impl TemplateUsage {
+    /// Use count decayed by age, halving every 30 days without use
+    pub fn score(&self, now: u64) -> f64 {
+        let days = now.saturating_sub(self.last_used) as f64 / 86_400.0;
+        self.count as f64 * 0.5_f64.powf(days / 30.0)
+    }
+}
+
+/// Metadata stored next to each cached template
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CacheMeta {
+    /// Unix timestamp of the last successful fetch
+    pub fetched_at: u64,
+    /// Commit the cached files were extracted from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Pinned ref of the template when it was fetched
+    #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+}
+
+/// Options controlling how a project is generated
+#[derive(Debug, Clone, Default)]
+pub struct GenerateOptions {
+    /// Fetch the template into a temp dir instead of using the cache
+    pub no_cache: bool,
+    /// Only offer templates of this language in the picker
+    pub language: Option<String>,
+    /// Addon templates from `new --with`, applied over the template in order
+    pub addons: Vec<String>,
+    /// Update dependency ranges in package.json to the newest versions on npm
+    pub latest_deps: bool,
+    /// Community files to add, e.g. "codeowners"
+    pub community_files: Vec<String>,
+    /// Organization or team substituted into community files
+    pub org: Option<String>,
+    /// SPDX license inserted as a header into source files, overrides the manifest
+    pub license: Option<String>,
+    /// Start the project from the template path's git history instead of a fresh repository
+    pub keep_history: bool,
+    /// Print the placeholder report after generation
+    pub verbose: bool,
+    /// Print a JSON summary instead of the human-readable messages
+    pub json: bool,
+    /// Prompt answers from `new --values`
+    pub values: PromptValues,
+    /// Install dependencies after generation; `None` asks on interactive runs
+    pub install: Option<bool>,
+    /// What to do when the project directory already contains files
+    pub existing: ExistingDirPolicy,
+    /// Commits from `mammoth.lock` per qualified template ID, fetched instead of the cache
+    pub locked_commits: BTreeMap<String, String>,
+    /// Leave out `git init`, for scratch renders that are compared rather than used
+    pub skip_git_init: bool,
+}
+
+/// Handling of a non-empty project directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExistingDirPolicy {
+    /// Refuse to generate (default)
+    #[default]
+    Abort,
+    /// Write every template file, overwriting files that differ
+    Force,
+    /// Only add files that do not exist yet
+    Merge,
+}
+
+/// Answers to the `new` prompts read from a YAML or JSON file. Command line flags win
+/// over the file; questions it answers are not asked.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PromptValues {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Addon templates applied over the template, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addons: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Output directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Answers to the variables declared by the template manifest
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, serde_json::Value>,
+}
+
+impl PromptValues {
+    /// Read a values file, JSON for `.json` files and YAML otherwise
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        use anyhow::Context;
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read values file: {}", path.display()))?;
+        let values = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&content)?
+        } else {
+            serde_yaml::from_str(&content)?
+        };
+        Ok(values)
+    }
 }
 
 #[derive(Debug)]
 pub struct ProjectConfig {
     pub name: String,
     pub author: String,
+    /// Author email, may be empty
+    pub email: String,
+    /// Author homepage, may be empty
+    pub author_url: String,
     pub description: String,
     pub output_dir: String,
     pub template: Template,
-}
\ No newline at end of file
+    /// Addon templates applied over the template, in order
+    pub addons: Vec<Template>,
+    /// Answers to the variables declared by the template manifests
+    pub values: BTreeMap<String, String>,
+}
+
+impl ProjectConfig {
+    /// Variables available for `{{variable}}` interpolation in templates
+    pub fn variables(&self) -> HashMap<String, String> {
+        let mut vars = HashMap::new();
+        vars.insert("name".to_string(), self.name.clone());
+        vars.insert("author".to_string(), self.author.clone());
+        vars.insert("author_email".to_string(), self.email.clone());
+        vars.insert("author_url".to_string(), self.author_url.clone());
+        vars.insert("description".to_string(), self.description.clone());
+        vars.insert("output_dir".to_string(), self.output_dir.clone());
+        vars.insert("template".to_string(), self.template.id.clone());
+        vars.insert("language".to_string(), self.template.language.clone());
+        vars.extend(self.values.clone());
+        vars
+    }
+    
+    /// Author in the "Name <email>" form used by Cargo, Poetry and copyright lines
+    pub fn author_line(&self) -> String {
+        if self.email.is_empty() {
+            self.author.clone()
+        } else {
+            format!("{} <{}>", self.author, self.email)
+        }
+    }
+    
+    /// npm `author` object, empty fields are left out
+    pub fn author_json(&self) -> serde_json::Value {
+        let mut author = serde_json::Map::new();
+        for (key, value) in [("name", &self.author), ("email", &self.email), ("url", &self.author_url)] {
+            if !value.is_empty() {
+                author.insert(key.to_string(), serde_json::Value::String(value.clone()));
+            }
+        }
+        serde_json::Value::Object(author)
+    }
+}
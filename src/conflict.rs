@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use dialoguer::Select;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::diff::{print_unified_diff, FileChange};
+use crate::git::git_binary;
+use crate::ui::{is_interactive, prompt_timed_out};
+
+/// How files changed both locally and upstream are resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Ask for every conflicting file
+    Prompt,
+    /// Keep the local file
+    Mine,
+    /// Overwrite with the template file
+    Theirs,
+    /// Keep the local file and write the template version next to it as `.new`
+    New,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "prompt" => Ok(ConflictPolicy::Prompt),
+            "mine" => Ok(ConflictPolicy::Mine),
+            "theirs" => Ok(ConflictPolicy::Theirs),
+            "new" => Ok(ConflictPolicy::New),
+            _ => anyhow::bail!(
+                "Invalid conflict policy: {}. Use 'prompt', 'mine', 'theirs' or 'new'",
+                s
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    KeepMine,
+    TakeTheirs,
+    WriteNew,
+    MergeTool,
+}
+
+pub fn choose_resolution(change: &FileChange, policy: ConflictPolicy) -> Result<Resolution> {
+    match policy {
+        ConflictPolicy::Prompt => prompt_resolution(change),
+        ConflictPolicy::Mine => Ok(Resolution::KeepMine),
+        ConflictPolicy::Theirs => Ok(Resolution::TakeTheirs),
+        ConflictPolicy::New => Ok(Resolution::WriteNew),
+    }
+}
+
+fn prompt_resolution(change: &FileChange) -> Result<Resolution> {
+    if !is_interactive() {
+        anyhow::bail!(
+            "Conflict in {} needs a decision, pass --on-conflict mine|theirs|new when running non-interactively",
+            change.path.display()
+        );
+    }
+    
+    let items = [
+        "Keep mine",
+        "Take theirs",
+        "Write theirs as .new file",
+        "Open merge tool",
+        "Show diff",
+    ];
+    
+    let prompt = format!("Conflict in {}", change.path.display());
+    if prompt_timed_out(&prompt, items[0])? {
+        return Ok(Resolution::KeepMine);
+    }
+    
+    loop {
+        let selection = Select::new()
+            .with_prompt(&prompt)
+            .items(&items)
+            .default(0)
+            .interact()?;
+        
+        match selection {
+            0 => return Ok(Resolution::KeepMine),
+            1 => return Ok(Resolution::TakeTheirs),
+            2 => return Ok(Resolution::WriteNew),
+            3 => return Ok(Resolution::MergeTool),
+            _ => print_unified_diff(change),
+        }
+    }
+}
+
+/// Apply a resolution for `relative`, copying from the template into the project as needed
+pub fn apply_resolution(
+    resolution: Resolution,
+    relative: &Path,
+    template_dir: &Path,
+    project_dir: &Path,
+) -> Result<()> {
+    let theirs = template_dir.join(relative);
+    let mine = project_dir.join(relative);
+    
+    match resolution {
+        Resolution::KeepMine => {}
+        Resolution::TakeTheirs => {
+            if let Some(parent) = mine.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&theirs, &mine)
+                .with_context(|| format!("Failed to update {}", mine.display()))?;
+        }
+        Resolution::WriteNew => {
+            let new_path = with_suffix(&mine, ".new");
+            fs::copy(&theirs, &new_path)
+                .with_context(|| format!("Failed to write {}", new_path.display()))?;
+        }
+        Resolution::MergeTool => {
+            // 复制一份模板文件，避免合并工具直接修改缓存
+            let theirs_copy = with_suffix(&mine, ".theirs");
+            fs::copy(&theirs, &theirs_copy)
+                .with_context(|| format!("Failed to write {}", theirs_copy.display()))?;
+            let result = run_merge_tool(&mine, &theirs_copy);
+            fs::remove_file(&theirs_copy).ok();
+            result?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// `path` with `suffix` appended to its file name, e.g. "package.json.new"
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn run_merge_tool(mine: &Path, theirs: &Path) -> Result<()> {
+    let tool = std::env::var("MAMMOTH_MERGETOOL").unwrap_or_else(|_| "vimdiff".to_string());
+    
+    let status = Command::new(&tool)
+        .arg(mine)
+        .arg(theirs)
+        .status()
+        .with_context(|| {
+            format!(
+                "Failed to launch merge tool '{}'. Set MAMMOTH_MERGETOOL to your preferred tool",
+                tool
+            )
+        })?;
+    
+    if !status.success() {
+        anyhow::bail!("Merge tool '{}' exited with {}", tool, status);
+    }
+    
+    Ok(())
+}
+
+/// Result of a three-way merge of one file
+pub struct MergedFile {
+    pub content: Vec<u8>,
+    /// Conflicting hunks, written with `<<<<<<<`/`>>>>>>>` markers
+    pub conflicts: usize,
+}
+
+/// Merge the template change from `base` to `theirs` into `mine` with `git merge-file`
+pub fn merge_file(mine: &Path, base: &Path, theirs: &Path) -> Result<MergedFile> {
+    let output = Command::new(git_binary())
+        .args(["merge-file", "-p", "-L", "project", "-L", "previous template", "-L", "template"])
+        .arg(mine)
+        .arg(base)
+        .arg(theirs)
+        .output()
+        .context("Failed to run git merge-file")?;
+    
+    // 退出码为冲突块数，负数（信号或 >127）表示出错
+    match output.status.code() {
+        Some(code) if (0..128).contains(&code) => Ok(MergedFile {
+            content: output.stdout,
+            conflicts: code as usize,
+        }),
+        _ => anyhow::bail!(
+            "git merge-file failed on {}: {}",
+            mine.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Service name of mammoth-cli entries in the OS keyring
+pub const KEYRING_SERVICE: &str = "mammoth-cli";
+
+/// Tokens already read from the keyring, so one run does not unlock it per clone
+static TOKENS: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+
+fn entry(key: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, key).context("Failed to open the OS keyring")
+}
+
+/// Token stored under `key`, `None` when the keyring has no such entry
+pub fn keyring_token(key: &str) -> Result<Option<String>> {
+    let cache = TOKENS.get_or_init(Default::default);
+    if let Some(token) = cache.lock().unwrap().get(key) {
+        return Ok(token.clone());
+    }
+    let token = match entry(key)?.get_password() {
+        Ok(token) => Some(token),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => return Err(e).with_context(|| format!("Failed to read '{}' from the OS keyring", key)),
+    };
+    cache.lock().unwrap().insert(key.to_string(), token.clone());
+    Ok(token)
+}
+
+/// Store `token` under `key`, replacing any previous token
+pub fn store_keyring_token(key: &str, token: &str) -> Result<()> {
+    entry(key)?
+        .set_password(token)
+        .with_context(|| format!("Failed to store '{}' in the OS keyring", key))?;
+    let cache = TOKENS.get_or_init(Default::default);
+    cache.lock().unwrap().insert(key.to_string(), Some(token.to_string()));
+    Ok(())
+}
+
+/// Remove the token stored under `key`; a missing entry is not an error
+pub fn delete_keyring_token(key: &str) -> Result<()> {
+    match entry(key)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(e).with_context(|| format!("Failed to remove '{}' from the OS keyring", key)),
+    }
+    let cache = TOKENS.get_or_init(Default::default);
+    cache.lock().unwrap().insert(key.to_string(), None);
+    Ok(())
+}
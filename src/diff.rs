@@ -0,0 +1,192 @@
+use anyhow::Result;
+use colored::*;
+use similar::{ChangeTag, TextDiff};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::manifest::is_manifest_file;
+use crate::output;
+use crate::theme::Themed;
+use crate::utils::list_files;
+
+/// How file changes are rendered in the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffFormat {
+    /// Colored unified diff of every changed file
+    Full,
+    /// Per-file insertion/deletion counts
+    Stat,
+    /// Changed file names only
+    NameOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// File exists in the template but not in the project
+    Added,
+    /// File exists in both with different content
+    Modified,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+    /// Current project content, `None` when missing or binary
+    pub ours: Option<String>,
+    /// Template content, `None` when binary
+    pub theirs: Option<String>,
+}
+
+impl FileChange {
+    pub fn is_binary(&self) -> bool {
+        self.theirs.is_none() || (self.kind == ChangeKind::Modified && self.ours.is_none())
+    }
+}
+
+/// Compare template files against a project directory. Files that only exist in the
+/// project belong to the user and are not reported.
+pub fn compare_dirs(template_dir: &Path, project_dir: &Path) -> Result<Vec<FileChange>> {
+    let mut changes = Vec::new();
+    
+    for relative in list_files(template_dir)? {
+        if is_manifest_file(&relative) {
+            continue;
+        }
+        
+        let template_bytes = fs::read(template_dir.join(&relative))?;
+        let project_file = project_dir.join(&relative);
+        
+        if !project_file.exists() {
+            changes.push(FileChange {
+                path: relative,
+                kind: ChangeKind::Added,
+                ours: None,
+                theirs: String::from_utf8(template_bytes).ok(),
+            });
+            continue;
+        }
+        
+        let project_bytes = fs::read(&project_file)?;
+        if project_bytes != template_bytes {
+            changes.push(FileChange {
+                path: relative,
+                kind: ChangeKind::Modified,
+                ours: String::from_utf8(project_bytes).ok(),
+                theirs: String::from_utf8(template_bytes).ok(),
+            });
+        }
+    }
+    
+    Ok(changes)
+}
+
+pub fn print_changes(changes: &[FileChange], format: DiffFormat) {
+    match format {
+        DiffFormat::NameOnly => {
+            for change in changes {
+                output!("{}", change.path.display());
+            }
+        }
+        DiffFormat::Stat => print_stat(changes),
+        DiffFormat::Full => {
+            for change in changes {
+                print_unified_diff(change);
+            }
+        }
+    }
+}
+
+/// Count (insertions, deletions) between the project and the template version
+fn line_counts(change: &FileChange) -> (usize, usize) {
+    let ours = change.ours.as_deref().unwrap_or("");
+    let theirs = change.theirs.as_deref().unwrap_or("");
+    let diff = TextDiff::from_lines(ours, theirs);
+    
+    let mut insertions = 0;
+    let mut deletions = 0;
+    for line in diff.iter_all_changes() {
+        match line.tag() {
+            ChangeTag::Insert => insertions += 1,
+            ChangeTag::Delete => deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+    (insertions, deletions)
+}
+
+fn print_stat(changes: &[FileChange]) {
+    let width = changes
+        .iter()
+        .map(|c| c.path.display().to_string().len())
+        .max()
+        .unwrap_or(0);
+    
+    let mut total_insertions = 0;
+    let mut total_deletions = 0;
+    for change in changes {
+        let name = change.path.display().to_string();
+        if change.is_binary() {
+            output!(" {:width$} | Bin", name, width = width);
+            continue;
+        }
+        
+        let (insertions, deletions) = line_counts(change);
+        total_insertions += insertions;
+        total_deletions += deletions;
+        output!(
+            " {:width$} | {:>4} {}{}",
+            name,
+            insertions + deletions,
+            "+".repeat(insertions.min(40)).success(),
+            "-".repeat(deletions.min(40)).error(),
+            width = width
+        );
+    }
+    
+    output!(
+        " {} files changed, {} insertions(+), {} deletions(-)",
+        changes.len(),
+        total_insertions,
+        total_deletions
+    );
+}
+
+pub fn print_unified_diff(change: &FileChange) {
+    let name = change.path.display().to_string();
+    let old_label = match change.kind {
+        ChangeKind::Added => "/dev/null".to_string(),
+        ChangeKind::Modified => format!("a/{}", name),
+    };
+    
+    output!("{}", format!("diff --mammoth a/{} b/{}", name, name).bold());
+    if change.is_binary() {
+        output!("Binary files {} and b/{} differ", old_label, name);
+        return;
+    }
+    
+    output!("{}", format!("--- {}", old_label).bold());
+    output!("{}", format!("+++ b/{}", name).bold());
+    
+    let ours = change.ours.as_deref().unwrap_or("");
+    let theirs = change.theirs.as_deref().unwrap_or("");
+    let diff = TextDiff::from_lines(ours, theirs);
+    let mut unified = diff.unified_diff();
+    unified.context_radius(3);
+    
+    for hunk in unified.iter_hunks() {
+        output!("{}", hunk.header().to_string().highlight());
+        for line in hunk.iter_changes() {
+            let text = line.value().trim_end_matches(['\r', '\n']);
+            match line.tag() {
+                ChangeTag::Delete => output!("{}", format!("-{}", text).error()),
+                ChangeTag::Insert => output!("{}", format!("+{}", text).success()),
+                ChangeTag::Equal => output!(" {}", text),
+            }
+            if line.missing_newline() {
+                output!("\\ No newline at end of file");
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use toml_edit::{value, Array, DocumentMut, InlineTable};
+
+use crate::config::{canonical_language, ProjectConfig};
+
+/// Project manifests besides package.json that carry the project name, description and authors
+pub static PROJECT_MANIFESTS: [&str; 4] = ["Cargo.toml", "pyproject.toml", "go.mod", "composer.json"];
+
+/// Manifests to update for a template language. Unknown and custom languages get every
+/// manifest that exists, so a PHP or polyglot template works without extra config.
+fn manifests_for(language: &str) -> &'static [&'static str] {
+    match canonical_language(language).as_str() {
+        "rust" => &PROJECT_MANIFESTS[0..1],
+        "python" => &PROJECT_MANIFESTS[1..2],
+        "go" => &PROJECT_MANIFESTS[2..3],
+        "php" => &PROJECT_MANIFESTS[3..4],
+        "vue" | "react" | "svelte" | "angular" | "solid" | "javascript" | "typescript" | "node" => &[],
+        _ => &PROJECT_MANIFESTS,
+    }
+}
+
+/// Write the project name, description and author into the non-npm manifests of the
+/// template language. Missing files are skipped like a missing package.json.
+pub fn update_project_manifests(project_path: &Path, config: &ProjectConfig) -> Result<()> {
+    for file in manifests_for(&config.template.language) {
+        let path = project_path.join(file);
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", file))?;
+        let updated = match *file {
+            "Cargo.toml" => update_cargo_toml(&content, config),
+            "pyproject.toml" => update_pyproject_toml(&content, config),
+            "go.mod" => Ok(update_go_mod(&content, &config.name)),
+            _ => update_composer_json(&content, config),
+        }
+        .with_context(|| format!("Failed to update {}", file))?;
+        fs::write(&path, updated).with_context(|| format!("Failed to write {}", file))?;
+    }
+    Ok(())
+}
+
+fn update_cargo_toml(content: &str, config: &ProjectConfig) -> Result<String> {
+    let mut doc: DocumentMut = content.parse()?;
+    // 工作区根目录的 Cargo.toml 没有 [package]
+    if let Some(package) = doc.get_mut("package").and_then(|item| item.as_table_mut()) {
+        package["name"] = value(config.name.as_str());
+        if !config.description.is_empty() {
+            package["description"] = value(config.description.as_str());
+        }
+        if !config.author.is_empty() {
+            package["authors"] = value(Array::from_iter([config.author_line()]));
+        }
+    }
+    Ok(doc.to_string())
+}
+
+fn update_pyproject_toml(content: &str, config: &ProjectConfig) -> Result<String> {
+    let mut doc: DocumentMut = content.parse()?;
+    // PEP 621 [project] 使用作者表，Poetry 使用 "Name <email>" 字符串
+    if let Some(project) = doc.get_mut("project").and_then(|item| item.as_table_mut()) {
+        project["name"] = value(config.name.as_str());
+        if !config.description.is_empty() {
+            project["description"] = value(config.description.as_str());
+        }
+        if !config.author.is_empty() {
+            let mut author = InlineTable::new();
+            author.insert("name", config.author.as_str().into());
+            if !config.email.is_empty() {
+                author.insert("email", config.email.as_str().into());
+            }
+            project["authors"] = value(Array::from_iter([author]));
+        }
+    }
+    if let Some(poetry) = doc
+        .get_mut("tool")
+        .and_then(|tool| tool.get_mut("poetry"))
+        .and_then(|item| item.as_table_mut())
+    {
+        poetry["name"] = value(config.name.as_str());
+        if !config.description.is_empty() {
+            poetry["description"] = value(config.description.as_str());
+        }
+        if !config.author.is_empty() {
+            poetry["authors"] = value(Array::from_iter([config.author_line()]));
+        }
+    }
+    Ok(doc.to_string())
+}
+
+/// Replace the last segment of the module path, e.g. github.com/acme/starter -> github.com/acme/<name>
+fn update_go_mod(content: &str, name: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    if let Some(line) = lines.iter_mut().find(|line| line.trim_start().starts_with("module ")) {
+        let module = line.trim_start()["module ".len()..].trim().trim_matches('"');
+        let module = match module.rsplit_once('/') {
+            Some((parent, _)) => format!("{}/{}", parent, name),
+            None => name.to_string(),
+        };
+        *line = format!("module {}", module);
+    }
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated
+}
+
+/// Composer names are "vendor/package", the template's vendor is kept
+fn update_composer_json(content: &str, config: &ProjectConfig) -> Result<String> {
+    let mut composer: serde_json::Value = serde_json::from_str(content)?;
+    if let Some(obj) = composer.as_object_mut() {
+        let vendor = obj
+            .get("name")
+            .and_then(|name| name.as_str())
+            .and_then(|name| name.split_once('/'))
+            .map_or("app".to_string(), |(vendor, _)| vendor.to_string());
+        obj.insert(
+            "name".to_string(),
+            serde_json::Value::String(format!("{}/{}", vendor, config.name.to_lowercase())),
+        );
+        if !config.description.is_empty() {
+            obj.insert(
+                "description".to_string(),
+                serde_json::Value::String(config.description.clone()),
+            );
+        }
+        if !config.author.is_empty() {
+            obj.insert(
+                "authors".to_string(),
+                serde_json::json!([config.author_json()]),
+            );
+        }
+    }
+    Ok(serde_json::to_string_pretty(&composer)? + "\n")
+}
@@ -0,0 +1,124 @@
+use colored::*;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::theme::Themed;
+use crate::utils::format_size;
+
+/// Troubleshooting section of the docs, anchored by error code
+const DOCS_URL: &str = "https://github.com/Mulander-J/mammoth-cli/blob/main/README.md";
+
+/// Common failures, reported with a short cause, a suggested fix and a docs link
+#[derive(Debug)]
+pub enum MammothError {
+    GitNotFound,
+    BranchNotFound { repo: String, branch: String },
+    RefNotFound { repo: String, git_ref: String },
+    PathNotInRepo { repo: String, path: String },
+    CachePermissionDenied { path: PathBuf },
+    InsufficientDiskSpace { path: PathBuf, required: u64, available: u64 },
+    Offline { action: String },
+}
+
+impl MammothError {
+    /// Stable code, also used as the docs anchor
+    pub fn code(&self) -> &'static str {
+        match self {
+            MammothError::GitNotFound => "git-not-found",
+            MammothError::BranchNotFound { .. } => "branch-not-found",
+            MammothError::RefNotFound { .. } => "ref-not-found",
+            MammothError::PathNotInRepo { .. } => "path-not-in-repo",
+            MammothError::CachePermissionDenied { .. } => "cache-permission-denied",
+            MammothError::InsufficientDiskSpace { .. } => "insufficient-disk-space",
+            MammothError::Offline { .. } => "offline",
+        }
+    }
+    
+    pub fn fix(&self) -> String {
+        match self {
+            MammothError::GitNotFound => {
+                "Install git (https://git-scm.com/downloads) and make sure it is on your PATH, or point MAMMOTH_GIT / settings.git_path at the executable".to_string()
+            }
+            MammothError::BranchNotFound { repo, branch } => format!(
+                "Check the branch name with 'git ls-remote --heads <url>', then fix it with 'repo update {} --branch <branch>' or 'template update <id> --branch <branch>' (currently '{}')",
+                repo, branch
+            ),
+            MammothError::RefNotFound { git_ref, .. } => format!(
+                "Check the tag with 'git ls-remote --tags <url>' (commits must be full SHAs), then fix the pin with 'template update <id> --ref <ref>' or remove it with --ref \"\" (currently '{}')",
+                git_ref
+            ),
+            MammothError::PathNotInRepo { repo, path } => format!(
+                "Check that '{}' exists on the template branch of '{}', then fix it with 'template update <id> --path <path>'",
+                path, repo
+            ),
+            MammothError::CachePermissionDenied { path } => format!(
+                "Make {} writable by your user (e.g. fix its owner), or remove it and run again",
+                path.display()
+            ),
+            MammothError::InsufficientDiskSpace { path, .. } => format!(
+                "Free up space on the disk holding {}, run 'clean' to drop cached templates, or lower the repository's size_hint if the estimate is too high",
+                path.display()
+            ),
+            MammothError::Offline { .. } => {
+                "Run again without --offline, or download the templates you need beforehand with 'template download <id>' or 'template download-all'; 'template list --offline' shows which ones are usable offline".to_string()
+            }
+        }
+    }
+    
+    pub fn docs_url(&self) -> String {
+        format!("{}#error-{}", DOCS_URL, self.code())
+    }
+}
+
+impl fmt::Display for MammothError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MammothError::GitNotFound => write!(f, "git is not installed or not on PATH"),
+            MammothError::BranchNotFound { repo, branch } => {
+                write!(f, "Branch '{}' does not exist in repository '{}'", branch, repo)
+            }
+            MammothError::RefNotFound { repo, git_ref } => {
+                write!(f, "Ref '{}' does not exist in repository '{}'", git_ref, repo)
+            }
+            MammothError::PathNotInRepo { repo, path } => {
+                write!(f, "Template path '{}' not found in repository '{}'", path, repo)
+            }
+            MammothError::CachePermissionDenied { path } => {
+                write!(f, "Permission denied on cache directory {}", path.display())
+            }
+            MammothError::InsufficientDiskSpace {
+                path,
+                required,
+                available,
+            } => write!(
+                f,
+                "Not enough disk space in {}: about {} needed, {} available",
+                path.display(),
+                format_size(*required),
+                format_size(*available)
+            ),
+            MammothError::Offline { action } => write!(f, "Cannot {} in offline mode", action),
+        }
+    }
+}
+
+impl std::error::Error for MammothError {}
+
+/// Print an error, in structured form when the chain contains a known failure
+pub fn report_error(error: &anyhow::Error) {
+    let known = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<MammothError>());
+    
+    match known {
+        Some(known) => {
+            eprintln!("{} {}", "❌".error(), error.to_string().bold());
+            if error.to_string() != known.to_string() {
+                eprintln!("   Cause: {}", known);
+            }
+            eprintln!("   Fix:   {}", known.fix());
+            eprintln!("   Docs:  {}", known.docs_url().highlight());
+        }
+        None => eprintln!("{} Error: {:?}", "❌".error(), error),
+    }
+}
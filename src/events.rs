@@ -0,0 +1,183 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::ui::{is_quiet, plain_text, render_progress, suspend_progress};
+
+/// Text reported by core operations. The CLI prints it; embedders install their own
+/// handler with `set_event_handler` instead of capturing stdout.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum Event {
+    /// Progress of an operation, e.g. "✅ Template 'admin' downloaded"
+    Status(String),
+    /// Something the user should look at, the operation continues
+    Warning(String),
+    /// Requested output: listings, tables, diffs and JSON documents
+    Output(String),
+    Progress(ProgressEvent),
+}
+
+/// What a progress indicator tracks, so a front end can lay it out
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressKind {
+    /// Download of one template, labelled with its ID
+    Download,
+    /// Count of finished downloads in a batch
+    Batch,
+    /// Phases of project generation
+    Generate,
+}
+
+/// Update of the progress indicator `id`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    Started { id: u64, kind: ProgressKind, label: String, total: u64 },
+    Message { id: u64, message: String },
+    Position { id: u64, position: u64 },
+    /// Completed, `message` is the final state
+    Finished { id: u64, message: String },
+    /// Stopped by an error
+    Failed { id: u64, message: String },
+}
+
+static NEXT_PROGRESS_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Handle core operations report progress through; clones share the position
+#[derive(Debug, Clone)]
+pub struct Progress {
+    id: u64,
+    total: u64,
+    position: Arc<AtomicU64>,
+}
+
+impl Progress {
+    pub fn start(kind: ProgressKind, label: impl Into<String>, total: u64) -> Self {
+        let id = NEXT_PROGRESS_ID.fetch_add(1, Ordering::Relaxed);
+        emit(Event::Progress(ProgressEvent::Started {
+            id,
+            kind,
+            label: label.into(),
+            total,
+        }));
+        Self {
+            id,
+            total,
+            position: Arc::new(AtomicU64::new(0)),
+        }
+    }
+    
+    pub fn set_message(&self, message: impl Into<String>) {
+        emit(Event::Progress(ProgressEvent::Message {
+            id: self.id,
+            message: message.into(),
+        }));
+    }
+    
+    pub fn set_position(&self, position: u64) {
+        let position = position.min(self.total);
+        if self.position.swap(position, Ordering::Relaxed) == position {
+            return;
+        }
+        emit(Event::Progress(ProgressEvent::Position { id: self.id, position }));
+    }
+    
+    pub fn inc(&self, delta: u64) {
+        self.set_position(self.position.load(Ordering::Relaxed) + delta);
+    }
+    
+    /// Move to the end and finish with `message`
+    pub fn finish(&self, message: impl Into<String>) {
+        self.set_position(self.total);
+        emit(Event::Progress(ProgressEvent::Finished {
+            id: self.id,
+            message: message.into(),
+        }));
+    }
+    
+    pub fn fail(&self, message: impl Into<String>) {
+        emit(Event::Progress(ProgressEvent::Failed {
+            id: self.id,
+            message: message.into(),
+        }));
+    }
+}
+
+type EventHandler = Box<dyn Fn(&Event) + Send + Sync>;
+
+static HANDLER: RwLock<Option<EventHandler>> = RwLock::new(None);
+
+/// Send every following event to `handler` instead of the terminal
+pub fn set_event_handler(handler: impl Fn(&Event) + Send + Sync + 'static) {
+    *HANDLER.write().unwrap() = Some(Box::new(handler));
+}
+
+/// Restore the terminal output of events
+pub fn reset_event_handler() {
+    *HANDLER.write().unwrap() = None;
+}
+
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Print status messages to stderr, so that stdout only carries the JSON document of a
+/// `--json` run
+pub fn set_status_to_stderr(enabled: bool) {
+    STATUS_TO_STDERR.store(enabled, Ordering::Relaxed);
+}
+
+pub fn emit(event: Event) {
+    if let Some(handler) = HANDLER.read().unwrap().as_ref() {
+        handler(&event);
+        return;
+    }
+    print_event(&event);
+}
+
+/// Terminal output of an event: status and warnings are silenced by `--quiet` and lose
+/// their emoji in plain mode, warnings (and status with `set_status_to_stderr`) go to
+/// stderr and progress is drawn as progress bars. Output is printed as it is, it may be
+/// a JSON document or a diff; its messages use `plain_text` themselves.
+pub fn print_event(event: &Event) {
+    match event {
+        Event::Status(text) if !is_quiet() && STATUS_TO_STDERR.load(Ordering::Relaxed) => {
+            suspend_progress(|| eprintln!("{}", plain_text(text)))
+        }
+        Event::Status(text) if !is_quiet() => suspend_progress(|| println!("{}", plain_text(text))),
+        Event::Warning(text) if !is_quiet() => suspend_progress(|| eprintln!("{}", plain_text(text))),
+        Event::Output(text) => suspend_progress(|| println!("{}", text)),
+        Event::Progress(progress) => render_progress(progress),
+        _ => {}
+    }
+}
+
+/// Status message, silenced by `--quiet`
+#[macro_export]
+macro_rules! status {
+    () => {
+        $crate::events::emit($crate::events::Event::Status(String::new()))
+    };
+    ($($arg:tt)*) => {
+        $crate::events::emit($crate::events::Event::Status(format!($($arg)*)))
+    };
+}
+
+/// Warning, printed to stderr and silenced by `--quiet`
+#[macro_export]
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::events::emit($crate::events::Event::Warning(format!($($arg)*)))
+    };
+}
+
+/// Requested output, printed even with `--quiet`
+#[macro_export]
+macro_rules! output {
+    () => {
+        $crate::events::emit($crate::events::Event::Output(String::new()))
+    };
+    ($($arg:tt)*) => {
+        $crate::events::emit($crate::events::Event::Output(format!($($arg)*)))
+    };
+}
@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use git2::{Cred, CredentialType, ErrorClass, ErrorCode, FetchOptions, ObjectType, RemoteCallbacks, Repository};
+use std::fs;
+use std::path::Path;
+
+use crate::errors::MammothError;
+
+/// Fetch `branch` of `url` into the bare repository `clone_dir` (created on first use)
+/// with libgit2 and write the tree at `path` into `dest`. Only the tip commit is fetched.
+/// SSH remotes authenticate with `ssh_key` when set, otherwise through the ssh-agent.
+/// `progress` receives received and total objects while fetching. Returns the commit id.
+#[allow(clippy::too_many_arguments)]
+pub fn fetch_template(
+    repo_name: &str,
+    url: &str,
+    credentials: (Option<String>, Option<String>),
+    ssh_key: Option<&str>,
+    clone_dir: &Path,
+    branch: &str,
+    path: &str,
+    dest: &Path,
+    progress: &mut dyn FnMut(usize, usize),
+) -> Result<String> {
+    let repo = if clone_dir.exists() {
+        Repository::open_bare(clone_dir).context("Failed to open repository clone")?
+    } else {
+        Repository::init_bare(clone_dir).context("Failed to create repository clone")?
+    };
+    
+    let (username, token) = credentials;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |_url, username_from_url, allowed| {
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(token) = &token {
+                let username = username.as_deref().or(username_from_url).unwrap_or("x-access-token");
+                return Cred::userpass_plaintext(username, token);
+            }
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let username = username_from_url.unwrap_or("git");
+            return match ssh_key {
+                Some(key) => Cred::ssh_key(username, None, Path::new(key), None),
+                None => Cred::ssh_key_from_agent(username),
+            };
+        }
+        Cred::default()
+    });
+    callbacks.transfer_progress(|stats| {
+        progress(stats.received_objects(), stats.total_objects());
+        true
+    });
+    
+    let tracking = format!("refs/remotes/origin/{}", branch);
+    let refspec = format!("+refs/heads/{}:{}", branch, tracking);
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks).depth(1);
+    repo.remote_anonymous(url)?
+        .fetch(&[refspec.as_str()], Some(&mut options), None)
+        .map_err(|e| fetch_error(e, repo_name, branch))?;
+    
+    let reference = repo
+        .find_reference(&tracking)
+        .map_err(|_| MammothError::BranchNotFound {
+            repo: repo_name.to_string(),
+            branch: branch.to_string(),
+        })?;
+    let commit = reference.peel_to_commit()?;
+    let commit_id = commit.id().to_string();
+    
+    let path_not_in_repo = || MammothError::PathNotInRepo {
+        repo: repo_name.to_string(),
+        path: path.to_string(),
+    };
+    let root = commit.tree()?;
+    let tree = match path.trim_matches('/') {
+        "" | "." => root,
+        path => root
+            .get_path(Path::new(path))
+            .map_err(|_| path_not_in_repo())?
+            .to_object(&repo)?
+            .peel_to_tree()
+            .map_err(|_| path_not_in_repo())?,
+    };
+    
+    write_tree(&repo, &tree, dest)?;
+    Ok(commit_id)
+}
+
+/// Name the failure from libgit2's error code instead of only passing its message on
+fn fetch_error(error: git2::Error, repo_name: &str, branch: &str) -> anyhow::Error {
+    match (error.code(), error.class()) {
+        (ErrorCode::Auth, _) | (_, ErrorClass::Ssh) => {
+            anyhow::anyhow!("Authentication failed for repository '{}': {}", repo_name, error.message())
+        }
+        (ErrorCode::NotFound, _) => MammothError::BranchNotFound {
+            repo: repo_name.to_string(),
+            branch: branch.to_string(),
+        }
+        .into(),
+        (ErrorCode::Certificate, _) => {
+            anyhow::anyhow!("Certificate check failed for repository '{}': {}", repo_name, error.message())
+        }
+        (_, ErrorClass::Net) | (_, ErrorClass::Http) => {
+            anyhow::anyhow!("Cannot reach repository '{}': {}", repo_name, error.message())
+        }
+        _ => anyhow::anyhow!("Failed to fetch branch '{}' of '{}': {}", branch, repo_name, error.message()),
+    }
+}
+
+fn write_tree(repo: &Repository, tree: &git2::Tree<'_>, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in tree.iter() {
+        let target = dest.join(entry.name().unwrap_or_default());
+        match entry.kind() {
+            Some(ObjectType::Tree) => {
+                write_tree(repo, &entry.to_object(repo)?.peel_to_tree()?, &target)?;
+            }
+            Some(ObjectType::Blob) => {
+                let blob = entry.to_object(repo)?.peel_to_blob()?;
+                fs::write(&target, blob.content())
+                    .with_context(|| format!("Failed to write {}", target.display()))?;
+            }
+            // 子模块不在同一个仓库中，跳过
+            _ => continue,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use gix::bstr::ByteSlice;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+
+use crate::errors::MammothError;
+
+/// Fetch `branch` of `url` into the bare repository `clone_dir` (created on first use)
+/// without a git binary and write the tree at `path` into `dest`. Only the tip commit is
+/// fetched. Returns the commit id.
+pub fn fetch_template(
+    repo_name: &str,
+    url: &str,
+    clone_dir: &Path,
+    branch: &str,
+    path: &str,
+    dest: &Path,
+) -> Result<String> {
+    let repo = if clone_dir.exists() {
+        gix::open(clone_dir).context("Failed to open repository clone")?
+    } else {
+        gix::init_bare(clone_dir).context("Failed to create repository clone")?
+    };
+    
+    let tracking = format!("refs/remotes/origin/{}", branch);
+    let refspec = format!("+refs/heads/{}:{}", branch, tracking);
+    let depth = NonZeroU32::new(1).expect("depth is non-zero");
+    repo.remote_at(url)?
+        .with_refspecs(Some(refspec.as_str()), gix::remote::Direction::Fetch)?
+        .connect(gix::remote::Direction::Fetch)
+        .with_context(|| format!("Failed to connect to repository '{}'", repo_name))?
+        .prepare_fetch(gix::progress::Discard, Default::default())?
+        .with_shallow(gix::remote::fetch::Shallow::DepthAtRemote(depth))
+        .receive(gix::progress::Discard, &AtomicBool::new(false))
+        .with_context(|| format!("Failed to fetch branch '{}' of '{}'", branch, repo_name))?;
+    
+    let mut reference = repo
+        .find_reference(tracking.as_str())
+        .map_err(|_| MammothError::BranchNotFound {
+            repo: repo_name.to_string(),
+            branch: branch.to_string(),
+        })?;
+    let commit = reference.peel_to_id_in_place()?.object()?.try_into_commit()?;
+    let commit_id = commit.id.to_string();
+    
+    let path_not_in_repo = || MammothError::PathNotInRepo {
+        repo: repo_name.to_string(),
+        path: path.to_string(),
+    };
+    let root = commit.tree()?;
+    let tree = match path.trim_matches('/') {
+        "" | "." => root,
+        path => root
+            .lookup_entry_by_path(path)?
+            .ok_or_else(path_not_in_repo)?
+            .object()?
+            .try_into_tree()
+            .map_err(|_| path_not_in_repo())?,
+    };
+    
+    write_tree(&repo, &tree, dest)?;
+    Ok(commit_id)
+}
+
+fn write_tree(repo: &gix::Repository, tree: &gix::Tree<'_>, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in tree.iter() {
+        let entry = entry?;
+        let mode = entry.mode();
+        // 子模块不在同一个仓库中，跳过
+        if mode.is_commit() {
+            continue;
+        }
+        
+        let target = dest.join(entry.filename().to_str_lossy().as_ref());
+        let object = repo.find_object(entry.oid())?;
+        if mode.is_tree() {
+            write_tree(repo, &object.try_into_tree()?, &target)?;
+        } else if mode.is_link() {
+            write_symlink(&target, &object.data)?;
+        } else {
+            fs::write(&target, &object.data)
+                .with_context(|| format!("Failed to write {}", target.display()))?;
+            if mode.is_executable() {
+                set_executable(&target)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Symlink blobs hold the link target. Without symlink support the target is written as
+/// a plain file, like git does with `core.symlinks=false`.
+#[cfg(unix)]
+fn write_symlink(target: &Path, link: &[u8]) -> Result<()> {
+    std::os::unix::fs::symlink(link.to_str_lossy().as_ref(), target)
+        .with_context(|| format!("Failed to create symlink {}", target.display()))
+}
+
+#[cfg(not(unix))]
+fn write_symlink(target: &Path, link: &[u8]) -> Result<()> {
+    fs::write(target, link).with_context(|| format!("Failed to write {}", target.display()))
+}
+
+#[cfg(unix)]
+fn set_executable(target: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(target, fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("Failed to make {} executable", target.display()))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_target: &Path) -> Result<()> {
+    Ok(())
+}
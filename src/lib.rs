@@ -1,11 +1,90 @@
+//! Core of mammoth-cli, usable without the command line.
+//!
+//! [`TemplateManager`] loads the configuration and downloads templates, [`new_project`]
+//! generates a project from one. Core operations never write to stdout themselves: they
+//! return their results and report everything else as [`Event`]s, which the CLI prints.
+//! Downloads and project generation report their progress as [`ProgressEvent`]s, drawn
+//! as progress bars by the CLI.
+//! Embedders install their own handler and turn prompts off:
+//!
+//! ```no_run
+//! use mammoth_cli::{new_project, set_event_handler, set_interactive, Event, GenerateOptions, TemplateManager};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! set_interactive(false);
+//! set_event_handler(|event: &Event| match event {
+//!     Event::Warning(text) => eprintln!("mammoth: {}", text),
+//!     _ => {}
+//! });
+//! let mut manager = TemplateManager::new().await?;
+//! let project = new_project(&mut manager, Some("admin"), Some("my-app"), ".", &GenerateOptions::default()).await?;
+//! println!("{}", project.path.display());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Terminal helpers such as [`report_error`] and [`print_timings`] are meant for the CLI.
+
+pub mod browse;
 pub mod cli;
+pub mod community;
+pub mod completions;
+pub mod codemod;
 pub mod config;
+pub mod conflict;
+pub mod credentials;
+pub mod diff;
+pub mod ecosystem;
+pub mod errors;
+pub mod events;
+pub mod git;
+#[cfg(feature = "gix")]
+pub mod gix_backend;
+#[cfg(feature = "git2")]
+pub mod git2_backend;
+pub mod license;
+pub mod lockfile;
 pub mod manager;
+pub mod manifest;
+pub mod npm;
+pub mod package_manager;
+pub mod popularity;
 pub mod project;
+pub mod publish;
+pub mod provider;
+pub mod source;
+pub mod templatize;
+pub mod theme;
+pub mod timings;
+pub mod ui;
 pub mod utils;
 
+pub use browse::*;
 pub use cli::*;
+pub use community::*;
+pub use completions::*;
+pub use codemod::*;
 pub use config::*;
+pub use conflict::*;
+pub use credentials::*;
+pub use diff::*;
+pub use ecosystem::*;
+pub use errors::*;
+pub use events::*;
+pub use git::*;
+pub use license::*;
+pub use lockfile::*;
 pub use manager::*;
+pub use manifest::*;
+pub use npm::*;
+pub use package_manager::*;
+pub use popularity::*;
 pub use project::*;
+pub use publish::*;
+pub use provider::*;
+pub use source::*;
+pub use templatize::*;
+pub use theme::*;
+pub use timings::*;
+pub use ui::*;
 pub use utils::*; 
\ No newline at end of file
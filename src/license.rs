@@ -0,0 +1,85 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+use crate::utils::list_files;
+
+/// Marker used to detect files that already carry a license header
+const SPDX_MARKER: &str = "SPDX-License-Identifier:";
+
+/// Check that `license` looks like an SPDX expression, e.g. "MIT" or "Apache-2.0 OR MIT"
+pub fn validate_license(license: &str) -> Result<()> {
+    let valid = !license.trim().is_empty()
+        && license
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | ' ' | '(' | ')'));
+    if !valid {
+        anyhow::bail!(
+            "Invalid license '{}', expected an SPDX identifier such as MIT or Apache-2.0",
+            license
+        );
+    }
+    Ok(())
+}
+
+/// License header for a file extension in the comment syntax of that file type
+fn license_header(extension: &str, license: &str, copyright: &str) -> Option<String> {
+    let lines = [format!("{} {}", SPDX_MARKER, license), copyright.to_string()];
+    match extension {
+        "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs" => Some(
+            lines
+                .iter()
+                .map(|line| format!("// {}\n", line))
+                .collect(),
+        ),
+        "css" | "scss" | "less" => Some(format!("/*\n * {}\n * {}\n */\n", lines[0], lines[1])),
+        "vue" | "svelte" | "html" => Some(format!("<!--\n  {}\n  {}\n-->\n", lines[0], lines[1])),
+        _ => None,
+    }
+}
+
+/// Insert an SPDX license and copyright header at the top of every source file of a
+/// known type, keeping shebang lines and `<!DOCTYPE>` first. Files that already have an
+/// SPDX header and `node_modules` are skipped. Returns the number of files changed.
+pub fn insert_license_headers(project_path: &Path, license: &str, author: &str) -> Result<usize> {
+    validate_license(license)?;
+    let year = chrono::Local::now().format("%Y");
+    let copyright = format!("Copyright (c) {} {}", year, author);
+    
+    let mut changed = 0;
+    for relative in list_files(project_path)? {
+        if relative.components().any(|c| c.as_os_str() == "node_modules") {
+            continue;
+        }
+        let extension = relative
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let header = match license_header(&extension, license, &copyright) {
+            Some(header) => header,
+            None => continue,
+        };
+        
+        let path = project_path.join(&relative);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        if content.contains(SPDX_MARKER) {
+            continue;
+        }
+        
+        // 保留 shebang 和 <!DOCTYPE> 在第一行
+        let keep_first_line = content.starts_with("#!")
+            || content.trim_start().to_lowercase().starts_with("<!doctype");
+        let updated = match content.split_once('\n') {
+            Some((first, rest)) if keep_first_line => format!("{}\n{}{}", first, header, rest),
+            _ => format!("{}{}", header, content),
+        };
+        fs::write(&path, updated)?;
+        changed += 1;
+    }
+    
+    Ok(changed)
+}
@@ -0,0 +1,158 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{is_false, GenerateOptions, ProjectConfig, Template};
+use crate::manager::TemplateManager;
+use crate::utils::unix_now;
+
+/// Lock file written into every generated project
+pub const LOCK_FILE: &str = "mammoth.lock";
+
+/// Template revision a project was generated from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LockedLayer {
+    /// Qualified template ID, e.g. "aio-templates/admin"
+    pub template: String,
+    /// Repository URL at generation time
+    pub url: String,
+    pub path: String,
+    /// Commit the files came from; the archive checksum for archive repositories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+/// Everything needed to recreate a project exactly: template revisions, prompt
+/// answers and generation options. `regenerate` reads it back.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProjectLock {
+    /// mammoth-cli version that generated the project
+    pub cli_version: String,
+    /// Unix timestamp of the generation
+    pub generated_at: u64,
+    /// Qualified ID of the template picked for the project
+    pub template: String,
+    /// Qualified IDs of the addons applied over the template, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addons: Vec<String>,
+    pub name: String,
+    pub author: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub email: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub author_url: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub community_files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+    /// Dependency ranges were bumped to the newest npm versions, which depends on the day
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub latest_deps: bool,
+    /// Answers to the variables declared by the template manifests
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, String>,
+    /// Layers in the order they were applied: the picked template after its
+    /// dependencies, then the addons
+    #[serde(default, rename = "layer")]
+    pub layers: Vec<LockedLayer>,
+}
+
+impl ProjectLock {
+    /// Lock of a project generated from `layers`, each with the commit it was fetched at
+    pub fn record(
+        manager: &TemplateManager,
+        config: &ProjectConfig,
+        options: &GenerateOptions,
+        layers: &[(&Template, Option<String>)],
+    ) -> Self {
+        ProjectLock {
+            cli_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: unix_now(),
+            template: config.template.qualified_id(),
+            addons: config.addons.iter().map(|addon| addon.qualified_id()).collect(),
+            name: config.name.clone(),
+            author: config.author.clone(),
+            email: config.email.clone(),
+            author_url: config.author_url.clone(),
+            description: config.description.clone(),
+            license: options.license.clone(),
+            community_files: options.community_files.clone(),
+            org: options.org.clone(),
+            latest_deps: options.latest_deps,
+            values: config.values.clone(),
+            layers: layers
+                .iter()
+                .map(|(template, commit)| {
+                    let repo = manager.get_repo_by_name(&template.repo);
+                    LockedLayer {
+                        template: template.qualified_id(),
+                        url: repo.map(|repo| repo.url.clone()).unwrap_or_default(),
+                        path: repo.map_or_else(|| template.path.clone(), |repo| template.repo_path(repo)),
+                        commit: commit.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+    
+    /// Read `mammoth.lock` from a project directory, or a lock file given directly
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = if path.is_dir() { path.join(LOCK_FILE) } else { path.to_path_buf() };
+        let content = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read lock file: {}", file.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse lock file: {}", file.display()))
+    }
+    
+    pub fn write(&self, project_dir: &Path) -> Result<()> {
+        let content = format!(
+            "# Written by mammoth-cli, recreate the project with: mammoth-cli regenerate\n{}",
+            toml::to_string_pretty(self)?
+        );
+        fs::write(project_dir.join(LOCK_FILE), content).context("Failed to write mammoth.lock")
+    }
+    
+    /// Prompt answers of the lock for generating its template and addons into `output_dir`
+    pub fn project_config(&self, manager: &TemplateManager, output_dir: &str) -> Result<ProjectConfig> {
+        let configured = |id: &String| {
+            manager
+                .find_template(id)
+                .cloned()
+                .with_context(|| format!("Template '{}' from {} is not configured", id, LOCK_FILE))
+        };
+        Ok(ProjectConfig {
+            name: self.name.clone(),
+            author: self.author.clone(),
+            email: self.email.clone(),
+            author_url: self.author_url.clone(),
+            description: self.description.clone(),
+            output_dir: output_dir.to_string(),
+            template: configured(&self.template)?,
+            addons: self.addons.iter().map(configured).collect::<Result<_>>()?,
+            values: self.values.clone(),
+        })
+    }
+    
+    /// Generation options of the lock, fetching the locked commits
+    pub fn generate_options(&self) -> GenerateOptions {
+        GenerateOptions {
+            community_files: self.community_files.clone(),
+            org: self.org.clone(),
+            license: self.license.clone(),
+            locked_commits: self.commits(),
+            ..GenerateOptions::default()
+        }
+    }
+    
+    /// Locked commit per qualified template ID
+    pub fn commits(&self) -> BTreeMap<String, String> {
+        self.layers
+            .iter()
+            .filter_map(|layer| Some((layer.template.clone(), layer.commit.clone()?)))
+            .collect()
+    }
+}
@@ -1,38 +1,266 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
 
 use mammoth_cli::{
-    cli::{Cli, Commands, ConfigCommands, RepoCommands, TemplateCommands},
+    browse::browse_templates,
+    cli::{CacheCommands, Cli, Commands, ConfigCommands, RegistryCommands, RepoCommands, TemplateCommands},
+    community::parse_community_files,
+    completions::{print_completions, COMPLETE_VAR},
+    config::{ExistingDirPolicy, ExportFilter, GenerateOptions, PromptValues, Repo, RepoUpdate, Template, TemplateFilter, TemplateUpdate},
+    conflict::ConflictPolicy,
+    diff::DiffFormat,
+    errors::report_error,
+    events::set_status_to_stderr,
+    license::validate_license,
     manager::TemplateManager,
-    project::new_project,
+    project::{diff_project, new_project, regenerate_project, update_project, upgrade_project},
+    publish::{publish_template, template_lint, PublishTarget, DEFAULT_MAX_FILE_SIZE},
+    templatize::{extract_vars, init_template, templatize},
+    timings::{print_timings, record_timing, set_timings_enabled},
+    ui::{detect_ci, init_color, init_logging, set_ci_mode, set_interactive, set_offline, set_plain_mode, set_prompt_timeout},
+    utils::{parse_duration, parse_size, parse_tags},
 };
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
+    // 由 `completions --dynamic` 注册的脚本在补全时回调这里
+    CompleteEnv::with_factory(Cli::command).var(COMPLETE_VAR).complete();
     let cli = Cli::parse();
+    init_color();
+    init_logging(cli.verbose, cli.quiet);
+    // CI 环境下自动关闭进度条和交互提示
+    let ci = cli.ci || detect_ci();
+    set_ci_mode(ci);
+    set_interactive(!ci && !cli.yes);
+    set_plain_mode(cli.plain);
+    set_offline(cli.offline);
+    set_timings_enabled(cli.timings);
     
-    let mut manager = TemplateManager::new()?;
+    let result = run(&cli).await;
+    if cli.timings {
+        print_timings();
+    }
+    if let Err(e) = result {
+        report_error(&e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: &Cli) -> Result<()> {
+    let started = Instant::now();
+    let loaded = TemplateManager::new().await;
+    record_timing("config load", started);
+    // doctor 需要在配置文件损坏时也能运行
+    if let Some(Commands::Doctor { json }) = &cli.command {
+        return TemplateManager::doctor(loaded, *json).await;
+    }
+    let mut manager = loaded?;
+    manager.apply_git_settings()?;
+    if let Some(timeout) = cli.prompt_timeout.as_ref().or(manager.config.prompt_timeout.as_ref()) {
+        set_prompt_timeout(parse_duration(timeout)?);
+    }
     
     match &cli.command {
         Some(Commands::New {
             template,
+            with,
             name,
             output,
+            no_cache,
+            language,
+            latest_deps,
+            community,
+            org,
+            license,
+            keep_history,
+            json,
+            values,
+            install,
+            no_install,
+            force,
+            merge,
+        }) => {
+            if let Some(license) = license {
+                validate_license(license)?;
+            }
+            // stdout 只留给 JSON 摘要
+            if *json {
+                set_status_to_stderr(true);
+            }
+            let community_files = match community {
+                Some(list) => parse_community_files(list)?,
+                None => manager.config.defaults.community_files.clone(),
+            };
+            let options = GenerateOptions {
+                no_cache: *no_cache,
+                language: language.clone(),
+                addons: with
+                    .iter()
+                    .flat_map(|list| list.split(','))
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(String::from)
+                    .collect(),
+                latest_deps: *latest_deps,
+                community_files,
+                org: org.clone().or_else(|| manager.config.defaults.org.clone()),
+                license: license.clone(),
+                keep_history: *keep_history,
+                verbose: cli.verbose > 0,
+                json: *json,
+                values: match values {
+                    Some(path) => PromptValues::load(Path::new(path))?,
+                    None => PromptValues::default(),
+                },
+                install: match (*install, *no_install) {
+                    (true, _) => Some(true),
+                    (_, true) => Some(false),
+                    _ => None,
+                },
+                existing: match (*force, *merge) {
+                    (true, _) => ExistingDirPolicy::Force,
+                    (_, true) => ExistingDirPolicy::Merge,
+                    _ => ExistingDirPolicy::Abort,
+                },
+                locked_commits: Default::default(),
+                skip_git_init: false,
+            };
+            new_project(&mut manager, template.as_deref(), name.as_deref(), output, &options).await?;
+        }
+        Some(Commands::Completions { shell, dynamic }) => {
+            print_completions(*shell, *dynamic)?;
+        }
+        Some(Commands::Diff {
+            template,
+            dir,
+            stat,
+            name_only,
+        }) => {
+            let format = if *stat {
+                DiffFormat::Stat
+            } else if *name_only {
+                DiffFormat::NameOnly
+            } else {
+                DiffFormat::Full
+            };
+            diff_project(&manager, template, dir, format)?;
+        }
+        Some(Commands::Upgrade {
+            template,
+            dir,
+            on_conflict,
+        }) => {
+            let policy: ConflictPolicy = on_conflict.parse()?;
+            upgrade_project(&manager, template, dir, policy).await?;
+        }
+        Some(Commands::Update { dir, dry_run }) => {
+            update_project(&manager, dir, *dry_run, None).await?;
+        }
+        Some(Commands::Regenerate { lock, output, force }) => {
+            let existing = if *force {
+                ExistingDirPolicy::Force
+            } else {
+                ExistingDirPolicy::Abort
+            };
+            regenerate_project(&manager, lock, output, existing).await?;
+        }
+        Some(Commands::Templatize {
+            dir,
+            output,
+            register,
+            id,
+            path,
+            extract_vars,
         }) => {
-            new_project(&mut manager, template.as_deref(), name.as_deref(), output).await?;
+            templatize(
+                &mut manager,
+                dir,
+                output.as_deref(),
+                register.as_deref(),
+                id.as_deref(),
+                path.as_deref(),
+                *extract_vars,
+            )?;
+        }
+        Some(Commands::Undo) => {
+            manager.undo()?;
+        }
+        Some(Commands::Log { limit }) => {
+            manager.show_log(*limit)?;
         }
         Some(Commands::Template { command }) => match command {
-            TemplateCommands::List { verbose } => {
-                manager.list_templates(*verbose);
+            TemplateCommands::List {
+                verbose,
+                language,
+                tag,
+                repo,
+                popularity,
+                json,
+            } => {
+                let filter = TemplateFilter {
+                    query: None,
+                    tag: tag.clone(),
+                    language: language.clone(),
+                    repo: repo.clone(),
+                };
+                let stats = if *popularity {
+                    manager.fetch_template_popularity(&filter).await
+                } else {
+                    HashMap::new()
+                };
+                manager.list_templates(*verbose, *json, &filter, &stats)?;
             }
-            TemplateCommands::Download { template_id, force } => {
-                let template = manager
-                    .get_template_by_id(template_id)
-                    .ok_or_else(|| anyhow::anyhow!("Template '{}' not found", template_id))?;
-                manager.download_template(template, *force).await?;
+            TemplateCommands::Search {
+                query,
+                verbose,
+                language,
+                tag,
+                repo,
+                json,
+            } => {
+                let filter = TemplateFilter {
+                    query: Some(query.clone()),
+                    tag: tag.clone(),
+                    language: language.clone(),
+                    repo: repo.clone(),
+                };
+                manager.list_templates(*verbose, *json, &filter, &HashMap::new())?;
+            }
+            TemplateCommands::ExtractVars { dir } => {
+                extract_vars(Path::new(dir))?;
+            }
+            TemplateCommands::Init { dir, toml } => {
+                init_template(dir, *toml)?;
+            }
+            TemplateCommands::Download {
+                template_id,
+                select,
+                force,
+                no_cache,
+                git_ref,
+            } => {
+                if let (Some(template_id), Some(git_ref)) = (template_id, git_ref) {
+                    manager.pin_template(template_id, git_ref)?;
+                }
+                // clap 保证未使用 --select 时提供了模板 ID
+                let template = match template_id {
+                    Some(template_id) if !*select => manager.find_template(template_id)?,
+                    _ => return manager.download_selected_templates(*force).await,
+                };
+                if *no_cache {
+                    for layer in manager.resolve_dependencies(template)? {
+                        manager.refresh_template_uncached(layer).await?;
+                    }
+                } else {
+                    manager.download_template_with_dependencies(template, *force).await?;
+                }
             }
-            TemplateCommands::DownloadAll { force } => {
-                manager.download_all_templates(*force).await?;
+            TemplateCommands::DownloadAll { force, concurrency } => {
+                manager.download_all_templates(*force, *concurrency).await?;
             }
             TemplateCommands::Add {
                 template_id,
@@ -42,27 +270,168 @@ async fn main() -> Result<()> {
                 description,
                 language,
                 tags,
+                branch,
+                git_ref,
+                depends_on,
+                category,
+                color,
+                icon,
+                download,
+            } => {
+                manager.add_template(Template {
+                    id: template_id.clone(),
+                    name: name.clone().into(),
+                    repo: repo.clone(),
+                    path: path.clone(),
+                    description: description.clone().into(),
+                    language: language.clone(),
+                    tags: parse_tags(tags.as_deref()),
+                    branch: branch.clone(),
+                    git_ref: git_ref.clone(),
+                    dependencies: parse_tags(depends_on.as_deref()),
+                    category: category.clone(),
+                    color: color.clone(),
+                    icon: icon.clone(),
+                    disabled: false,
+                })?;
+                if *download || manager.config.settings.auto_download {
+                    manager.warm_template_cache(template_id).await;
+                }
+            }
+            TemplateCommands::Update {
+                template_id,
+                name,
+                repo,
+                path,
+                description,
+                language,
+                tags,
+                branch,
+                git_ref,
+                depends_on,
+                category,
+                color,
+                icon,
             } => {
-                manager.add_template(
-                    template_id.clone(),
-                    name.clone(),
-                    repo.clone(),
-                    path.clone(),
-                    description.clone(),
-                    language.clone(),
-                    tags.clone(),
+                manager.update_template(
+                    template_id,
+                    TemplateUpdate {
+                        name: name.clone(),
+                        repo: repo.clone(),
+                        path: path.clone(),
+                        description: description.clone(),
+                        language: language.clone(),
+                        tags: tags.as_deref().map(|t| parse_tags(Some(t))),
+                        branch: branch.clone(),
+                        git_ref: git_ref.clone(),
+                        dependencies: depends_on.as_deref().map(|d| parse_tags(Some(d))),
+                        category: category.clone(),
+                        color: color.clone(),
+                        icon: icon.clone(),
+                    },
                 )?;
             }
+            TemplateCommands::Show { template_id } => {
+                let template = manager.find_template(template_id)?;
+                manager.show_template(template);
+            }
+            TemplateCommands::Outdated { json } => {
+                manager.outdated_templates(*json).await?;
+            }
+            TemplateCommands::Changelog { template_id } => {
+                let template = manager.find_template(template_id)?;
+                manager.show_changelog(template).await?;
+            }
+            TemplateCommands::Disable { template_id } => {
+                manager.set_template_disabled(template_id, true)?;
+            }
+            TemplateCommands::Enable { template_id } => {
+                manager.set_template_disabled(template_id, false)?;
+            }
+            TemplateCommands::Duplicate {
+                template_id,
+                new_id,
+                path,
+                branch,
+                with_cache,
+            } => {
+                manager.duplicate_template(template_id, new_id, path.clone(), branch.clone(), *with_cache)?;
+            }
             TemplateCommands::Remove { template_id } => {
                 manager.remove_template(template_id)?;
             }
+            TemplateCommands::Lint {
+                target,
+                max_file_size,
+                json,
+            } => {
+                template_lint(&manager, target, max_file_size.as_deref(), *json)?;
+            }
+            TemplateCommands::Publish {
+                dir,
+                repo,
+                path,
+                branch,
+                registry_url,
+                token,
+                max_file_size,
+                dry_run,
+            } => {
+                let target = match (repo, registry_url) {
+                    (Some(name), _) => Some(PublishTarget::Repo {
+                        name: name.clone(),
+                        // "." 也要取到真实目录名
+                        path: match path {
+                            Some(path) => path.clone(),
+                            None => Path::new(dir)
+                                .canonicalize()?
+                                .file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .ok_or_else(|| anyhow::anyhow!("Pass --path for the template path in the repository"))?,
+                        },
+                        branch: branch.clone(),
+                    }),
+                    (None, Some(url)) => Some(PublishTarget::Registry {
+                        url: url.clone(),
+                        token: token.clone().or_else(|| std::env::var("MAMMOTH_REGISTRY_TOKEN").ok()),
+                    }),
+                    (None, None) => None,
+                };
+                let max_file_size = match max_file_size {
+                    Some(size) => parse_size(size)?,
+                    None => DEFAULT_MAX_FILE_SIZE,
+                };
+                publish_template(&manager, dir, target, max_file_size, *dry_run).await?;
+            }
         },
-        Some(Commands::Clean { all, force }) => {
-            manager.clean_templates(*all, *force)?;
+        Some(Commands::Clean {
+            all,
+            force,
+            trash,
+            permanent,
+        }) => {
+            manager.clean_templates(*all, *force, trash_choice(*trash, *permanent))?;
+        }
+        Some(Commands::Browse { language, output }) => {
+            // 先退出全屏界面再开始生成
+            let picked = browse_templates(&manager, language.as_deref())?.map(|t| t.qualified_id());
+            if let Some(id) = picked {
+                let options = GenerateOptions {
+                    community_files: manager.config.defaults.community_files.clone(),
+                    org: manager.config.defaults.org.clone(),
+                    verbose: cli.verbose > 0,
+                    ..GenerateOptions::default()
+                };
+                new_project(&mut manager, Some(&id), None, output, &options).await?;
+            }
         }
         Some(Commands::Info { json }) => {
             manager.show_info(*json)?;
         }
+        Some(Commands::Env { json }) => {
+            manager.show_env(*json)?;
+        }
+        Some(Commands::Doctor { .. }) => unreachable!("doctor runs before the config is required"),
         Some(Commands::Repo { command }) => match command {
             RepoCommands::Add {
                 repo_name,
@@ -70,39 +439,161 @@ async fn main() -> Result<()> {
                 branch,
                 username,
                 auth_token,
+                ssh_key,
+                path_prefix,
+            } => {
+                manager.add_repo(Repo {
+                    name: repo_name.clone(),
+                    url: url.clone(),
+                    branch: branch.clone(),
+                    auth_token: auth_token.clone(),
+                    username: username.clone(),
+                    keyring: None,
+                    ssh_key: ssh_key.clone(),
+                    path_prefix: path_prefix.clone(),
+                    disabled: false,
+                    size_hint: None,
+                    provider: None,
+                })?;
+            }
+            RepoCommands::Update {
+                repo_name,
+                url,
+                branch,
+                path_prefix,
+                size_hint,
+                provider,
+                ssh_key,
+            } => {
+                manager.update_repo(
+                    repo_name,
+                    RepoUpdate {
+                        url: url.clone(),
+                        branch: branch.clone(),
+                        path_prefix: path_prefix.clone(),
+                        size_hint: size_hint.clone(),
+                        provider: provider.clone(),
+                        ssh_key: ssh_key.clone(),
+                    },
+                )?;
+            }
+            RepoCommands::Auth {
+                repo_name,
+                username,
+                token_stdin,
+                remove,
             } => {
-                manager.add_repo(repo_name.clone(), url.clone(), branch.clone(), username.clone(), auth_token.clone())?;
+                manager.auth_repo(repo_name, username.clone(), *token_stdin, *remove)?;
+            }
+            RepoCommands::Disable { repo_name } => {
+                manager.set_repo_disabled(repo_name, true)?;
+            }
+            RepoCommands::Enable { repo_name } => {
+                manager.set_repo_disabled(repo_name, false)?;
             }
             RepoCommands::Remove { repo_name } => {
                 manager.remove_repo(repo_name)?;
             }
-            RepoCommands::List => {
-                manager.list_repos();
+            RepoCommands::List { json } => {
+                manager.list_repos(*json)?;
+            }
+            RepoCommands::Sync { repo_name, download } => {
+                manager.sync_repo(repo_name, *download).await?;
+            }
+            RepoCommands::Verify { repo_name, all: _ } => {
+                manager.verify_repos(repo_name.as_deref()).await?;
             }
         },
         Some(Commands::Config { command }) => match command {
             ConfigCommands::Export {
                 output,
                 include_cache,
+                reveal_secrets,
+                only,
+                repo,
+                tag,
             } => {
-                manager.export_config(output, *include_cache)?;
+                let filter = ExportFilter {
+                    only: only.clone(),
+                    repo: repo.clone(),
+                    tag: tag.clone(),
+                };
+                manager.export_config(output, *include_cache, *reveal_secrets, &filter)?;
             }
             ConfigCommands::Import {
                 file,
                 mode,
                 skip_validation,
+                sha256,
             } => {
-                manager.import_config(file, mode, *skip_validation)?;
+                manager
+                    .import_config(file, mode, *skip_validation, sha256.as_deref())
+                    .await?;
             }
             ConfigCommands::Validate { file } => {
                 manager.validate_config_file(file)?;
             }
+            ConfigCommands::Migrate { file } => {
+                manager.migrate_config(file.as_deref())?;
+            }
+            ConfigCommands::Set { key, value } => {
+                manager.set_setting(key, value)?;
+            }
+        },
+        Some(Commands::Cache { command }) => match command {
+            CacheCommands::Stats { json } => {
+                manager.cache_stats(*json)?;
+            }
+            CacheCommands::Gc {
+                max_size,
+                unused_for,
+                dry_run,
+                trash,
+                permanent,
+            } => {
+                manager.cache_gc(
+                    max_size.as_deref(),
+                    unused_for.as_deref(),
+                    *dry_run,
+                    trash_choice(*trash, *permanent),
+                )?;
+            }
+            CacheCommands::Clean {
+                template_id,
+                repo,
+                trash,
+                permanent,
+            } => {
+                manager.cache_clean(template_id.as_deref(), repo.as_deref(), trash_choice(*trash, *permanent))?;
+            }
+        },
+        Some(Commands::Registry { command }) => match command {
+            RegistryCommands::Add { name, url, sync } => {
+                manager.add_registry(name, url, *sync).await?;
+            }
+            RegistryCommands::Sync { name } => {
+                manager.sync_registries(name.as_deref()).await?;
+            }
+            RegistryCommands::List { json } => {
+                manager.list_registries(*json)?;
+            }
+            RegistryCommands::Remove { name, prune } => {
+                manager.remove_registry(name, *prune)?;
+            }
         },
         None => {
             // Default to new project creation
-            new_project(&mut manager, None, None, ".").await?;
+            new_project(&mut manager, None, None, ".", &GenerateOptions::default()).await?;
         }
     }
     
     Ok(())
-} 
\ No newline at end of file
+} 
+/// `--trash` / `--permanent` as an explicit choice, `None` for the default of the command
+fn trash_choice(trash: bool, permanent: bool) -> Option<bool> {
+    match (trash, permanent) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
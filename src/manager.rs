@@ -1,455 +1,2714 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use anyhow::{Context, Result};
-use indicatif::{ProgressBar, ProgressStyle};
-use crate::config::{Config, Repo, Template};
-use crate::utils::copy_directory;
+use crate::codemod::apply_code_patch;
+use crate::community::parse_community_files;
+use crate::config::{canonical_language, group_by_category, normalize_language, validate_picker_order, AuditEntry, CacheMeta, Config, ExportFilter, JournalEntry, Registry, Repo, RepoUpdate, Template, TemplateFilter, TemplateUpdate, TemplateUsage, CONFIG_VERSION};
+use crate::errors::MammothError;
+use crate::events::{Progress, ProgressKind};
+use crate::credentials::{delete_keyring_token, store_keyring_token};
+use crate::git::{authenticated_url, init_hosts, redact_credentials, redact_git_error, repo_credentials, run_git, run_remote_git, short_sha};
+use crate::git::{git_backend, git_binary, set_git_backend, set_git_binary, GitBackend};
+use crate::manifest::{evaluate_condition, MergeRule, TemplateManifest, TemplateVariable, MANIFEST_FILE, MANIFEST_TOML_FILE};
+use crate::popularity::{fetch_repo_popularity, fetch_repo_size, Popularity};
+use crate::provider::{Provider, RepoLocation};
+use crate::source::{download_archive, extract_archive, local_repo_url, probe_archive, ArchiveSource, RepoSource};
+use crate::utils::{add_package_dependencies, copy_directory, current_user, dir_size, ensure_disk_space, http_client, list_files, matching_paths, path_matches, merge_json, merge_package_json, parse_size, remove_path, format_duration, format_size, interpolate, parse_color, parse_duration, patch_package_json, sha256_hex, tool_version, unix_now, verify_sha256};
 use colored::*;
-use dialoguer::Confirm;
+use crate::theme::{init_theme, Theme, Themed};
+use crate::timings::record_timing;
+use crate::{output, status, warning};
+use crate::ui::{current_locale, ensure_online, is_ci_mode, is_interactive, is_offline, is_plain_mode, missing_input, plain_text, prompt_timed_out};
+use dialoguer::{Confirm, Input, MultiSelect, Password};
+use serde::Serialize;
+use tokio::sync::Semaphore;
 use serde_json;
 
+/// Free space required before cloning a repository of unknown size
+const MIN_FREE_SPACE: u64 = 100 * 1024 * 1024;
+
 pub struct TemplateManager {
     pub config: Config,
+    /// Entries inherited through `extends`, not written back on save
+    inherited: Config,
+    /// Project-local layer on top of the user's config, not written back on save
+    project: Option<ProjectLayer>,
     cache_dir: PathBuf,
 }
 
+/// File names of a project-local config, looked up from the working directory upwards
+pub const PROJECT_CONFIG_FILES: &[&str] = &[".mammothrc", "mammoth.config.toml"];
+
+/// Project-local config layered over the user's config
+#[derive(Debug, Clone)]
+pub struct ProjectLayer {
+    pub path: PathBuf,
+    /// The project file with its own `extends` resolved
+    pub config: Config,
+    /// Entries of the user's config replaced by the project file, restored on save
+    shadowed: Config,
+}
+
+/// Guard against `extends` cycles
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// Number of config snapshots kept for `undo`
+const MAX_JOURNAL_ENTRIES: usize = 20;
+
+/// Time allowed for downloading a config from a URL: `extends`, `import` and registries
+const CONFIG_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Templates downloaded at the same time unless `--concurrency` says otherwise
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Commit recorded for templates copied from a local directory repository
+const LOCAL_COMMIT: &str = "local";
+
+/// Result of checking one repository with `git ls-remote`
+#[derive(Debug, Serialize, Clone)]
+pub struct RepoHealth {
+    pub repo: String,
+    pub reachable: bool,
+    pub auth_ok: bool,
+    pub branch_exists: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RepoHealth {
+    pub fn is_healthy(&self) -> bool {
+        self.reachable && self.auth_ok && self.branch_exists
+    }
+}
+
+/// Commit at the tip of `branch` on the remote, `None` when the branch does not exist
+async fn remote_branch_head(repo: &Repo, branch: &str) -> Result<Option<String>> {
+    let heads = run_remote_git(
+        repo,
+        &std::env::temp_dir(),
+        &["ls-remote", "--heads", &authenticated_url(repo), branch],
+        Duration::from_secs(30),
+    )
+    .await
+    .map_err(|e| redact_git_error(e, repo))?;
+    Ok(heads.split_whitespace().next().map(str::to_string))
+}
+
+/// Archive repositories have no branches; a HEAD request for the archive checks that it
+/// is reachable with the configured credentials
+async fn check_archive_health(repo: &Repo, archive: &ArchiveSource) -> RepoHealth {
+    let started = std::time::Instant::now();
+    let result = probe_archive(repo, archive).await;
+    let mut health = RepoHealth {
+        repo: repo.name.clone(),
+        reachable: false,
+        auth_ok: false,
+        branch_exists: false,
+        latency_ms: started.elapsed().as_millis() as u64,
+        error: None,
+    };
+    match result {
+        Ok(status) => {
+            health.reachable = true;
+            health.auth_ok = status != reqwest::StatusCode::UNAUTHORIZED && status != reqwest::StatusCode::FORBIDDEN;
+            health.branch_exists = status.is_success();
+            if !status.is_success() {
+                health.error = Some(format!("Archive request returned {}", status));
+            }
+        }
+        Err(e) => health.error = Some(redact_credentials(&e.to_string(), repo)),
+    }
+    health
+}
+
+/// Ask the remote for the repository branch, telling unreachable hosts apart from
+/// rejected credentials by git's error message
+async fn check_repo_health(repo: &Repo) -> RepoHealth {
+    match RepoSource::of(repo) {
+        RepoSource::Local(root) => {
+            let exists = root.is_dir();
+            return RepoHealth {
+                repo: repo.name.clone(),
+                reachable: exists,
+                auth_ok: exists,
+                branch_exists: exists,
+                latency_ms: 0,
+                error: (!exists).then(|| format!("Directory '{}' not found", root.display())),
+            };
+        }
+        RepoSource::Archive(archive) => return check_archive_health(repo, &archive).await,
+        RepoSource::Git => {}
+    }
+    let started = std::time::Instant::now();
+    let url = authenticated_url(repo);
+    let result = run_remote_git(
+        repo,
+        &std::env::temp_dir(),
+        &["ls-remote", "--heads", &url, &repo.branch],
+        Duration::from_secs(30),
+    )
+    .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    
+    let mut health = RepoHealth {
+        repo: repo.name.clone(),
+        reachable: false,
+        auth_ok: false,
+        branch_exists: false,
+        latency_ms,
+        error: None,
+    };
+    match result {
+        Ok(heads) => {
+            health.reachable = true;
+            health.auth_ok = true;
+            health.branch_exists = !heads.is_empty();
+            if !health.branch_exists {
+                health.error = Some(format!("Branch '{}' not found", repo.branch));
+            }
+        }
+        Err(e) => {
+            let message = redact_credentials(&e.to_string(), repo);
+            const AUTH_ERRORS: [&str; 6] = [
+                "Authentication failed",
+                "could not read Username",
+                "terminal prompts disabled",
+                "Permission denied",
+                "401",
+                "403",
+            ];
+            health.reachable = AUTH_ERRORS.iter().any(|pattern| message.contains(pattern));
+            health.error = Some(message);
+        }
+    }
+    health
+}
+
+/// What a cache entry holds
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheEntryKind {
+    /// Files of one template
+    Template,
+    /// Repository clone kept for incremental fetches
+    Clone,
+}
+
+/// One template or repository clone in the cache directory
+#[derive(Debug, Serialize, Clone)]
+pub struct CacheEntry {
+    /// "repo/id" for templates, the repository name for clones
+    pub key: String,
+    pub kind: CacheEntryKind,
+    pub path: PathBuf,
+    pub size: u64,
+    /// Unix timestamp of the last fetch, the directory mtime without metadata
+    pub fetched_at: Option<u64>,
+    /// Unix timestamp of the last project generated from it
+    pub last_used: Option<u64>,
+    /// Whether the template or repository is still in the config
+    pub configured: bool,
+}
+
+impl CacheEntry {
+    fn label(&self) -> String {
+        match self.kind {
+            CacheEntryKind::Template => self.key.clone(),
+            CacheEntryKind::Clone => format!("{} (clone)", self.key),
+        }
+    }
+    
+    /// Last time the entry was used or refreshed, 0 when unknown
+    fn last_activity(&self) -> u64 {
+        self.last_used.max(self.fetched_at).unwrap_or(0)
+    }
+}
+
+/// Repository clones shared by the downloads of one batch, by repository name
+struct BatchClones {
+    repos: HashMap<String, BatchClone>,
+}
+
+impl BatchClones {
+    fn new(manager: &TemplateManager, templates: &[&Template]) -> Self {
+        let mut repos: HashMap<String, BatchClone> = HashMap::new();
+        for template in templates {
+            let Some(repo) = manager.get_repo_by_name(&template.repo) else {
+                continue;
+            };
+            let clone = repos.entry(repo.name.clone()).or_insert_with(|| BatchClone {
+                paths: Vec::new(),
+                checkout: tokio::sync::RwLock::new(None),
+            });
+            let path = template.repo_path(repo);
+            if !clone.paths.contains(&path) {
+                clone.paths.push(path);
+            }
+        }
+        Self { repos }
+    }
+}
+
+/// One repository clone of a batch. It is fetched and checked out once with the paths
+/// of all templates of the batch; the templates are then copied out of the worktree
+/// under the read lock.
+struct BatchClone {
+    /// Sparse checkout paths of the templates of the batch
+    paths: Vec<String>,
+    /// Ref the worktree is checked out at and its commit, `None` before the first
+    /// checkout or after a failed one
+    checkout: tokio::sync::RwLock<Option<(String, String)>>,
+}
+
+type CheckoutGuard<'a> = tokio::sync::RwLockReadGuard<'a, Option<(String, String)>>;
+
+impl BatchClone {
+    /// Sync the clone unless it is already checked out at the ref of `template`,
+    /// returning the commit and a guard keeping the worktree unchanged
+    async fn sync<'a>(
+        &'a self,
+        manager: &TemplateManager,
+        template: &Template,
+        repo: &Repo,
+        clone_dir: &Path,
+        pb: &Progress,
+    ) -> Result<(String, CheckoutGuard<'a>)> {
+        let revision = template.effective_ref(repo);
+        let checkout = self.checkout.read().await;
+        if let Some((checked_out, commit)) = checkout.as_ref() {
+            if checked_out == revision {
+                pb.inc(60);
+                return Ok((commit.clone(), checkout));
+            }
+        }
+        drop(checkout);
+        
+        // 等待写锁期间其他模板可能已检出同一 ref
+        let mut checkout = self.checkout.write().await;
+        let commit = match checkout.as_ref() {
+            Some((checked_out, commit)) if checked_out == revision => {
+                pb.inc(60);
+                commit.clone()
+            }
+            _ => {
+                *checkout = None;
+                let commit = manager
+                    .sync_repo_clone(template, repo, clone_dir, &self.paths, pb)
+                    .await?;
+                *checkout = Some((revision.to_string(), commit.clone()));
+                commit
+            }
+        };
+        Ok((commit, checkout.downgrade()))
+    }
+    
+    /// Hold the clone alone, for downloads that write into the clone directory
+    async fn exclusive(
+        clone: Option<&BatchClone>,
+    ) -> Option<tokio::sync::RwLockWriteGuard<'_, Option<(String, String)>>> {
+        match clone {
+            Some(clone) => {
+                let mut checkout = clone.checkout.write().await;
+                *checkout = None;
+                Some(checkout)
+            }
+            None => None,
+        }
+    }
+}
+
+/// Severity of a `doctor` finding
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// One finding of `doctor`, with the action that resolves it
+#[derive(Debug, Serialize, Clone)]
+pub struct DoctorCheck {
+    pub check: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn new(check: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            check: check.to_string(),
+            status,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+    
+    fn with_fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+/// Oldest git with `sparse-checkout`, used for every template download
+const MIN_GIT_VERSION: (u64, u64) = (2, 25);
+
+/// Free space below which `doctor` warns about the cache volume
+const LOW_DISK_SPACE: u64 = 500 * 1024 * 1024;
+
+/// New project-relative path of a renamed template file, `to` interpolated with `vars`
+fn rename_target(to: &str, vars: &HashMap<String, String>) -> Result<PathBuf> {
+    let target = interpolate(to, vars);
+    let target = target.trim().trim_matches('/');
+    if target.is_empty() || Path::new(target).is_absolute() || target.split('/').any(|s| s == "..") {
+        anyhow::bail!("'{}' must be a relative path inside the project", target);
+    }
+    Ok(PathBuf::from(target))
+}
+
+/// Absolute path of an SSH key given on the command line, with `~` expanded. git runs
+/// in the clone directory, so a relative path would not be found there.
+fn resolve_ssh_key(key: &str) -> Result<String> {
+    let path = match key.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Cannot expand '~' in SSH key path: no home directory"))?
+            .join(rest),
+        None => PathBuf::from(key),
+    };
+    if !path.is_file() {
+        anyhow::bail!("SSH key not found: {}", key);
+    }
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve SSH key path {}", key))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Whether an `extends` entry is fetched over HTTP rather than read from disk
+fn is_config_url(source: &str) -> bool {
+    source.starts_with("https://") || source.starts_with("http://")
+}
+
+/// Directory from an environment variable, ignored when unset or empty
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).filter(|dir| !dir.is_empty()).map(PathBuf::from)
+}
+
+/// Major and minor version from `git --version` output, e.g. "git version 2.39.2.windows.1"
+fn parse_git_version(output: &str) -> Option<(u64, u64)> {
+    let version = output.split_whitespace().find(|word| word.starts_with(|c: char| c.is_ascii_digit()))?;
+    let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+    Some((parts.next()??, parts.next()??))
+}
+
+/// Combine `path`, just written by a template layer, with the `existing` content an
+/// earlier layer wrote there
+fn merge_layer_file(path: &Path, rule: MergeRule, existing: Vec<u8>) -> Result<()> {
+    let merged = match rule {
+        MergeRule::Replace => return Ok(()),
+        MergeRule::Keep => existing,
+        MergeRule::Append => {
+            let mut merged = existing;
+            if !merged.is_empty() && !merged.ends_with(b"\n") {
+                merged.push(b'\n');
+            }
+            merged.extend(fs::read(path)?);
+            merged
+        }
+        MergeRule::Json => {
+            let mut base: serde_json::Value =
+                serde_json::from_slice(&existing).context("Earlier layer's file is not valid JSON")?;
+            let layer: serde_json::Value = serde_json::from_slice(&fs::read(path)?).context("Not valid JSON")?;
+            merge_json(&mut base, &layer);
+            serde_json::to_string_pretty(&base)?.into_bytes()
+        }
+    };
+    fs::write(path, merged)?;
+    Ok(())
+}
+
 impl TemplateManager {
-    pub fn new() -> Result<Self> {
+    pub async fn new() -> Result<Self> {
         let config_path = Self::get_config_path()?;
-        let config = if config_path.exists() {
-            let content = fs::read_to_string(&config_path).context("Failed to read config file")?;
-            serde_json::from_str(&content).context("Failed to parse config file")?
-        } else {
-            Config {
-                repos: vec![],
-                templates: vec![],
+        let local = Self::load_local_config()?;
+        if !config_path.exists() && Self::get_legacy_config_path()?.exists() {
+            Self::migrate_legacy_config(&local)?;
+        }
+        
+        let config_dir = config_path.parent().unwrap_or(Path::new("."));
+        let inherited = Self::resolve_extends(&local.extends, config_dir, true, 0).await?;
+        let mut config = inherited.clone();
+        config.overlay(local.clone());
+        config.extends = local.extends;
+        
+        let project = match Self::find_project_config() {
+            Some(path) => Some(Self::load_project_config(path, &config).await?),
+            None => None,
+        };
+        if let Some(project) = &project {
+            config.overlay(project.config.clone());
+        }
+        init_theme(&config.theme);
+        init_hosts(&config.hosts);
+        
+        let cache_dir = Self::get_cache_dir()?;
+        fs::create_dir_all(&cache_dir)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::PermissionDenied => {
+                    anyhow::Error::new(MammothError::CachePermissionDenied { path: cache_dir.clone() })
+                }
+                _ => anyhow::Error::new(e),
+            })
+            .context("Failed to create cache directory")?;
+        
+        Ok(Self {
+            config,
+            inherited,
+            project,
+            cache_dir,
+        })
+    }
+    
+    /// First `.mammothrc` or `mammoth.config.toml` in the working directory or its parents
+    fn find_project_config() -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        cwd.ancestors()
+            .flat_map(|dir| PROJECT_CONFIG_FILES.iter().map(move |name| dir.join(name)))
+            .find(|path| path.is_file())
+    }
+    
+    /// Read a project config (TOML or JSON) and its `extends`, relative to its directory
+    async fn load_project_config(path: PathBuf, global: &Config) -> Result<ProjectLayer> {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read project config: {}", path.display()))?;
+        let layer = Config::parse(&content)
+            .with_context(|| format!("Failed to parse project config {}", path.display()))?;
+        let project_dir = path.parent().unwrap_or(Path::new("."));
+        // 项目配置来自工作目录，可能属于不可信的仓库：不拉取远程配置，只采用仓库、模板和默认值
+        let mut config = Self::resolve_extends(&layer.extends, project_dir, false, 0).await?;
+        config.overlay(layer);
+        let (config, ignored) = config.project_layer();
+        if !ignored.is_empty() {
+            warning!(
+                "⚠️  Ignoring {} in project config {}, set them in your own config instead",
+                ignored.join(", "),
+                path.display()
+            );
+        }
+        tracing::info!(
+            "project config {} ({} repositories, {} templates)",
+            path.display(),
+            config.repos.len(),
+            config.templates.len()
+        );
+        
+        Ok(ProjectLayer {
+            shadowed: global.shadowed_by(&config),
+            path,
+            config,
+        })
+    }
+    
+    /// The project-local config in effect, if any
+    pub fn project_config(&self) -> Option<&ProjectLayer> {
+        self.project.as_ref()
+    }
+    
+    /// Load and merge every `extends` layer (and their own `extends`) in order. URLs are
+    /// only followed when `remote` is set, i.e. not for project configs.
+    async fn resolve_extends(extends: &[String], base_dir: &Path, remote: bool, depth: usize) -> Result<Config> {
+        if depth >= MAX_EXTENDS_DEPTH {
+            anyhow::bail!("Config 'extends' nested more than {} levels, is there a cycle?", MAX_EXTENDS_DEPTH);
+        }
+        
+        let mut resolved = Config::default();
+        for source in extends {
+            if !remote && is_config_url(source) {
+                warning!("⚠️  Skipping extended config '{}': project configs can only extend local files", source);
+                continue;
+            }
+            let (layer, layer_dir) = match Self::load_config_layer(source, base_dir).await {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    warning!("⚠️  Skipping extended config '{}': {:#}", source, e);
+                    continue;
+                }
+            };
+            
+            let mut layer_config = Box::pin(Self::resolve_extends(&layer.extends, &layer_dir, remote, depth + 1)).await?;
+            layer_config.overlay(layer);
+            resolved.overlay(layer_config);
+        }
+        
+        Ok(resolved)
+    }
+    
+    /// Read a config layer from a URL or a path relative to `base_dir`
+    async fn load_config_layer(source: &str, base_dir: &Path) -> Result<(Config, PathBuf)> {
+        if is_config_url(source) {
+            ensure_online(&format!("load extended config {}", source))?;
+            let content = http_client(CONFIG_DOWNLOAD_TIMEOUT)?
+                .get(source)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            let config = Config::parse(&content).context("Failed to parse extended config")?;
+            return Ok((config, base_dir.to_path_buf()));
+        }
+        
+        let path = base_dir.join(source);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read extended config: {}", path.display()))?;
+        let config = Config::parse(&content).context("Failed to parse extended config")?;
+        let layer_dir = path.parent().map(Path::to_path_buf).unwrap_or_else(|| base_dir.to_path_buf());
+        Ok((config, layer_dir))
+    }
+    
+    /// The user's own config file without `extends` layers, empty when there is none.
+    /// Falls back to the `templates.json` of version 1 until it has been migrated.
+    pub fn load_local_config() -> Result<Config> {
+        let mut config_path = Self::get_config_path()?;
+        if !config_path.exists() {
+            config_path = Self::get_legacy_config_path()?;
+        }
+        if !config_path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(&config_path).context("Failed to read config file")?;
+        Config::parse(&content).with_context(|| format!("Failed to parse config file {}", config_path.display()))
+    }
+    
+    /// `MAMMOTH_CONFIG_DIR`, else `mammoth-cli` in the platform config directory
+    fn get_config_dir() -> Result<PathBuf> {
+        let config_dir = env_dir("MAMMOTH_CONFIG_DIR").unwrap_or_else(|| {
+            dirs::config_dir()
+                .unwrap_or_else(|| PathBuf::from(".config"))
+                .join("mammoth-cli")
+        });
+        fs::create_dir_all(&config_dir).context("Failed to create config directory")?;
+        Ok(config_dir)
+    }
+    
+    fn get_config_path() -> Result<PathBuf> {
+        Ok(Self::get_config_dir()?.join("config.toml"))
+    }
+    
+    /// JSON config file of version 1, replaced by `config.toml`
+    fn get_legacy_config_path() -> Result<PathBuf> {
+        Ok(Self::get_config_dir()?.join("templates.json"))
+    }
+    
+    /// Write the loaded `templates.json` as `config.toml` and keep the original as
+    /// `templates.json.bak`
+    fn migrate_legacy_config(local: &Config) -> Result<()> {
+        let legacy_path = Self::get_legacy_config_path()?;
+        let config_path = Self::get_config_path()?;
+        fs::write(&config_path, local.to_toml()?).context("Failed to write config file")?;
+        let backup = legacy_path.with_extension("json.bak");
+        fs::rename(&legacy_path, &backup).context("Failed to back up templates.json")?;
+        warning!(
+            "📦 Configuration migrated to {} (version {}), the old file is kept as {}",
+            config_path.display(),
+            CONFIG_VERSION,
+            backup.display()
+        );
+        Ok(())
+    }
+    
+    /// Convert a JSON config file (e.g. a shared `extends` layer or an export) to TOML next
+    /// to it, or migrate the user's config when no file is given
+    pub fn migrate_config(&self, file: Option<&str>) -> Result<()> {
+        let Some(file) = file else {
+            let config_path = Self::get_config_path()?;
+            let content = fs::read_to_string(&config_path).unwrap_or_default();
+            let version = toml::from_str::<Config>(&content).ok().map(|config| config.config_version);
+            if version == Some(CONFIG_VERSION) {
+                status!("✨ {} is already at version {}", config_path.display(), CONFIG_VERSION);
+                return Ok(());
+            }
+            self.save_config()?;
+            status!("✅ {} migrated to version {}", config_path.display(), CONFIG_VERSION);
+            return Ok(());
+        };
+        
+        let source = Path::new(file);
+        let content = fs::read_to_string(source)
+            .with_context(|| format!("Failed to read configuration file: {}", file))?;
+        let config = Config::parse(&content).context("Failed to parse configuration file")?;
+        let target = source.with_extension("toml");
+        if target == source {
+            status!("✨ {} is already TOML", file);
+            return Ok(());
+        }
+        fs::write(&target, config.to_toml()?)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        status!("✅ Wrote {} (version {})", target.display(), CONFIG_VERSION);
+        output!(
+            "{}",
+            plain_text("💡 Update 'extends' entries that point to the JSON file to use the new path")
+        );
+        Ok(())
+    }
+    
+    /// `templates` in `MAMMOTH_CACHE_DIR`, else in `mammoth-cli` in the platform cache directory
+    fn get_cache_dir() -> Result<PathBuf> {
+        let cache_dir = env_dir("MAMMOTH_CACHE_DIR")
+            .unwrap_or_else(|| {
+                dirs::cache_dir()
+                    .unwrap_or_else(|| PathBuf::from(".cache"))
+                    .join("mammoth-cli")
+            })
+            .join("templates");
+        Ok(cache_dir)
+    }
+    
+    pub fn save_config(&self) -> Result<()> {
+        let config_path = Self::get_config_path()?;
+        let mut config = self.config.clone();
+        if let Some(project) = &self.project {
+            // 项目配置的条目留在项目文件中，被它覆盖的用户条目原样写回
+            config = config.without_inherited(&project.config);
+            config.overlay(project.shadowed.clone());
+        }
+        let local = config.without_inherited(&self.inherited);
+        let content = local.to_toml().context("Failed to serialize config")?;
+        tracing::debug!("write config {}", config_path.display());
+        fs::write(config_path, content).context("Failed to write config file")?;
+        Ok(())
+    }
+    
+    /// Select the git backend and executable from the settings; `MAMMOTH_GIT` overrides
+    /// `settings.git_path`
+    pub fn apply_git_settings(&self) -> Result<()> {
+        let git_path = std::env::var("MAMMOTH_GIT")
+            .ok()
+            .filter(|path| !path.is_empty())
+            .or_else(|| self.config.settings.git_path.clone());
+        if let Some(backend) = &self.config.settings.git_backend {
+            set_git_backend(backend.parse()?)?;
+        }
+        // gix/git2 后端不需要 git 可执行文件
+        if let Some(git_path) = git_path.filter(|_| git_backend() == GitBackend::Cli) {
+            set_git_binary(&git_path)?;
+        }
+        Ok(())
+    }
+    
+    fn get_journal_dir() -> Result<PathBuf> {
+        let config_path = Self::get_config_path()?;
+        Ok(config_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("history"))
+    }
+    
+    /// Snapshot the config file as it is on disk before `operation` changes it
+    fn record_snapshot(&self, operation: &str) -> Result<()> {
+        let config_path = Self::get_config_path()?;
+        let journal_dir = Self::get_journal_dir()?;
+        fs::create_dir_all(&journal_dir).context("Failed to create history directory")?;
+        
+        let entry = JournalEntry {
+            timestamp: unix_now(),
+            operation: operation.to_string(),
+            previous: fs::read_to_string(&config_path).ok(),
+        };
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let content = serde_json::to_string_pretty(&entry).context("Failed to serialize history entry")?;
+        fs::write(journal_dir.join(format!("{:020}.json", millis)), content)
+            .context("Failed to write history entry")?;
+        
+        // 只保留最近的快照
+        let entries = Self::journal_entries()?;
+        if entries.len() > MAX_JOURNAL_ENTRIES {
+            for old in &entries[..entries.len() - MAX_JOURNAL_ENTRIES] {
+                fs::remove_file(old).ok();
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Journal files, oldest first
+    fn journal_entries() -> Result<Vec<PathBuf>> {
+        let journal_dir = Self::get_journal_dir()?;
+        if !journal_dir.exists() {
+            return Ok(vec![]);
+        }
+        
+        let mut entries: Vec<PathBuf> = fs::read_dir(&journal_dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        entries.sort();
+        Ok(entries)
+    }
+    
+    /// Journal the current state and persist the change made by `operation`
+    fn commit_change(&self, operation: &str) -> Result<()> {
+        self.record_snapshot(operation)?;
+        self.save_config()?;
+        self.record_audit(operation)
+    }
+    
+    fn get_audit_log_path() -> Result<PathBuf> {
+        let config_path = Self::get_config_path()?;
+        Ok(config_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("audit.log"))
+    }
+    
+    /// Append a state-changing operation to the audit log
+    pub fn record_audit(&self, operation: &str) -> Result<()> {
+        let entry = AuditEntry {
+            time: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            user: current_user(),
+            operation: operation.to_string(),
+        };
+        let line = serde_json::to_string(&entry).context("Failed to serialize audit entry")?;
+        
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::get_audit_log_path()?)
+            .context("Failed to open audit log")?;
+        writeln!(file, "{}", line).context("Failed to write audit log")?;
+        Ok(())
+    }
+    
+    fn get_usage_path() -> Result<PathBuf> {
+        let config_path = Self::get_config_path()?;
+        Ok(config_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("usage.json"))
+    }
+    
+    /// Template usage keyed by qualified id, empty when nothing was recorded yet
+    fn load_usage() -> BTreeMap<String, TemplateUsage> {
+        Self::get_usage_path()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+    
+    /// Count a project generated from `template` for the picker ordering
+    pub fn record_usage(&self, template: &Template) -> Result<()> {
+        let mut usage = Self::load_usage();
+        let entry = usage.entry(template.qualified_id()).or_default();
+        entry.count += 1;
+        entry.last_used = unix_now();
+        
+        let content = serde_json::to_string_pretty(&usage).context("Failed to serialize usage")?;
+        fs::write(Self::get_usage_path()?, content).context("Failed to write usage file")?;
+        Ok(())
+    }
+    
+    /// Order templates for the interactive picker: most used recently first (unused ones
+    /// keep config order), or by ID when `settings.picker_order` is "alphabetical"
+    pub fn sort_for_picker(&self, templates: &mut [&Template]) {
+        if self.config.settings.picker_order.as_deref() == Some("alphabetical") {
+            templates.sort_by_key(|t| t.id.to_lowercase());
+            return;
+        }
+        let usage = Self::load_usage();
+        let now = unix_now();
+        let score = |t: &Template| usage.get(&t.qualified_id()).map_or(0.0, |u| u.score(now));
+        templates.sort_by(|a, b| score(b).total_cmp(&score(a)));
+    }
+    
+    /// Print the most recent `limit` audit log entries
+    pub fn show_log(&self, limit: usize) -> Result<()> {
+        let log_path = Self::get_audit_log_path()?;
+        if !log_path.exists() {
+            output!("No operations recorded yet");
+            return Ok(());
+        }
+        
+        let content = fs::read_to_string(&log_path).context("Failed to read audit log")?;
+        let entries: Vec<AuditEntry> = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        
+        output!("{}", "📜 Operation Log".header());
+        output!();
+        for entry in entries.iter().skip(entries.len().saturating_sub(limit)) {
+            output!("{}  {}  {}", entry.time.dimmed(), entry.user.bold(), entry.operation);
+        }
+        
+        Ok(())
+    }
+    
+    /// Revert the most recent configuration change
+    pub fn undo(&mut self) -> Result<()> {
+        let latest = match Self::journal_entries()?.pop() {
+            Some(latest) => latest,
+            None => {
+                output!("Nothing to undo");
+                return Ok(());
+            }
+        };
+        
+        let content = fs::read_to_string(&latest).context("Failed to read history entry")?;
+        let entry: JournalEntry =
+            serde_json::from_str(&content).context("Failed to parse history entry")?;
+        
+        let config_path = Self::get_config_path()?;
+        match &entry.previous {
+            Some(previous) => {
+                // 迁移前的快照是 JSON，恢复时转换为当前格式
+                let restored = Config::parse(previous).context("Failed to parse history entry")?;
+                fs::write(&config_path, restored.to_toml()?).context("Failed to restore config file")?
+            }
+            None => {
+                if config_path.exists() {
+                    fs::remove_file(&config_path).context("Failed to remove config file")?;
+                }
+            }
+        }
+        fs::remove_file(&latest).context("Failed to remove history entry")?;
+        self.record_audit(&format!("undo {}", entry.operation))?;
+        
+        status!("↩️  Reverted: {}", entry.operation);
+        Ok(())
+    }
+    
+    /// Look up a template by bare ID or by "repo/id"
+    pub fn find_template(&self, reference: &str) -> Result<&Template> {
+        let index = self.config.template_index(reference)?;
+        Ok(&self.config.templates[index])
+    }
+    
+    pub fn get_template_by_id(&self, id: &str) -> Option<&Template> {
+        self.find_template(id).ok()
+    }
+    
+    /// The template and its dependencies in application order, dependencies first
+    pub fn resolve_dependencies<'a>(&'a self, template: &'a Template) -> Result<Vec<&'a Template>> {
+        let mut ordered = Vec::new();
+        let mut visiting = Vec::new();
+        self.visit_dependencies(template, &mut visiting, &mut ordered)?;
+        Ok(ordered)
+    }
+    
+    /// Layers of a template composed with addons: the template and its dependencies,
+    /// then each addon with the dependencies not applied yet, in the given order
+    pub fn compose_layers<'a>(&'a self, template: &'a Template, addons: &'a [Template]) -> Result<Vec<&'a Template>> {
+        let mut ordered = Vec::new();
+        let mut visiting = Vec::new();
+        self.visit_dependencies(template, &mut visiting, &mut ordered)?;
+        for addon in addons {
+            self.visit_dependencies(addon, &mut visiting, &mut ordered)?;
+        }
+        Ok(ordered)
+    }
+    
+    fn visit_dependencies<'a>(
+        &'a self,
+        template: &'a Template,
+        visiting: &mut Vec<String>,
+        ordered: &mut Vec<&'a Template>,
+    ) -> Result<()> {
+        let qualified_id = template.qualified_id();
+        if ordered.iter().any(|t| t.qualified_id() == qualified_id) {
+            return Ok(());
+        }
+        if let Some(start) = visiting.iter().position(|id| id == &qualified_id) {
+            let mut cycle = visiting[start..].to_vec();
+            cycle.push(qualified_id);
+            anyhow::bail!("Template dependency cycle: {}", cycle.join(" -> "));
+        }
+        
+        visiting.push(qualified_id);
+        for dependency_id in &template.dependencies {
+            let dependency = self.find_template(dependency_id).with_context(|| {
+                format!(
+                    "Template '{}' depends on missing template '{}'",
+                    template.id,
+                    dependency_id
+                )
+            })?;
+            self.visit_dependencies(dependency, visiting, ordered)?;
+        }
+        visiting.pop();
+        
+        ordered.push(template);
+        Ok(())
+    }
+    
+    pub fn get_repo_by_name(&self, name: &str) -> Option<&Repo> {
+        self.config.repos.iter().find(|r| r.name == name)
+    }
+    
+    fn get_template_cache_path(&self, template: &Template) -> PathBuf {
+        self.cache_dir.join(&template.repo).join(&template.id)
+    }
+    
+    fn get_template_meta_path(&self, template: &Template) -> PathBuf {
+        self.cache_dir
+            .join(&template.repo)
+            .join(format!("{}.meta.json", template.id))
+    }
+    
+    fn read_cache_meta(&self, template: &Template) -> Option<CacheMeta> {
+        let content = fs::read_to_string(self.get_template_meta_path(template)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+    
+    fn write_cache_meta(&self, template: &Template, meta: &CacheMeta) -> Result<()> {
+        let content = serde_json::to_string_pretty(meta).context("Failed to serialize cache metadata")?;
+        fs::write(self.get_template_meta_path(template), content)
+            .context("Failed to write cache metadata")?;
+        Ok(())
+    }
+    
+    /// Age of a cached template, based on its metadata or the cache directory mtime
+    pub fn cache_age(&self, template: &Template) -> Option<Duration> {
+        let fetched_at = match self.read_cache_meta(template) {
+            Some(meta) => meta.fetched_at,
+            None => fs::metadata(self.get_template_cache_path(template))
+                .and_then(|m| m.modified())
+                .ok()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?
+                .as_secs(),
+        };
+        Some(Duration::from_secs(unix_now().saturating_sub(fetched_at)))
+    }
+    
+    /// Age of a cached template if it is older than `cache.max_age`
+    fn stale_cache_age(&self, template: &Template) -> Result<Option<Duration>> {
+        let max_age = match self.config.cache.max_age().context("Invalid cache.max_age")? {
+            Some(max_age) => max_age,
+            None => return Ok(None),
+        };
+        
+        Ok(self.cache_age(template).filter(|age| *age > max_age))
+    }
+    
+    /// Download a template along with every template it depends on
+    pub async fn download_template_with_dependencies(&self, template: &Template, force: bool) -> Result<()> {
+        for layer in self.resolve_dependencies(template)? {
+            self.download_template(layer, force).await?;
+        }
+        Ok(())
+    }
+    
+    pub async fn download_template(&self, template: &Template, force: bool) -> Result<()> {
+        let pb = Progress::start(ProgressKind::Download, template.id.clone(), 100);
+        self.download_template_with_progress(template, force, None, &pb).await
+    }
+    
+    /// Download a template reporting everything through `pb`, so that it can be
+    /// reported alone or as one download of a batch
+    async fn download_template_with_progress(
+        &self,
+        template: &Template,
+        force: bool,
+        batch: Option<&BatchClones>,
+        pb: &Progress,
+    ) -> Result<()> {
+        let cache_path = self.get_template_cache_path(template);
+        
+        if cache_path.exists() && !force {
+            // 固定的 ref 变了，缓存内容不再对应配置，必须重新获取
+            let cached_ref = self.read_cache_meta(template).and_then(|meta| meta.git_ref);
+            if cached_ref != template.git_ref {
+                pb.set_message("Pinned ref changed, fetching...");
+                return self.update_cached_template(template, false, batch, pb).await;
+            }
+            
+            let age = match self.stale_cache_age(template)? {
+                Some(age) => age,
+                None => {
+                    tracing::debug!("template '{}' cached at {}", template.id, cache_path.display());
+                    pb.finish("✨ Already cached");
+                    return Ok(());
+                }
+            };
+            
+            if is_offline() && self.needs_network(template) {
+                pb.finish(format!(
+                    "⚠️  Cached copy is {} old, using it in offline mode",
+                    format_duration(age)
+                ));
+                return Ok(());
+            }
+            
+            if !self.config.cache.auto_refresh {
+                pb.finish(format!(
+                    "⚠️  Cached copy is {} old, use --force to refresh",
+                    format_duration(age)
+                ));
+                return Ok(());
+            }
+            
+            pb.set_message(format!("Cached copy is {} old, refreshing...", format_duration(age)));
+            if let Err(e) = self.update_cached_template(template, false, batch, pb).await {
+                // 刷新失败时继续使用旧缓存
+                warning!(
+                    "⚠️  Failed to refresh template '{}', using cached copy: {}",
+                    template.id, e
+                );
+            }
+            return Ok(());
+        }
+        
+        self.update_cached_template(template, force, batch, pb).await
+    }
+    
+    /// Whether fetching the template goes over the network, i.e. it is not in a local
+    /// directory repository
+    fn needs_network(&self, template: &Template) -> bool {
+        self.get_repo_by_name(&template.repo)
+            .is_none_or(|repo| !RepoSource::of(repo).is_local())
+    }
+    
+    /// Whether `new` can generate from the template under `--offline`: every layer is
+    /// cached or comes from a local directory repository
+    pub fn is_usable_offline(&self, template: &Template) -> bool {
+        match self.resolve_dependencies(template) {
+            Ok(layers) => layers
+                .iter()
+                .all(|layer| self.get_template_cache_path(layer).exists() || !self.needs_network(layer)),
+            Err(_) => false,
+        }
+    }
+    
+    /// Bring the cache entry of a template up to date through the retained repository clone.
+    /// Only changed files are copied, unless `force` asks for a full copy that also repairs
+    /// edited or damaged cache entries.
+    async fn update_cached_template(
+        &self,
+        template: &Template,
+        force: bool,
+        batch: Option<&BatchClones>,
+        pb: &Progress,
+    ) -> Result<()> {
+        let repo = self
+            .get_repo_by_name(&template.repo)
+            .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", template.repo))?;
+        let cache_path = self.get_template_cache_path(template);
+        let clone_dir = self.get_repo_clone_path(repo);
+        let previous_commit = if !force && cache_path.exists() {
+            self.read_cache_meta(template).and_then(|meta| meta.commit)
+        } else {
+            None
+        };
+        
+        let commit = self
+            .fetch_template_into(template, &cache_path, &clone_dir, true, previous_commit, batch, pb)
+            .await?;
+        self.record_fetch(template, commit)
+    }
+    
+    fn get_repo_clone_path(&self, repo: &Repo) -> PathBuf {
+        self.cache_dir.join(".repos").join(&repo.name)
+    }
+    
+    fn record_fetch(&self, template: &Template, commit: String) -> Result<()> {
+        self.write_cache_meta(
+            template,
+            &CacheMeta {
+                fetched_at: unix_now(),
+                commit: Some(commit),
+                git_ref: template.git_ref.clone(),
+            },
+        )
+    }
+    
+    /// Fetch a template straight from its repository into `dest` without reading or
+    /// writing the cache, e.g. for `--no-cache` runs
+    pub async fn fetch_template_uncached(&self, template: &Template, dest: &Path) -> Result<String> {
+        let repo = self
+            .get_repo_by_name(&template.repo)
+            .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", template.repo))?;
+        
+        let pb = Progress::start(ProgressKind::Download, template.id.clone(), 100);
+        let temp_dir = std::env::temp_dir()
+            .join("mammoth-cli")
+            .join(format!("temp_{}_{}", repo.name, template.id));
+        self.fetch_template_into(template, dest, &temp_dir, false, None, None, &pb).await
+    }
+    
+    /// Re-fetch a template with a fresh clone and replace its cache entry
+    pub async fn refresh_template_uncached(&self, template: &Template) -> Result<()> {
+        let cache_path = self.get_template_cache_path(template);
+        let commit = self.fetch_template_uncached(template, &cache_path).await?;
+        self.record_fetch(template, commit)
+    }
+    
+    /// Sync `clone_dir` and extract the template into `dest`, returning the checked out
+    /// commit. A kept clone is fetched incrementally on the next run, otherwise it is a
+    /// throwaway clone removed afterwards. With the `previous_commit` `dest` holds, only
+    /// the files changed since are copied.
+    async fn fetch_template_into(
+        &self,
+        template: &Template,
+        dest: &Path,
+        clone_dir: &Path,
+        keep_clone: bool,
+        previous_commit: Option<String>,
+        batch: Option<&BatchClones>,
+        pb: &Progress,
+    ) -> Result<String> {
+        let repo = self
+            .get_repo_by_name(&template.repo)
+            .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", template.repo))?;
+        
+        if !RepoSource::of(repo).is_local() {
+            ensure_online(&format!("download template '{}'", template.id))?;
+        }
+        tracing::info!("fetch template '{}' from {} into {}", template.id, repo.url, dest.display());
+        pb.set_message("Downloading...");
+        
+        // 一次性克隆前确保清理旧的临时目录
+        if !keep_clone {
+            self.cleanup_temp_dir(clone_dir)?;
+        }
+        
+        // 使用 Result 来确保清理操作
+        let result = self
+            .download_template_internal(template, repo, clone_dir, dest, previous_commit, batch, pb)
+            .await;
+        
+        if let Err(ref e) = result {
+            pb.fail(format!("❌ Download failed: {:#}", e));
+        }
+        
+        // 清理一次性克隆
+        if !keep_clone {
+            self.cleanup_temp_dir(clone_dir)?;
+        }
+        
+        result
+    }
+    
+    /// Rough size of a clone of `repo`: the configured `size_hint`, the size reported by
+    /// the GitHub API, or `MIN_FREE_SPACE` when neither is known
+    async fn estimate_clone_size(&self, repo: &Repo) -> Result<u64> {
+        if let Some(hint) = &repo.size_hint {
+            return parse_size(hint).with_context(|| format!("Invalid size_hint of repository '{}'", repo.name));
+        }
+        Ok(fetch_repo_size(repo)
+            .await
+            .map(|size| size.max(MIN_FREE_SPACE))
+            .unwrap_or(MIN_FREE_SPACE))
+    }
+    
+    /// Make sure `clone_dir` holds a blobless, sparse clone of `repo`. Credentials are
+    /// only passed on the command line and never stored in the clone.
+    async fn ensure_repo_clone(&self, repo: &Repo, clone_dir: &Path) -> Result<()> {
+        if clone_dir.join(".git").exists() {
+            return Ok(());
+        }
+        
+        ensure_disk_space(clone_dir, self.estimate_clone_size(repo).await?)?;
+        self.cleanup_temp_dir(clone_dir)?;
+        fs::create_dir_all(clone_dir).context("Failed to create clone dir")?;
+        
+        let remote_url = authenticated_url(repo);
+        let clone_target = clone_dir.to_string_lossy().to_string();
+        let cwd = clone_dir.parent().unwrap_or(clone_dir);
+        if let Err(e) = run_remote_git(
+            repo,
+            cwd,
+            &[
+                "clone",
+                "--no-checkout",
+                "--filter=blob:none",
+                "--sparse",
+                &remote_url,
+                &clone_target,
+            ],
+            Duration::from_secs(300), // 5分钟超时
+        )
+        .await
+        {
+            let error_msg = if repo_credentials(repo).1.is_some() {
+                format!("Failed to clone private repository: {}. Please check your authentication credentials.", repo.url)
+            } else {
+                format!("Failed to clone repository: {}", repo.url)
+            };
+            return Err(redact_git_error(e, repo).context(error_msg));
+        }
+        
+        // 保留的克隆不应在 .git/config 中保存凭据
+        if remote_url != repo.url {
+            run_git(
+                clone_dir,
+                &["remote", "set-url", "origin", &repo.url],
+                Duration::from_secs(30),
+            )
+            .await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Fetch `branch` into an existing clone, returning the fetched commit
+    async fn fetch_branch(&self, repo: &Repo, clone_dir: &Path, branch: &str) -> Result<String> {
+        let remote_url = authenticated_url(repo);
+        run_remote_git(
+            repo,
+            clone_dir,
+            &["fetch", "--filter=blob:none", &remote_url, branch],
+            Duration::from_secs(300), // 5分钟超时
+        )
+        .await
+        .map_err(|e| {
+            let message = redact_credentials(&e.to_string(), repo);
+            if message.contains("couldn't find remote ref") {
+                anyhow::Error::new(MammothError::BranchNotFound {
+                    repo: repo.name.clone(),
+                    branch: branch.to_string(),
+                })
+            } else {
+                redact_git_error(e, repo)
+            }
+        })
+        .with_context(|| format!("Failed to fetch branch '{}' from {}", branch, repo.url))?;
+        
+        run_git(clone_dir, &["rev-parse", "FETCH_HEAD"], Duration::from_secs(30)).await
+    }
+    
+    /// Fetch the tag or commit a template is pinned to, returning the commit it names
+    async fn fetch_pinned_ref(&self, repo: &Repo, clone_dir: &Path, git_ref: &str) -> Result<String> {
+        let remote_url = authenticated_url(repo);
+        run_remote_git(
+            repo,
+            clone_dir,
+            &["fetch", "--filter=blob:none", "--no-tags", &remote_url, git_ref],
+            Duration::from_secs(300), // 5分钟超时
+        )
+        .await
+        .map_err(|e| {
+            let message = redact_credentials(&e.to_string(), repo);
+            // 不存在的标签报 "couldn't find remote ref"，服务器拒绝的提交报 "not our ref"
+            if message.contains("couldn't find remote ref") || message.contains("not our ref") {
+                anyhow::Error::new(MammothError::RefNotFound {
+                    repo: repo.name.clone(),
+                    git_ref: git_ref.to_string(),
+                })
+            } else {
+                redact_git_error(e, repo)
+            }
+        })
+        .with_context(|| format!("Failed to fetch '{}' from {}", git_ref, repo.url))?;
+        
+        // 附注标签指向标签对象，取它指向的提交
+        run_git(clone_dir, &["rev-parse", "FETCH_HEAD^{commit}"], Duration::from_secs(30)).await
+    }
+    
+    /// Turn `project_path` into a git repository whose history is the commit history of
+    /// the template path, split out with `git subtree split`. Files end up at the project
+    /// root; commits of the template repository outside the path are dropped.
+    pub async fn import_template_history(&self, template: &Template, project_path: &Path) -> Result<()> {
+        let repo = self
+            .get_repo_by_name(&template.repo)
+            .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", template.repo))?;
+        let branch = template.effective_ref(repo);
+        let path = template.repo_path(repo);
+        let path = path.trim_matches('/');
+        
+        ensure_disk_space(project_path, self.estimate_clone_size(repo).await?)?;
+        run_git(project_path, &["init"], Duration::from_secs(30)).await?;
+        let remote_url = authenticated_url(repo);
+        run_remote_git(
+            repo,
+            project_path,
+            &["fetch", "--no-tags", &remote_url, branch],
+            Duration::from_secs(600), // 完整历史，10分钟超时
+        )
+        .await
+        .map_err(|e| redact_git_error(e, repo))
+        .with_context(|| format!("Failed to fetch history of '{}' from {}", branch, repo.url))?;
+        
+        let head = if path.is_empty() || path == "." {
+            "FETCH_HEAD".to_string()
+        } else {
+            run_git(
+                project_path,
+                &["subtree", "split", &format!("--prefix={}", path), "FETCH_HEAD"],
+                Duration::from_secs(600),
+            )
+            .await
+            .with_context(|| format!("Failed to split the history of '{}' (is git-subtree installed?)", path))?
+        };
+        run_git(project_path, &["reset", "--hard", &head], Duration::from_secs(120)).await?;
+        
+        // 丢弃模板路径以外的对象，只保留拆分后的历史
+        fs::remove_file(project_path.join(".git").join("FETCH_HEAD")).ok();
+        run_git(
+            project_path,
+            &["reflog", "expire", "--expire=now", "--all"],
+            Duration::from_secs(60),
+        )
+        .await?;
+        run_git(project_path, &["gc", "--prune=now", "--quiet"], Duration::from_secs(600)).await?;
+        
+        Ok(())
+    }
+    
+    /// Clone `repo` into `clone_dir` (or reuse an existing clone), fetch the template
+    /// branch and check out the template path. Returns the checked out commit.
+    async fn sync_repo_clone(
+        &self,
+        template: &Template,
+        repo: &Repo,
+        clone_dir: &Path,
+        paths: &[String],
+        pb: &Progress,
+    ) -> Result<String> {
+        let revision = template.effective_ref(repo);
+        
+        if clone_dir.join(".git").exists() {
+            pb.set_message("Fetching updates...");
+        } else {
+            // Clone repository with sparse checkout and timeout
+            pb.set_message("Cloning repository...");
+        }
+        pb.inc(30);
+        let started = Instant::now();
+        self.ensure_repo_clone(repo, clone_dir).await?;
+        let commit = match &template.git_ref {
+            Some(git_ref) => self.fetch_pinned_ref(repo, clone_dir, git_ref).await?,
+            None => self.fetch_branch(repo, clone_dir, revision).await?,
+        };
+        record_timing("clone", started);
+        
+        // Set sparse checkout directory
+        pb.set_message("Configuring sparse checkout...");
+        pb.inc(10);
+        let started = Instant::now();
+        let mut args = vec!["sparse-checkout", "set"];
+        args.extend(paths.iter().map(String::as_str));
+        run_git(
+            clone_dir,
+            &args,
+            Duration::from_secs(60), // 1分钟超时
+        )
+        .await
+        .with_context(|| format!("Failed to set sparse checkout for path: {}", paths.join(", ")))?;
+        
+        // Checkout the fetched branch or pinned ref
+        pb.set_message("Checking out files...");
+        pb.inc(20);
+        run_git(
+            clone_dir,
+            &["checkout", "--force", &commit],
+            Duration::from_secs(120), // 2分钟超时
+        )
+        .await
+        .with_context(|| format!("Failed to checkout: {}", revision))?;
+        record_timing("sparse checkout", started);
+        
+        Ok(commit)
+    }
+    
+    /// Print the commits touching a template path between the cached and latest revision
+    pub async fn show_changelog(&self, template: &Template) -> Result<()> {
+        let repo = self
+            .get_repo_by_name(&template.repo)
+            .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", template.repo))?;
+        if !RepoSource::of(repo).is_git() {
+            anyhow::bail!("Repository '{}' is not a git repository and has no commit history", repo.name);
+        }
+        let clone_dir = self.get_repo_clone_path(repo);
+        let branch = template.effective_branch(repo);
+        let path = template.repo_path(repo);
+        
+        status!("🔍 Fetching latest '{}' from {}...", branch, repo.url);
+        self.ensure_repo_clone(repo, &clone_dir).await?;
+        let latest = self.fetch_branch(repo, &clone_dir, branch).await?;
+        
+        let cached = self.read_cache_meta(template).and_then(|meta| meta.commit);
+        let range = match &cached {
+            Some(cached) => format!("{}..{}", cached, latest),
+            None => latest.clone(),
+        };
+        
+        let mut args = vec![
+            "log",
+            "--no-decorate",
+            "--date=short",
+            "--format=%h%x09%ad%x09%an%x09%s",
+        ];
+        if cached.is_none() {
+            args.push("-n20");
+        }
+        args.extend([range.as_str(), "--", path.as_str()]);
+        let log = run_git(&clone_dir, &args, Duration::from_secs(60)).await?;
+        
+        output!();
+        output!("{}", format!("📝 Changelog for '{}'", template.id).header());
+        match &cached {
+            Some(cached) => output!(
+                "Cached: {} → Latest: {}",
+                short_sha(cached).warning(),
+                short_sha(&latest).success()
+            ),
+            None => output!("Not cached yet, showing the latest 20 changes"),
+        }
+        output!();
+        
+        if log.is_empty() {
+            status!("✨ No changes to '{}' since the cached revision", path);
+            return Ok(());
+        }
+        
+        for line in log.lines() {
+            let mut fields = line.splitn(4, '\t');
+            let (sha, date, author, subject) = (
+                fields.next().unwrap_or(""),
+                fields.next().unwrap_or(""),
+                fields.next().unwrap_or(""),
+                fields.next().unwrap_or(""),
+            );
+            output!("{} {} {} {}", sha.warning(), date.dimmed(), author.highlight(), subject);
+        }
+        
+        Ok(())
+    }
+    
+    async fn download_template_internal(
+        &self,
+        template: &Template,
+        repo: &Repo,
+        clone_dir: &Path,
+        dest: &Path,
+        previous_commit: Option<String>,
+        batch: Option<&BatchClones>,
+        pb: &Progress,
+    ) -> Result<String> {
+        let shared = batch.and_then(|batch| batch.repos.get(&repo.name));
+        match RepoSource::of(repo) {
+            RepoSource::Local(root) => return self.copy_local_template(template, repo, &root, dest, pb),
+            RepoSource::Archive(archive) => {
+                // 压缩包每次都解压到克隆目录，同一仓库的模板依次处理
+                let _clone = BatchClone::exclusive(shared).await;
+                return self
+                    .extract_archive_template(template, repo, &archive, clone_dir, dest, previous_commit, pb)
+                    .await;
+            }
+            RepoSource::Git => {}
+        }
+        // 固定的标签或提交只由 git 命令行获取，内置后端只拉取分支
+        #[cfg(feature = "gix")]
+        {
+            if git_backend() == GitBackend::Gix && template.git_ref.is_none() {
+                let _clone = BatchClone::exclusive(shared).await;
+                return self.download_template_gix(template, repo, clone_dir, dest, pb).await;
+            }
+        }
+        #[cfg(feature = "git2")]
+        {
+            if git_backend() == GitBackend::Libgit2 && template.git_ref.is_none() {
+                let _clone = BatchClone::exclusive(shared).await;
+                return self.download_template_git2(template, repo, clone_dir, dest, pb).await;
+            }
+        }
+        let path = template.repo_path(repo);
+        let (commit, _checkout) = match shared {
+            Some(shared) => {
+                // 读锁期间工作区保持不变，同一提交的模板可以并行复制
+                let (commit, checkout) = shared.sync(self, template, repo, clone_dir, pb).await?;
+                (commit, Some(checkout))
+            }
+            None => (
+                self.sync_repo_clone(template, repo, clone_dir, std::slice::from_ref(&path), pb)
+                    .await?,
+                None,
+            ),
+        };
+        
+        let template_source = clone_dir.join(&path);
+        if !template_source.exists() {
+            return Err(MammothError::PathNotInRepo {
+                repo: repo.name.clone(),
+                path,
+            }
+            .into());
+        }
+        
+        // Create target directory
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).context("Failed to create repo cache parent dir")?;
+        }
+        
+        pb.set_message("Copying template files...");
+        pb.inc(20);
+        
+        let message = match previous_commit {
+            Some(previous) if previous == commit => "✨ Already up to date".to_string(),
+            Some(previous) => {
+                // 只提取变化的文件，失败时回退到完整复制
+                match self
+                    .apply_changed_paths(&path, clone_dir, dest, &previous, &commit)
+                    .await
+                {
+                    Ok(count) => format!("✅ Updated {} changed files in: {}", count, dest.display()),
+                    Err(_) => {
+                        self.safe_copy_template_files(&template_source, dest)?;
+                        format!("✅ Downloaded to: {}", dest.display())
+                    }
+                }
+            }
+            None => {
+                // 安全地清理和复制文件
+                self.safe_copy_template_files(&template_source, dest)?;
+                format!("✅ Downloaded to: {}", dest.display())
+            }
+        };
+        
+        pb.finish(message);
+        
+        Ok(commit)
+    }
+    
+    /// Copy a template of a local directory repository straight from disk, so edits show
+    /// up on the next run without committing them
+    fn copy_local_template(
+        &self,
+        template: &Template,
+        repo: &Repo,
+        root: &Path,
+        dest: &Path,
+        pb: &Progress,
+    ) -> Result<String> {
+        let path = template.repo_path(repo);
+        let template_source = root.join(&path);
+        if !template_source.exists() {
+            return Err(MammothError::PathNotInRepo {
+                repo: repo.name.clone(),
+                path,
+            }
+            .into());
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).context("Failed to create repo cache parent dir")?;
+        }
+        
+        pb.set_message("Copying local template files...");
+        let started = Instant::now();
+        self.safe_copy_template_files(&template_source, dest)?;
+        record_timing("copy", started);
+        
+        pb.finish(format!("✅ Copied from {} to: {}", template_source.display(), dest.display()));
+        // 本地目录没有提交，用固定标记代替
+        Ok(LOCAL_COMMIT.to_string())
+    }
+    
+    /// Download the archive of an archive repository, unpack it into `clone_dir` and copy
+    /// the template path into `dest`. The archive checksum stands in for the commit.
+    #[allow(clippy::too_many_arguments)]
+    async fn extract_archive_template(
+        &self,
+        template: &Template,
+        repo: &Repo,
+        archive: &ArchiveSource,
+        clone_dir: &Path,
+        dest: &Path,
+        previous_commit: Option<String>,
+        pb: &Progress,
+    ) -> Result<String> {
+        pb.set_message("Downloading archive...");
+        pb.inc(30);
+        let started = Instant::now();
+        let content = download_archive(repo, archive).await?;
+        record_timing("download", started);
+        let commit = sha256_hex(&content);
+        
+        if previous_commit.as_deref() == Some(commit.as_str()) {
+            pb.finish("✨ Already up to date");
+            return Ok(commit);
+        }
+        
+        pb.set_message("Extracting archive...");
+        pb.inc(30);
+        // 每次都重新解压，避免残留已删除的文件
+        self.cleanup_temp_dir(clone_dir)?;
+        let root = extract_archive(&content, archive.format, clone_dir)?;
+        
+        let path = template.repo_path(repo);
+        let template_source = root.join(&path);
+        if !template_source.exists() {
+            return Err(MammothError::PathNotInRepo {
+                repo: repo.name.clone(),
+                path,
+            }
+            .into());
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).context("Failed to create repo cache parent dir")?;
+        }
+        
+        pb.set_message("Copying template files...");
+        pb.inc(20);
+        let started = Instant::now();
+        self.safe_copy_template_files(&template_source, dest)?;
+        record_timing("copy", started);
+        
+        pb.finish(format!("✅ Downloaded to: {}", dest.display()));
+        Ok(commit)
+    }
+    
+    /// Download through the built-in gitoxide backend: fetch the branch tip into a bare
+    /// clone and write the template tree into a staging directory that replaces `dest`
+    #[cfg(feature = "gix")]
+    async fn download_template_gix(
+        &self,
+        template: &Template,
+        repo: &Repo,
+        clone_dir: &Path,
+        dest: &Path,
+        pb: &Progress,
+    ) -> Result<String> {
+        if !clone_dir.exists() {
+            ensure_disk_space(clone_dir, self.estimate_clone_size(repo).await?)?;
+        }
+        pb.set_message("Fetching with gitoxide...");
+        pb.inc(40);
+        
+        let staging = dest.with_extension("staging");
+        if staging.exists() {
+            fs::remove_dir_all(&staging).context("Failed to clear staging dir")?;
+        }
+        let repo_name = repo.name.clone();
+        let url = authenticated_url(repo);
+        let clone = clone_dir.to_path_buf();
+        let branch = template.effective_branch(repo).to_string();
+        let path = template.repo_path(repo);
+        let target = staging.clone();
+        let started = Instant::now();
+        let commit = tokio::task::spawn_blocking(move || {
+            crate::gix_backend::fetch_template(&repo_name, &url, &clone, &branch, &path, &target)
+        })
+        .await?
+        .map_err(|e| redact_git_error(e, repo))?;
+        record_timing("clone", started);
+        
+        pb.set_message("Copying template files...");
+        pb.inc(40);
+        if dest.exists() {
+            fs::remove_dir_all(dest).context("Failed to remove old template files")?;
+        }
+        fs::rename(&staging, dest).context("Failed to move template files into place")?;
+        
+        pb.finish(format!("✅ Downloaded to: {}", dest.display()));
+        Ok(commit)
+    }
+    
+    /// Download through libgit2: like the gitoxide backend, but the progress bar follows
+    /// the objects received and failures are classified from libgit2's error codes
+    #[cfg(feature = "git2")]
+    async fn download_template_git2(
+        &self,
+        template: &Template,
+        repo: &Repo,
+        clone_dir: &Path,
+        dest: &Path,
+        pb: &Progress,
+    ) -> Result<String> {
+        if !clone_dir.exists() {
+            ensure_disk_space(clone_dir, self.estimate_clone_size(repo).await?)?;
+        }
+        pb.set_message("Fetching with libgit2...");
+        pb.inc(10);
+        
+        let staging = dest.with_extension("staging");
+        if staging.exists() {
+            fs::remove_dir_all(&staging).context("Failed to clear staging dir")?;
+        }
+        let repo_name = repo.name.clone();
+        let url = repo.url.clone();
+        let credentials = repo_credentials(repo);
+        let ssh_key = repo.ssh_key.clone();
+        let clone = clone_dir.to_path_buf();
+        let branch = template.effective_branch(repo).to_string();
+        let path = template.repo_path(repo);
+        let target = staging.clone();
+        let fetch_pb = pb.clone();
+        let started = Instant::now();
+        let commit = tokio::task::spawn_blocking(move || {
+            // 拉取阶段占进度条的 10%..80%
+            let mut progress = |received: usize, total: usize| {
+                if total > 0 {
+                    fetch_pb.set_position(10 + (received * 70 / total) as u64);
+                }
+            };
+            crate::git2_backend::fetch_template(
+                &repo_name,
+                &url,
+                credentials,
+                ssh_key.as_deref(),
+                &clone,
+                &branch,
+                &path,
+                &target,
+                &mut progress,
+            )
+        })
+        .await?
+        .map_err(|e| redact_git_error(e, repo))?;
+        record_timing("clone", started);
+        
+        pb.set_message("Copying template files...");
+        pb.set_position(80);
+        if dest.exists() {
+            fs::remove_dir_all(dest).context("Failed to remove old template files")?;
+        }
+        fs::rename(&staging, dest).context("Failed to move template files into place")?;
+        
+        pb.finish(format!("✅ Downloaded to: {}", dest.display()));
+        Ok(commit)
+    }
+    
+    /// Copy only the files of the template path that changed between two commits
+    async fn apply_changed_paths(
+        &self,
+        template_path: &str,
+        clone_dir: &Path,
+        dest: &Path,
+        from: &str,
+        to: &str,
+    ) -> Result<usize> {
+        let changes = run_git(
+            clone_dir,
+            &["diff", "--name-status", "--no-renames", from, to, "--", template_path],
+            Duration::from_secs(60),
+        )
+        .await?;
+        
+        let prefix = Path::new(template_path);
+        let mut count = 0;
+        for line in changes.lines() {
+            let (status, path) = match line.split_once('\t') {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let relative = match Path::new(path).strip_prefix(prefix) {
+                Ok(relative) => relative,
+                Err(_) => continue,
+            };
+            let target = dest.join(relative);
+            
+            if status.starts_with('D') {
+                if target.exists() {
+                    fs::remove_file(&target)?;
+                }
+            } else {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(clone_dir.join(path), &target)?;
+            }
+            count += 1;
+        }
+        
+        Ok(count)
+    }
+    
+    fn cleanup_temp_dir(&self, temp_dir: &Path) -> Result<()> {
+        if temp_dir.exists() {
+            tracing::debug!("remove temp dir {}", temp_dir.display());
+            // 在 Windows 上，可能需要多次尝试删除
+            for attempt in 1..=3 {
+                match fs::remove_dir_all(temp_dir) {
+                    Ok(_) => {
+                        if attempt > 1 {
+                            status!("✅ Temp directory cleaned on attempt {}", attempt);
+                        }
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        if attempt == 3 {
+                            warning!("⚠️  Warning: Failed to remove temp dir after 3 attempts: {}", e);
+                            return Err(e.into());
+                        }
+                        // 等待一小段时间再重试
+                        std::thread::sleep(std::time::Duration::from_millis(500));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    fn safe_copy_template_files(&self, source: &Path, dest: &Path) -> Result<()> {
+        // 如果目标目录存在，先尝试删除
+        if dest.exists() {
+            // 在 Windows 上，可能需要多次尝试
+            for attempt in 1..=3 {
+                match fs::remove_dir_all(dest) {
+                    Ok(_) => break,
+                    Err(e) => {
+                        if attempt == 3 {
+                            anyhow::bail!("Failed to remove old cache after 3 attempts: {}", e);
+                        }
+                        std::thread::sleep(std::time::Duration::from_millis(500));
+                    }
+                }
+            }
+        }
+        
+        // 复制文件
+        copy_directory(source, dest).context("Failed to copy template files")?;
+        
+        Ok(())
+    }
+    
+    pub async fn download_all_templates(&self, force: bool, concurrency: usize) -> Result<()> {
+        if concurrency == 0 {
+            anyhow::bail!("Concurrency must be at least 1");
+        }
+        status!("🚀 Downloading all templates...");
+        let templates: Vec<&Template> = self.config.templates.iter().collect();
+        self.download_templates(&templates, force, concurrency).await;
+        Ok(())
+    }
+    
+    /// Let the user tick templates in a checklist showing their cache status, then
+    /// download the chosen ones together with their dependencies
+    pub async fn download_selected_templates(&self, force: bool) -> Result<()> {
+        if !is_interactive() {
+            return Err(missing_input("Template selection", "a template ID or use download-all"));
+        }
+        let candidates: Vec<&Template> = self
+            .config
+            .templates
+            .iter()
+            .filter(|t| self.is_template_enabled(t))
+            .collect();
+        if candidates.is_empty() {
+            output!("No templates configured. Add templates first.");
+            return Ok(());
+        }
+        
+        let items: Vec<String> = candidates
+            .iter()
+            .map(|t| format!("{} {} - {}", self.status_marker(t), self.styled_id(t), t.name))
+            .collect();
+        let chosen = MultiSelect::new()
+            .with_prompt("Select templates to download (space to toggle, enter to confirm)")
+            .items(&items)
+            .interact()?;
+        if chosen.is_empty() {
+            output!("Nothing selected.");
+            return Ok(());
+        }
+        
+        // 依赖模板也要下载，去重后保持依赖在前
+        let mut templates: Vec<&Template> = Vec::new();
+        for index in chosen {
+            for layer in self.resolve_dependencies(candidates[index])? {
+                if !templates.iter().any(|t| std::ptr::eq(*t, layer)) {
+                    templates.push(layer);
+                }
             }
+        }
+        status!("🚀 Downloading {} templates...", templates.len());
+        self.download_templates(&templates, force, DEFAULT_CONCURRENCY).await;
+        Ok(())
+    }
+    
+    /// Fetch a newly added template and its dependencies into the cache, so the first
+    /// `new` does not pay the clone cost. A failure only warns, the template stays added.
+    pub async fn warm_template_cache(&self, id: &str) {
+        let result = match self.find_template(id) {
+            Ok(template) => self.download_template_with_dependencies(template, false).await,
+            Err(e) => Err(e),
         };
+        if let Err(e) = result {
+            warning!("⚠️  Failed to download template '{}', it will be fetched on first use: {}", id, e);
+        }
+    }
+    
+    /// Bring the cached templates of a repository up to date, and with `download` also
+    /// fetch the ones that were never downloaded
+    pub async fn sync_repo(&self, name: &str, download: bool) -> Result<()> {
+        if self.get_repo_by_name(name).is_none() {
+            anyhow::bail!("Repository '{}' not found", name);
+        }
+        let templates: Vec<&Template> = self
+            .config
+            .templates
+            .iter()
+            .filter(|t| t.repo == name && self.is_template_enabled(t))
+            .filter(|t| download || self.get_template_cache_path(t).exists())
+            .collect();
+        if templates.is_empty() {
+            output!("No cached templates in repository '{}', add --download to fetch them", name);
+            return Ok(());
+        }
         
-        let cache_dir = Self::get_cache_dir()?;
-        fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
+        status!("🔄 Syncing {} templates of repository '{}'...", templates.len(), name);
+        self.download_templates(&templates, true, DEFAULT_CONCURRENCY).await;
+        Ok(())
+    }
+    
+    /// Download `templates` with one progress bar each and an overall bar, up to
+    /// `concurrency` at a time, reporting failures at the end instead of stopping at the
+    /// first one. Templates of the same repository share its clone and run one by one.
+    async fn download_templates(&self, templates: &[&Template], force: bool, concurrency: usize) {
+        let overall = Progress::start(ProgressKind::Batch, "Overall", templates.len() as u64);
+        
+        let semaphore = &Semaphore::new(concurrency.max(1));
+        let batch = &BatchClones::new(self, templates);
+        let overall = &overall;
+        let downloads = templates.iter().map(|template| {
+            let pb = Progress::start(ProgressKind::Download, template.id.clone(), 100);
+            pb.set_message("Waiting...");
+            async move {
+                // 同一仓库只拉取并检出一次，之后各模板并行复制
+                let _permit = semaphore.acquire().await;
+                let result = self
+                    .download_template_with_progress(template, force, Some(batch), &pb)
+                    .await;
+                overall.inc(1);
+                (template.id.clone(), result)
+            }
+        });
+        let results = futures::future::join_all(downloads).await;
+        overall.finish("done");
         
-        Ok(Self { config, cache_dir })
+        let failed: Vec<(String, anyhow::Error)> = results
+            .into_iter()
+            .filter_map(|(id, result)| result.err().map(|e| (id, e)))
+            .collect();
+        if failed.is_empty() {
+            status!("🎉 All {} templates downloaded!", templates.len());
+        } else {
+            output!(
+                "{}",
+                plain_text(&format!(
+                    "⚠️  {} of {} templates downloaded, {} failed:",
+                    templates.len() - failed.len(),
+                    templates.len(),
+                    failed.len()
+                ))
+            );
+            for (id, error) in &failed {
+                output!("  {} {}: {:#}", "❌".error(), id, error);
+            }
+        }
     }
     
-    fn get_config_path() -> Result<PathBuf> {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from(".config"))
-            .join("mammoth-cli");
-        fs::create_dir_all(&config_dir).context("Failed to create config directory")?;
-        Ok(config_dir.join("templates.json"))
+    /// Template version declared in the cached manifest
+    /// Version in the manifest of the cached copy
+    pub fn cached_version(&self, template: &Template) -> Option<String> {
+        self.load_template_manifest(template).ok()?.version
     }
     
-    fn get_cache_dir() -> Result<PathBuf> {
-        let cache_dir = dirs::cache_dir()
-            .unwrap_or_else(|| PathBuf::from(".cache"))
-            .join("mammoth-cli")
-            .join("templates");
-        Ok(cache_dir)
+    /// Commit the cached copy was fetched at
+    pub fn cached_commit(&self, template: &Template) -> Option<String> {
+        self.read_cache_meta(template)?.commit
     }
     
-    pub fn save_config(&self) -> Result<()> {
-        let config_path = Self::get_config_path()?;
-        let content =
-            serde_json::to_string_pretty(&self.config).context("Failed to serialize config")?;
-        fs::write(config_path, content).context("Failed to write config file")?;
-        Ok(())
+    /// Cache status of a template, spelled out as a word in plain mode
+    fn status_marker(&self, template: &Template) -> ColoredString {
+        if is_offline() && self.is_template_enabled(template) {
+            return match (self.is_usable_offline(template), is_plain_mode()) {
+                (true, true) => "[offline]".normal(),
+                (true, false) => "✅".success(),
+                (false, true) => "[unavailable]".normal(),
+                (false, false) => "🚫".error(),
+            };
+        }
+        let cached = self.get_template_cache_path(template).exists();
+        match (self.is_template_enabled(template), cached, is_plain_mode()) {
+            (false, _, true) => "[disabled]".normal(),
+            (false, _, false) => "⏸️".dimmed(),
+            (true, true, true) => "[cached]".normal(),
+            (true, true, false) => "✅".success(),
+            (true, false, true) => "[missing]".normal(),
+            (true, false, false) => "❌".error(),
+        }
     }
     
-    pub fn get_template_by_id(&self, id: &str) -> Option<&Template> {
-        self.config.templates.iter().find(|t| t.id == id)
+    /// Template ID with its icon and color, taken from the config or else the cached manifest
+    pub fn styled_id(&self, template: &Template) -> String {
+        let manifest = self.load_template_manifest(template).unwrap_or_default();
+        let color = template
+            .color
+            .as_ref()
+            .or(manifest.color.as_ref())
+            .and_then(|c| parse_color(c).ok());
+        let id = match color {
+            Some(color) => self.display_id(template).color(color).bold(),
+            None => self.display_id(template).bold(),
+        };
+        match template.icon.as_ref().or(manifest.icon.as_ref()) {
+            Some(icon) if !is_plain_mode() => format!("{} {}", icon, id),
+            _ => id.to_string(),
+        }
     }
     
-    pub fn get_repo_by_name(&self, name: &str) -> Option<&Repo> {
-        self.config.repos.iter().find(|r| r.name == name)
+    /// ID shown for a template, `repo/id` when several repositories have the same ID
+    pub fn display_id(&self, template: &Template) -> String {
+        // 多个仓库存在同名模板时显示 repo/id
+        let duplicated = self
+            .config
+            .templates
+            .iter()
+            .any(|t| t.id == template.id && t.repo != template.repo);
+        if duplicated { template.qualified_id() } else { template.id.clone() }
     }
     
-    fn get_template_cache_path(&self, template: &Template) -> PathBuf {
-        self.cache_dir.join(&template.repo).join(&template.id)
+    fn print_template_details(&self, template: &Template, indent: &str) {
+        output!("{}Description: {}", indent, template.description);
+        output!("{}Language: {}", indent, template.language);
+        if let Some(category) = &template.category {
+            output!("{}Category: {}", indent, category);
+        }
+        if let Some(version) = self.cached_version(template) {
+            output!("{}Version: {}", indent, version);
+        }
+        output!("{}Repository: {}", indent, template.repo);
+        match self.get_repo_by_name(&template.repo) {
+            Some(repo) if repo.path_prefix.is_some() => {
+                output!("{}Path: {} ({})", indent, template.path, template.repo_path(repo));
+            }
+            _ => output!("{}Path: {}", indent, template.path),
+        }
+        if let Some(branch) = &template.branch {
+            output!("{}Branch: {}", indent, branch);
+        }
+        if let Some(git_ref) = &template.git_ref {
+            output!("{}Pinned to: {}", indent, git_ref);
+        }
+        if !template.dependencies.is_empty() {
+            output!("{}Depends on: {}", indent, template.dependencies.join(", "));
+        }
+        output!("{}Tags: {}", indent, template.tags.join(", "));
     }
     
-    pub async fn download_template(&self, template: &Template, force: bool) -> Result<()> {
-        let repo = self
-            .get_repo_by_name(&template.repo)
-            .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", template.repo))?;
+    pub fn show_template(&self, template: &Template) {
+        output!("{} - {}", self.styled_id(template), template.name.localized().bold());
+        output!();
+        self.print_template_details(template, "  ");
         
+        output!();
+        output!("{}", "💾 Cache".section());
         let cache_path = self.get_template_cache_path(template);
-        
-        if cache_path.exists() && !force {
-            println!("✨ Template '{}' already cached", template.id);
-            return Ok(());
+        if !cache_path.exists() {
+            output!("  {} Not cached", "❌".error());
+            return;
         }
         
-        println!("🚀 Downloading template '{}'...", template.id);
-        
-        // Create progress bar
-        let pb = ProgressBar::new(100);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template(
-                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
-                )
-                .unwrap()
-                .progress_chars("#>-"),
-        );
-        
-        // Create temporary directory for sparse clone
-        let temp_dir = self.cache_dir.join(format!("temp_{}", repo.name));
-        
-        // 确保清理旧的临时目录
-        self.cleanup_temp_dir(&temp_dir)?;
-        fs::create_dir_all(&temp_dir).context("Failed to create temp dir")?;
-        
-        // 使用 Result 来确保清理操作
-        let result = self.download_template_internal(template, repo, &temp_dir, &cache_path, &pb).await;
-        
-        // 无论成功还是失败，都尝试清理临时目录
-        if let Err(ref e) = result {
-            eprintln!("❌ Download failed: {}", e);
+        output!("  {} {}", "✅".success(), cache_path.display());
+        output!("  Size: {}", format_size(dir_size(&cache_path)));
+        if let Some(commit) = self.read_cache_meta(template).and_then(|meta| meta.commit) {
+            output!("  Commit: {}", short_sha(&commit));
+        }
+        if let Some(age) = self.cache_age(template) {
+            output!("  Fetched: {} ago", format_duration(age));
+        }
+    }
+    
+    /// Look up stars and last commit dates of the templates of `language`,
+    /// keyed by qualified id. Templates on other hosts or unreachable are left out.
+    pub async fn fetch_template_popularity(&self, filter: &TemplateFilter) -> HashMap<String, Popularity> {
+        // 每个仓库只查询一次，结果分给它的各个模板
+        let mut by_repo: BTreeMap<&str, (Repo, Vec<String>, Vec<(String, String)>)> = BTreeMap::new();
+        for template in self
+            .config
+            .templates
+            .iter()
+            .filter(|t| filter.matches(t))
+        {
+            let repo = match self.get_repo_by_name(&template.repo) {
+                Some(repo) if RepoLocation::of(repo).is_some_and(|l| l.provider != Provider::Generic) => repo,
+                _ => continue,
+            };
+            let (_, ids, targets) = by_repo
+                .entry(repo.name.as_str())
+                .or_insert_with(|| (repo.clone(), Vec::new(), Vec::new()));
+            ids.push(template.qualified_id());
+            targets.push((template.effective_branch(repo).to_string(), template.repo_path(repo)));
         }
         
-        // 清理临时目录
-        self.cleanup_temp_dir(&temp_dir)?;
+        let mut lookups = tokio::task::JoinSet::new();
+        for (_, (repo, ids, targets)) in by_repo {
+            lookups.spawn(async move {
+                let result = fetch_repo_popularity(&repo, &targets).await;
+                (repo.name, ids, result)
+            });
+        }
         
-        result
+        let mut popularity = HashMap::new();
+        while let Some(lookup) = lookups.join_next().await {
+            match lookup {
+                Ok((_, ids, Ok(results))) => {
+                    for (id, result) in ids.into_iter().zip(results) {
+                        match result {
+                            Ok(stats) => {
+                                popularity.insert(id, stats);
+                            }
+                            Err(e) => {
+                                warning!("{}", format!("⚠️  No popularity data for {}: {}", id, e).warning());
+                            }
+                        }
+                    }
+                }
+                Ok((repo, _, Err(e))) => {
+                    warning!("{}", format!("⚠️  No popularity data for repository {}: {}", repo, e).warning());
+                }
+                Err(_) => {}
+            }
+        }
+        popularity
     }
     
-    async fn download_template_internal(
+    pub fn list_templates(
         &self,
-        template: &Template,
-        repo: &Repo,
-        temp_dir: &Path,
-        cache_path: &Path,
-        pb: &ProgressBar,
+        verbose: bool,
+        json: bool,
+        filter: &TemplateFilter,
+        popularity: &HashMap<String, Popularity>,
     ) -> Result<()> {
-        pb.set_message("Preparing sparse checkout...");
-        pb.inc(20);
-        
-        // Clone repository with sparse checkout and timeout
-        pb.set_message("Cloning repository...");
-        pb.inc(30);
-        
-        // Prepare git clone command with authentication if needed
-        let mut clone_cmd = tokio::process::Command::new("git");
-        clone_cmd.args([
-            "clone",
-            "--no-checkout",
-            "--filter=blob:none",
-            "--sparse",
-        ]);
-        
-        // Add authentication if provided
-        if let (Some(username), Some(token)) = (&repo.username, &repo.auth_token) {
-            // For HTTPS URLs, embed credentials in the URL
-            let auth_url = if repo.url.starts_with("https://") {
-                format!("https://{}:{}@{}", username, token, &repo.url[8..])
-            } else if repo.url.starts_with("http://") {
-                format!("http://{}:{}@{}", username, token, &repo.url[7..])
-            } else {
-                // For SSH URLs, we can't embed credentials, so we'll rely on SSH keys
-                repo.url.clone()
-            };
-            clone_cmd.arg(&auth_url);
+        if json {
+            let templates = self
+                .config
+                .templates
+                .iter()
+                .filter(|t| filter.matches(t))
+                .map(|t| self.template_json(t, popularity.get(&t.qualified_id())))
+                .collect::<Result<Vec<_>>>()?;
+            output!("{}", serde_json::to_string_pretty(&templates)?);
+            return Ok(());
+        }
+        if verbose {
+            output!("{}", "📋 Available Templates".header());
         } else {
-            clone_cmd.arg(&repo.url);
+            output!("{}", "📋 Template List".header());
         }
+        output!();
         
-        clone_cmd.arg(&*temp_dir.to_string_lossy());
+        if self.config.templates.is_empty() {
+            output!("No templates available. Add templates first.");
+            return Ok(());
+        }
         
-        let clone_result = tokio::time::timeout(
-            std::time::Duration::from_secs(300), // 5分钟超时
-            clone_cmd.status(),
-        )
-        .await;
+        let templates: Vec<&Template> = self
+            .config
+            .templates
+            .iter()
+            .filter(|t| filter.matches(t))
+            .collect();
+        if templates.is_empty() {
+            output!("No templates found matching {}.", filter.describe());
+            return Ok(());
+        }
+        if !filter.is_empty() {
+            output!("Found {} of {} templates", templates.len(), self.config.templates.len());
+            output!();
+        }
         
-        let status = match clone_result {
-            Ok(Ok(status)) => status,
-            Ok(Err(e)) => {
-                let error_msg = if repo.auth_token.is_some() {
-                    format!("Failed to clone private repository: {}. Please check your authentication credentials.", e)
+        // 只有配置了分类时才按分类分组显示
+        let grouped = templates.iter().any(|t| t.category.is_some());
+        for (category, members) in group_by_category(&templates) {
+            if grouped {
+                output!("{}", format!("📁 {}", category).section());
+            }
+            
+            for template in members {
+                let status = self.status_marker(template);
+                
+                if verbose {
+                    // 全信息显示模式
+                    output!("{} {} - {}", status, self.styled_id(template), template.name);
+                    self.print_template_details(template, "   ");
+                    if let Some(stats) = popularity.get(&template.qualified_id()) {
+                        let stars = stats.stars.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+                        let last_commit = stats.last_commit.as_deref().unwrap_or("-");
+                        output!("   Stars: {}  Last commit: {}", stars, last_commit);
+                    }
+                    output!();
                 } else {
-                    format!("Failed to clone repository: {}", e)
-                };
-                anyhow::bail!(error_msg);
-            },
-            Err(_) => anyhow::bail!("Git clone timed out after 5 minutes"),
-        };
+                    // 简要信息显示模式
+                    let version = self
+                        .cached_version(template)
+                        .map(|v| format!(" v{}", v))
+                        .unwrap_or_default();
+                    output!(
+                        "{} {} - {} ({}){}",
+                        status,
+                        self.styled_id(template),
+                        template.name,
+                        template.language,
+                        version.dimmed()
+                    );
+                }
+            }
+            
+            if grouped && !verbose {
+                output!();
+            }
+        }
         
-        if !status.success() {
-            let error_msg = if repo.auth_token.is_some() {
-                format!("Failed to clone private repository: {}. Please check your authentication credentials.", repo.url)
+        if is_offline() {
+            output!();
+            if is_plain_mode() {
+                output!("Offline: [offline] usable from the cache or a local repository, [unavailable] needs a download");
             } else {
-                format!("Failed to clone repository: {}", repo.url)
-            };
-            anyhow::bail!(error_msg);
+                output!("📴 Offline: ✅ usable from the cache or a local repository, 🚫 needs a download");
+            }
         }
+        if !verbose {
+            output!();
+            output!("{}", plain_text("💡 Use --verbose to see detailed information"));
+        }
+        Ok(())
+    }
+    
+    /// Template config entry with its cache state, for `--json` listings
+    fn template_json(&self, template: &Template, popularity: Option<&Popularity>) -> Result<serde_json::Value> {
+        let mut value = serde_json::to_value(template)?;
+        value["qualified_id"] = template.qualified_id().into();
+        value["status"] = self.template_status(template).into();
+        value["version"] = self.cached_version(template).into();
+        if is_offline() {
+            value["usable_offline"] = self.is_usable_offline(template).into();
+        }
+        if let Some(popularity) = popularity {
+            value["popularity"] = serde_json::to_value(popularity)?;
+        }
+        Ok(value)
+    }
+    
+    /// "disabled", "cached" or "missing"
+    /// "disabled", "cached" or "missing"
+    pub fn template_status(&self, template: &Template) -> &'static str {
+        if !self.is_template_enabled(template) {
+            "disabled"
+        } else if self.get_template_cache_path(template).exists() {
+            "cached"
+        } else {
+            "missing"
+        }
+    }
+    
+    pub fn add_template(&mut self, template: Template) -> Result<()> {
+        let operation = format!("template add {}", template.id);
+        self.insert_template(template, &operation)?;
         
-        // Set sparse checkout directory
-        pb.set_message("Configuring sparse checkout...");
-        pb.inc(40);
-        
-        let sparse_result = tokio::time::timeout(
-            std::time::Duration::from_secs(60), // 1分钟超时
-            tokio::process::Command::new("git")
-                .args(["sparse-checkout", "set", &template.path])
-                .current_dir(temp_dir)
-                .status(),
-        )
-        .await;
+        status!("🎉 Template added successfully!");
+        Ok(())
+    }
+    
+    /// Validate `template` and append it to the config as one undoable change
+    fn insert_template(&mut self, mut template: Template, operation: &str) -> Result<()> {
+        template.language = self.config.normalize_language(&template.language)?;
+        if let Some(color) = &template.color {
+            parse_color(color)?;
+        }
         
-        let status = match sparse_result {
-            Ok(Ok(status)) => status,
-            Ok(Err(e)) => anyhow::bail!("Failed to set sparse checkout: {}", e),
-            Err(_) => anyhow::bail!("Sparse checkout timed out"),
-        };
+        // Verify repository exists
+        if !self.config.repos.iter().any(|r| r.name == template.repo) {
+            anyhow::bail!(
+                "Repository '{}' not found. Add it first with 'repo add'",
+                template.repo
+            );
+        }
         
-        if !status.success() {
-            anyhow::bail!("Failed to set sparse checkout for path: {}", template.path);
+        // Check if template ID already exists
+        if template.id.contains('/') {
+            anyhow::bail!("Template ID '{}' cannot contain '/'", template.id);
         }
         
-        // Checkout the specific branch
-        pb.set_message("Checking out files...");
-        pb.inc(50);
-        
-        let checkout_result = tokio::time::timeout(
-            std::time::Duration::from_secs(120), // 2分钟超时
-            tokio::process::Command::new("git")
-                .args(["checkout", &repo.branch])
-                .current_dir(temp_dir)
-                .status(),
-        )
-        .await;
+        // IDs only need to be unique within a repository
+        if self
+            .config
+            .templates
+            .iter()
+            .any(|t| t.id == template.id && t.repo == template.repo)
+        {
+            anyhow::bail!("Template '{}' already exists", template.qualified_id());
+        }
         
-        let status = match checkout_result {
-            Ok(Ok(status)) => status,
-            Ok(Err(e)) => anyhow::bail!("Failed to checkout branch: {}", e),
-            Err(_) => anyhow::bail!("Git checkout timed out"),
-        };
+        // Verify dependencies exist
+        for dependency in &template.dependencies {
+            self.find_template(dependency)
+                .with_context(|| format!("Dependency template '{}' not found", dependency))?;
+        }
         
-        if !status.success() {
-            anyhow::bail!("Failed to checkout branch: {}", repo.branch);
+        self.config.templates.push(template);
+        self.commit_change(operation)
+    }
+    
+    /// Copy the config entry of `id` to `new_id`, optionally pointing it at another path
+    /// or branch. The cache is copied too when `with_cache` is set and the source stays
+    /// the same, so the variant can be used without cloning again.
+    pub fn duplicate_template(
+        &mut self,
+        id: &str,
+        new_id: &str,
+        path: Option<String>,
+        branch: Option<String>,
+        with_cache: bool,
+    ) -> Result<()> {
+        let original = self.find_template(id)?.clone();
+        let mut template = original.clone();
+        template.id = new_id.to_string();
+        if let Some(path) = path {
+            template.path = path;
+        }
+        if let Some(branch) = branch {
+            template.branch = if branch.is_empty() { None } else { Some(branch) };
         }
         
-        // Create target directory
-        fs::create_dir_all(cache_path.parent().unwrap())
-            .context("Failed to create repo cache parent dir")?;
+        let same_source = template.path == original.path && template.branch == original.branch;
+        self.insert_template(template.clone(), &format!("template duplicate {} {}", id, new_id))?;
+        status!("🎉 Template '{}' duplicated as '{}'", id, new_id);
         
-        // Move template files to cache location
-        pb.set_message("Copying template files...");
-        pb.inc(60);
+        let cache_path = self.get_template_cache_path(&original);
+        if !with_cache || !cache_path.exists() {
+            return Ok(());
+        }
+        if !same_source {
+            warning!("⚠️  Cache not copied because the path or branch changed, run 'template download {}'", new_id);
+            return Ok(());
+        }
+        copy_directory(&cache_path, &self.get_template_cache_path(&template))
+            .context("Failed to copy template cache")?;
+        if let Some(meta) = self.read_cache_meta(&original) {
+            self.write_cache_meta(&template, &meta)?;
+        }
+        status!("📦 Cache copied");
+        Ok(())
+    }
+    
+    pub fn update_template(&mut self, id: &str, mut update: TemplateUpdate) -> Result<()> {
+        if let Some(language) = &update.language {
+            update.language = Some(self.config.normalize_language(language)?);
+        }
+        if let Some(color) = update.color.as_deref().filter(|c| !c.is_empty()) {
+            parse_color(color)?;
+        }
+        if let Some(repo) = &update.repo {
+            if !self.config.repos.iter().any(|r| &r.name == repo) {
+                anyhow::bail!(
+                    "Repository '{}' not found. Add it first with 'repo add'",
+                    repo
+                );
+            }
+        }
         
-        let template_source = temp_dir.join(&template.path);
-        if !template_source.exists() {
-            anyhow::bail!("Template path '{}' not found in repository", template.path);
+        let index = self.config.template_index(id)?;
+        let template = &mut self.config.templates[index];
+        
+        if let Some(name) = update.name {
+            template.name = name.into();
+        }
+        if let Some(repo) = update.repo {
+            template.repo = repo;
+        }
+        if let Some(path) = update.path {
+            template.path = path;
+        }
+        if let Some(description) = update.description {
+            template.description = description.into();
+        }
+        if let Some(language) = update.language {
+            template.language = language;
+        }
+        if let Some(tags) = update.tags {
+            template.tags = tags;
+        }
+        if let Some(branch) = update.branch {
+            template.branch = if branch.is_empty() { None } else { Some(branch) };
+        }
+        if let Some(git_ref) = update.git_ref {
+            template.git_ref = if git_ref.is_empty() { None } else { Some(git_ref) };
+        }
+        if let Some(dependencies) = update.dependencies {
+            template.dependencies = dependencies;
+        }
+        if let Some(category) = update.category {
+            template.category = if category.is_empty() { None } else { Some(category) };
+        }
+        if let Some(color) = update.color {
+            template.color = if color.is_empty() { None } else { Some(color) };
+        }
+        if let Some(icon) = update.icon {
+            template.icon = if icon.is_empty() { None } else { Some(icon) };
         }
         
-        // 安全地清理和复制文件
-        self.safe_copy_template_files(&template_source, cache_path)?;
+        self.commit_change(&format!("template update {}", id))?;
         
-        pb.finish_with_message("Template downloaded successfully!");
-        println!(
-            "✅ Template '{}' downloaded to: {}",
-            template.id,
-            cache_path.display()
-        );
+        status!("✅ Template '{}' updated successfully!", id);
+        Ok(())
+    }
+    
+    /// `config set`: change one entry of the `settings` section
+    pub fn set_setting(&mut self, key: &str, value: &str) -> Result<()> {
+        self.config.settings.set(key, value)?;
+        self.commit_change(&format!("config set {}", key))?;
+        if value.is_empty() {
+            status!("✅ Setting '{}' reset to its default", key);
+        } else {
+            status!("✅ Setting '{}' set to '{}'", key, value);
+        }
+        Ok(())
+    }
+    
+    /// Disable or re-enable a template, keeping its config entry and cache
+    pub fn set_template_disabled(&mut self, id: &str, disabled: bool) -> Result<()> {
+        let index = self.config.template_index(id)?;
+        let template = &mut self.config.templates[index];
         
+        let action = if disabled { "disable" } else { "enable" };
+        if template.disabled == disabled {
+            status!("ℹ️  Template '{}' is already {}d", id, action);
+            return Ok(());
+        }
+        template.disabled = disabled;
+        self.commit_change(&format!("template {} {}", action, id))?;
+        
+        status!("✅ Template '{}' {}d", id, action);
         Ok(())
     }
     
-    fn cleanup_temp_dir(&self, temp_dir: &Path) -> Result<()> {
-        if temp_dir.exists() {
-            // 在 Windows 上，可能需要多次尝试删除
-            for attempt in 1..=3 {
-                match fs::remove_dir_all(temp_dir) {
-                    Ok(_) => {
-                        if attempt > 1 {
-                            println!("✅ Temp directory cleaned on attempt {}", attempt);
-                        }
-                        return Ok(());
-                    }
-                    Err(e) => {
-                        if attempt == 3 {
-                            eprintln!("⚠️  Warning: Failed to remove temp dir after 3 attempts: {}", e);
-                            return Err(e.into());
-                        }
-                        // 等待一小段时间再重试
-                        std::thread::sleep(std::time::Duration::from_millis(500));
-                    }
-                }
-            }
+    /// Pin a template to a tag or full commit SHA, or follow its branch again when
+    /// `git_ref` is empty. The next download fetches the new revision.
+    pub fn pin_template(&mut self, id: &str, git_ref: &str) -> Result<()> {
+        let index = self.config.template_index(id)?;
+        let template = &mut self.config.templates[index];
+        let git_ref = git_ref.trim();
+        
+        let pinned = (!git_ref.is_empty()).then(|| git_ref.to_string());
+        if template.git_ref == pinned {
+            return Ok(());
+        }
+        template.git_ref = pinned;
+        self.commit_change(&format!("template pin {}", id))?;
+        
+        if git_ref.is_empty() {
+            status!("📌 Template '{}' follows its branch again", id);
+        } else {
+            status!("📌 Template '{}' pinned to {}", id, git_ref);
         }
         Ok(())
     }
     
-    fn safe_copy_template_files(&self, source: &Path, dest: &Path) -> Result<()> {
-        // 如果目标目录存在，先尝试删除
-        if dest.exists() {
-            // 在 Windows 上，可能需要多次尝试
-            for attempt in 1..=3 {
-                match fs::remove_dir_all(dest) {
-                    Ok(_) => break,
-                    Err(e) => {
-                        if attempt == 3 {
-                            anyhow::bail!("Failed to remove old cache after 3 attempts: {}", e);
-                        }
-                        std::thread::sleep(std::time::Duration::from_millis(500));
-                    }
-                }
-            }
+    /// Whether a template can be offered and generated
+    pub fn is_template_enabled(&self, template: &Template) -> bool {
+        !template.disabled
+            && !self
+                .get_repo_by_name(&template.repo)
+                .is_some_and(|repo| repo.disabled)
+    }
+    
+    /// Fail with an explanation if a template or its repository is disabled
+    pub fn ensure_template_enabled(&self, template: &Template) -> Result<()> {
+        if template.disabled {
+            anyhow::bail!(
+                "Template '{}' is disabled. Re-enable it with 'template enable {}'",
+                template.id,
+                template.id
+            );
+        }
+        if self.get_repo_by_name(&template.repo).is_some_and(|repo| repo.disabled) {
+            anyhow::bail!(
+                "Template '{}' comes from disabled repository '{}'. Re-enable it with 'repo enable {}'",
+                template.id,
+                template.repo,
+                template.repo
+            );
         }
-        
-        // 复制文件
-        copy_directory(source, dest).context("Failed to copy template files")?;
-        
         Ok(())
     }
     
-    pub async fn download_all_templates(&self, force: bool) -> Result<()> {
-        println!("🚀 Downloading all templates...");
+    pub fn remove_template(&mut self, id: &str) -> Result<()> {
+        let index = self.config.template_index(id)?;
         
-        for template in &self.config.templates {
-            match self.download_template(template, force).await {
-                Ok(_) => {}
-                Err(e) => {
-                    println!("❌ Failed to download template '{}': {}", template.id, e);
-                }
-            }
+        // Check if any templates depend on this template
+        if let Some(dependent) = self.config.templates.iter().find(|t| {
+            t.dependencies
+                .iter()
+                .any(|d| self.config.template_index(d).is_ok_and(|i| i == index))
+        }) {
+            anyhow::bail!(
+                "Cannot remove template '{}' - template '{}' depends on it",
+                id,
+                dependent.id
+            );
         }
         
-        println!("🎉 All templates downloaded!");
+        self.config.templates.remove(index);
+        self.commit_change(&format!("template remove {}", id))?;
+        status!("🗑️  Template '{}' removed successfully!", id);
+        
         Ok(())
     }
     
-    pub fn list_templates(&self, verbose: bool) {
-        if verbose {
-            println!("{}", "📋 Available Templates".bold().blue());
-        } else {
-            println!("{}", "📋 Template List".bold().blue());
+    pub fn add_repo(&mut self, mut repo: Repo) -> Result<()> {
+        // Check if repository already exists
+        if self.config.repos.iter().any(|r| r.name == repo.name) {
+            anyhow::bail!("Repository '{}' already exists", repo.name);
         }
-        println!();
-        
-        if self.config.templates.is_empty() {
-            println!("No templates available. Add templates first.");
-            return;
+        if let Some(key) = &repo.ssh_key {
+            repo.ssh_key = Some(resolve_ssh_key(key)?);
         }
         
-        for template in &self.config.templates {
-            let cache_path = self.get_template_cache_path(template);
-            let status = if cache_path.exists() {
-                "✅".green()
-            } else {
-                "❌".red()
-            };
-            
-            if verbose {
-                // 全信息显示模式
-                println!("{} {} - {}", status, template.id.bold(), template.name);
-                println!("   Description: {}", template.description);
-                println!("   Language: {}", template.language);
-                println!("   Repository: {}", template.repo);
-                println!("   Path: {}", template.path);
-                println!("   Tags: {}", template.tags.join(", "));
-                println!();
-            } else {
-                // 简要信息显示模式
-                println!(
-                    "{} {} - {} ({})",
-                    status,
-                    template.id.bold(),
-                    template.name,
-                    template.language
-                );
-            }
+        // 本地目录统一保存为 file:// URL
+        if let Some(url) = local_repo_url(&repo.url) {
+            repo.url = url;
         }
+        let operation = format!("repo add {}", repo.name);
+        self.config.repos.push(repo);
+        self.commit_change(&operation)?;
         
-        if !verbose {
-            println!();
-            println!("💡 Use --verbose to see detailed information");
-        }
+        status!("🎉 Repository added successfully!");
+        Ok(())
     }
     
-    pub fn add_template(
-        &mut self,
-        id: String,
-        name: String,
-        repo: String,
-        path: String,
-        description: String,
-        language: String,
-        tags: Option<String>,
-    ) -> Result<()> {
-        // Verify repository exists
-        if !self.config.repos.iter().any(|r| r.name == repo) {
-            anyhow::bail!(
-                "Repository '{}' not found. Add it first with 'repo add'",
-                repo
-            );
+    pub fn update_repo(&mut self, name: &str, update: RepoUpdate) -> Result<()> {
+        let RepoUpdate {
+            url,
+            branch,
+            path_prefix,
+            size_hint,
+            provider,
+            ssh_key,
+        } = update;
+        if let Some(hint) = size_hint.as_deref().filter(|hint| !hint.is_empty()) {
+            parse_size(hint)?;
         }
-        
-        // Check if template ID already exists
-        if self.config.templates.iter().any(|t| t.id == id) {
-            anyhow::bail!("Template with ID '{}' already exists", id);
+        if let Some(provider) = provider.as_deref().filter(|provider| !provider.is_empty()) {
+            provider.parse::<Provider>()?;
         }
-        
-        // Parse tags
-        let tags_vec = if let Some(tags_str) = tags {
-            tags_str
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect()
-        } else {
-            vec![]
+        let ssh_key = match ssh_key {
+            Some(key) if !key.is_empty() => Some(resolve_ssh_key(&key)?),
+            other => other,
         };
+        let repo = self
+            .config
+            .repos
+            .iter_mut()
+            .find(|r| r.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", name))?;
         
-        let template = Template {
-            id,
-            name,
-            repo,
-            path,
-            description,
-            language,
-            tags: tags_vec,
-        };
+        if let Some(url) = url {
+            repo.url = local_repo_url(&url).unwrap_or(url);
+        }
+        if let Some(branch) = branch {
+            repo.branch = branch;
+        }
+        if let Some(prefix) = path_prefix {
+            repo.path_prefix = if prefix.is_empty() { None } else { Some(prefix) };
+        }
+        if let Some(hint) = size_hint {
+            repo.size_hint = if hint.is_empty() { None } else { Some(hint) };
+        }
+        if let Some(provider) = provider {
+            repo.provider = if provider.is_empty() { None } else { Some(provider) };
+        }
+        if let Some(key) = ssh_key {
+            repo.ssh_key = if key.is_empty() { None } else { Some(key) };
+        }
         
-        self.config.templates.push(template);
-        self.save_config()?;
+        self.commit_change(&format!("repo update {}", name))?;
         
-        println!("🎉 Template added successfully!");
+        status!("✅ Repository '{}' updated successfully!", name);
         Ok(())
     }
     
-    pub fn remove_template(&mut self, id: &str) -> Result<()> {
-        let index = self.config.templates.iter().position(|t| t.id == id);
+    /// Store the token of a repository in the OS keyring and reference it from the config
+    /// by key (the repository name), dropping any plaintext `auth_token`. The token is read
+    /// from stdin with `token_stdin`, otherwise prompted for without echo.
+    pub fn auth_repo(&mut self, name: &str, username: Option<String>, token_stdin: bool, remove: bool) -> Result<()> {
+        let repo = self
+            .config
+            .repos
+            .iter_mut()
+            .find(|r| r.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", name))?;
+        let key = repo.keyring.clone().unwrap_or_else(|| repo.name.clone());
         
-        if let Some(index) = index {
-            self.config.templates.remove(index);
-            self.save_config()?;
-            println!("🗑️  Template '{}' removed successfully!", id);
+        if remove {
+            delete_keyring_token(&key)?;
+            repo.keyring = None;
+            self.commit_change(&format!("repo auth {} --remove", name))?;
+            status!("🗑️  Credentials of '{}' removed from the OS keyring", name);
+            return Ok(());
+        }
+        
+        if !token_stdin && !is_interactive() {
+            return Err(missing_input("Token", "--token-stdin"));
+        }
+        let username = match username {
+            Some(username) => Some(username),
+            None if !token_stdin => Some(
+                Input::<String>::new()
+                    .with_prompt("Username (empty for the provider default)")
+                    .with_initial_text(repo.username.clone().unwrap_or_default())
+                    .allow_empty(true)
+                    .interact_text()?,
+            ),
+            None => None,
+        };
+        let token = if token_stdin {
+            let mut token = String::new();
+            std::io::stdin().read_line(&mut token).context("Failed to read token from stdin")?;
+            token.trim().to_string()
         } else {
-            anyhow::bail!("Template '{}' not found", id);
+            Password::new().with_prompt("Token").interact()?
+        };
+        if token.is_empty() {
+            anyhow::bail!("Token must not be empty");
+        }
+        if let Some(username) = username {
+            repo.username = if username.is_empty() { None } else { Some(username) };
         }
         
+        store_keyring_token(&key, &token)?;
+        let had_plaintext = repo.auth_token.take().is_some();
+        repo.keyring = Some(key);
+        self.commit_change(&format!("repo auth {}", name))?;
+        
+        status!("🔐 Token of '{}' stored in the OS keyring", name);
+        if had_plaintext {
+            status!("🧹 Removed the plaintext token from the config file");
+        }
         Ok(())
     }
     
-    pub fn add_repo(&mut self, name: String, url: String, branch: String, username: Option<String>, auth_token: Option<String>) -> Result<()> {
-        // Check if repository already exists
-        if self.config.repos.iter().any(|r| r.name == name) {
-            anyhow::bail!("Repository '{}' already exists", name);
-        }
+    /// Disable or re-enable a repository, hiding and blocking all of its templates
+    pub fn set_repo_disabled(&mut self, name: &str, disabled: bool) -> Result<()> {
+        let repo = self
+            .config
+            .repos
+            .iter_mut()
+            .find(|r| r.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", name))?;
         
-        let repo = Repo { 
-            name, 
-            url, 
-            branch, 
-            auth_token, 
-            username 
-        };
-        self.config.repos.push(repo);
-        self.save_config()?;
+        let action = if disabled { "disable" } else { "enable" };
+        if repo.disabled == disabled {
+            status!("ℹ️  Repository '{}' is already {}d", name, action);
+            return Ok(());
+        }
+        repo.disabled = disabled;
+        self.commit_change(&format!("repo {} {}", action, name))?;
         
-        println!("🎉 Repository added successfully!");
+        let affected = self.config.templates.iter().filter(|t| t.repo == name).count();
+        status!("✅ Repository '{}' {}d ({} templates affected)", name, action, affected);
         Ok(())
     }
     
@@ -465,9 +2724,15 @@ impl TemplateManager {
         let index = self.config.repos.iter().position(|r| r.name == name);
         
         if let Some(index) = index {
-            self.config.repos.remove(index);
-            self.save_config()?;
-            println!("🗑️  Repository '{}' removed successfully!", name);
+            let repo = self.config.repos.remove(index);
+            if let Some(key) = &repo.keyring {
+                // 其他仓库可能共用同一个钥匙串条目
+                if !self.config.repos.iter().any(|r| r.keyring.as_ref() == Some(key)) {
+                    delete_keyring_token(key)?;
+                }
+            }
+            self.commit_change(&format!("repo remove {}", name))?;
+            status!("🗑️  Repository '{}' removed successfully!", name);
         } else {
             anyhow::bail!("Repository '{}' not found", name);
         }
@@ -475,7 +2740,8 @@ impl TemplateManager {
         Ok(())
     }
     
-    pub fn copy_template_files(&self, template: &Template, project_path: &Path) -> Result<()> {
+    /// Cache directory of a template, failing if it has not been downloaded yet
+    pub fn cached_template_path(&self, template: &Template) -> Result<PathBuf> {
         let cache_path = self.get_template_cache_path(template);
         
         if !cache_path.exists() {
@@ -486,123 +2752,769 @@ impl TemplateManager {
             );
         }
         
-        copy_directory(&cache_path, project_path)?;
+        Ok(cache_path)
+    }
+    
+    pub fn copy_template_files(
+        &self,
+        template: &Template,
+        project_path: &Path,
+        vars: &HashMap<String, String>,
+    ) -> Result<()> {
+        let cache_path = self.cached_template_path(template)?;
+        self.copy_template_dir(&cache_path, project_path, vars)
+    }
+    
+    /// Copy a template (or fragment) layer into the project. Files whose manifest condition
+    /// does not hold for `vars` are left out and renamed files are written under their new
+    /// path. A package.json shipped by a later layer is merged into the existing one instead
+    /// of replacing it, and the `package_json` patch, dependencies and code patches of the
+    /// layer manifest are applied afterwards.
+    pub fn copy_template_dir(
+        &self,
+        template_dir: &Path,
+        project_path: &Path,
+        vars: &HashMap<String, String>,
+    ) -> Result<()> {
+        let manifest = TemplateManifest::load(template_dir)?;
+        // 裁剪的文件和条件不成立的文件不复制，之前的层已写入的同名文件保持不变
+        let mut skipped = Vec::new();
+        for pattern in manifest.iter().flat_map(|m| &m.prune) {
+            skipped.extend(matching_paths(template_dir, pattern).context("Invalid prune entry in manifest")?);
+        }
+        for (pattern, condition) in manifest.iter().flat_map(|m| &m.conditions) {
+            let holds = evaluate_condition(condition, vars)
+                .with_context(|| format!("Invalid condition for '{}' in manifest", pattern))?;
+            if !holds {
+                skipped.extend(matching_paths(template_dir, pattern).context("Invalid conditions entry in manifest")?);
+            }
+        }
+        let mut renames = Vec::new();
+        for (from, to) in manifest.iter().flat_map(|m| &m.rename) {
+            let to = rename_target(to, vars).with_context(|| format!("Invalid rename of '{}' in manifest", from))?;
+            renames.push((PathBuf::from(from.trim().trim_matches('/')), to));
+        }
+        let mut rules = Vec::new();
+        for (pattern, rule) in manifest.iter().flat_map(|m| &m.merge) {
+            for matched in matching_paths(template_dir, pattern).context("Invalid merge entry in manifest")? {
+                rules.push((matched, *rule));
+            }
+        }
+        
+        // 源文件 → 项目中的位置，并按合并规则记下之前的层已写入的同名文件，复制后再合并
+        let mut files = Vec::new();
+        let mut merged = Vec::new();
+        for relative in list_files(template_dir)? {
+            if skipped.iter().any(|path| relative.starts_with(path)) {
+                continue;
+            }
+            let destination = renames
+                .iter()
+                .find_map(|(from, to)| match relative.strip_prefix(from).ok()? {
+                    rest if rest.as_os_str().is_empty() => Some(to.clone()),
+                    rest => Some(to.join(rest)),
+                })
+                .unwrap_or_else(|| relative.clone());
+            // 规则既可以写模板中的路径，也可以写重命名后的路径，如 `_gitignore` 的 ".gitignore"
+            let rule = rules
+                .iter()
+                .find(|(matched, _)| relative.starts_with(matched))
+                .map(|(_, rule)| *rule)
+                .or_else(|| {
+                    manifest
+                        .iter()
+                        .flat_map(|m| &m.merge)
+                        .find(|(pattern, _)| path_matches(pattern, &destination))
+                        .map(|(_, rule)| *rule)
+                })
+                .unwrap_or_default();
+            if rule != MergeRule::Replace && destination != Path::new("package.json") {
+                if let Ok(existing) = fs::read(project_path.join(&destination)) {
+                    merged.push((destination.clone(), rule, existing));
+                }
+            }
+            files.push((relative, destination));
+        }
+        
+        let package_json_path = project_path.join("package.json");
+        let existing_package_json = if package_json_path.exists() {
+            Some(fs::read_to_string(&package_json_path).context("Failed to read package.json")?)
+        } else {
+            None
+        };
+        let copies_package_json = files.iter().any(|(_, destination)| destination == Path::new("package.json"));
+        
+        tracing::debug!("copy {} -> {}", template_dir.display(), project_path.display());
+        for (relative, destination) in &files {
+            let target = project_path.join(destination);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(template_dir.join(relative), &target)
+                .with_context(|| format!("Failed to copy {}", relative.display()))?;
+        }
+        for (relative, rule, existing) in merged {
+            merge_layer_file(&project_path.join(&relative), rule, existing)
+                .with_context(|| format!("Failed to merge {}", relative.display()))?;
+        }
+        
+        if let Some(existing) = existing_package_json {
+            if copies_package_json {
+                let layer: serde_json::Value = serde_json::from_str(
+                    &fs::read_to_string(&package_json_path).context("Failed to read package.json")?,
+                )
+                .context("Failed to parse template package.json")?;
+                let mut merged: serde_json::Value =
+                    serde_json::from_str(&existing).context("Failed to parse package.json")?;
+                for conflict in merge_package_json(&mut merged, &layer) {
+                    warning!(
+                        "⚠️  package.json: '{}' is {} in an earlier layer, overridden with {}",
+                        conflict.key,
+                        conflict.earlier,
+                        conflict.later
+                    );
+                }
+                fs::write(&package_json_path, serde_json::to_string_pretty(&merged)?)
+                    .context("Failed to write package.json")?;
+            }
+        }
+        
+        if let Some(manifest) = manifest {
+            if let Some(patch) = &manifest.package_json {
+                patch_package_json(project_path, patch).context("Failed to apply package.json patch")?;
+            }
+            add_package_dependencies(project_path, &manifest.dependencies, &manifest.dev_dependencies)
+                .context("Failed to add package.json dependencies")?;
+            for patch in &manifest.code_patches {
+                apply_code_patch(project_path, patch)
+                    .with_context(|| format!("Failed to patch {}", patch.file))?;
+            }
+        }
+        
+        // 清单文件只供 mammoth 使用，不进入生成的项目
+        for file in [MANIFEST_FILE, MANIFEST_TOML_FILE] {
+            let manifest_path = project_path.join(file);
+            if manifest_path.exists() {
+                fs::remove_file(&manifest_path).context("Failed to remove template manifest")?;
+            }
+        }
+        Ok(())
+    }
+    
+    pub fn load_template_manifest(&self, template: &Template) -> Result<TemplateManifest> {
+        let cache_path = self.get_template_cache_path(template);
+        Ok(TemplateManifest::load(&cache_path)?.unwrap_or_default())
+    }
+    
+    /// Variables declared by the manifests of a template, its dependencies and addons,
+    /// downloading them first if needed. A later layer redeclaring a variable replaces it.
+    pub async fn template_variables(&self, template: &Template, addons: &[Template]) -> Result<Vec<TemplateVariable>> {
+        let mut variables: Vec<TemplateVariable> = Vec::new();
+        for layer in self.compose_layers(template, addons)? {
+            self.download_template(layer, false).await?;
+            for variable in self.load_template_manifest(layer)?.variables {
+                variables.retain(|v| v.name != variable.name);
+                variables.push(variable);
+            }
+        }
+        Ok(variables)
+    }
+    
+    /// Compare the commit of every cached template with the tip of its branch, asking each
+    /// repository branch once with `git ls-remote`
+    pub async fn outdated_templates(&self, json: bool) -> Result<()> {
+        let cached: Vec<(&Template, &Repo, String)> = self
+            .config
+            .templates
+            .iter()
+            .filter(|t| self.get_template_cache_path(t).exists())
+            .filter_map(|t| {
+                let repo = self.get_repo_by_name(&t.repo)?;
+                let commit = self.read_cache_meta(t).and_then(|meta| meta.commit).unwrap_or_default();
+                Some((t, repo, commit))
+            })
+            .collect();
+        if cached.is_empty() && json {
+            output!("[]");
+            return Ok(());
+        }
+        if cached.is_empty() {
+            output!("No cached templates. Download templates first.");
+            return Ok(());
+        }
+        
+        if !json {
+            status!("🔍 Checking {} cached templates against upstream...", cached.len());
+        }
+        let mut lookups = tokio::task::JoinSet::new();
+        let mut branches: Vec<(String, String)> = Vec::new();
+        for (template, repo, _) in &cached {
+            let key = (repo.name.clone(), template.effective_branch(repo).to_string());
+            // 固定到标签或提交的模板不跟随分支
+            if template.git_ref.is_some() || branches.contains(&key) || !RepoSource::of(repo).is_git() {
+                continue;
+            }
+            let repo = (*repo).clone();
+            let branch = key.1.clone();
+            lookups.spawn(async move { (repo.name.clone(), branch.clone(), remote_branch_head(&repo, &branch).await) });
+            branches.push(key);
+        }
+        let mut heads: HashMap<(String, String), Result<Option<String>>> = HashMap::new();
+        while let Some(lookup) = lookups.join_next().await {
+            let (repo, branch, head) = lookup?;
+            heads.insert((repo, branch), head);
+        }
+        
+        if json {
+            let entries: Vec<serde_json::Value> = cached
+                .iter()
+                .map(|(template, repo, commit)| {
+                    let key = (repo.name.clone(), template.effective_branch(repo).to_string());
+                    let (upstream, status, error) = match heads.get(&key) {
+                        _ if template.git_ref.is_some() => (None, "pinned", None),
+                        None => (None, "untracked", None),
+                        Some(Ok(Some(head))) if commit.is_empty() => (Some(head.clone()), "unknown", None),
+                        Some(Ok(Some(head))) if head == commit => (Some(head.clone()), "up-to-date", None),
+                        Some(Ok(Some(head))) => (Some(head.clone()), "outdated", None),
+                        Some(Ok(None)) => (None, "branch-missing", None),
+                        Some(Err(e)) => (None, "error", Some(e.to_string())),
+                    };
+                    serde_json::json!({
+                        "template": template.qualified_id(),
+                        "branch": key.1,
+                        "ref": template.git_ref,
+                        "cached": (!commit.is_empty()).then_some(commit),
+                        "upstream": upstream,
+                        "status": status,
+                        "error": error,
+                    })
+                })
+                .collect();
+            output!("{}", serde_json::to_string_pretty(&entries)?);
+            return Ok(());
+        }
+        
+        let width = cached.iter().map(|(t, _, _)| t.qualified_id().len()).max().unwrap_or(0).max(8);
+        output!();
+        output!("{:<width$}  {:<8}  {:<8}  Status", "Template", "Cached", "Upstream", width = width);
+        let mut outdated = 0;
+        let mut errors = Vec::new();
+        for (template, repo, commit) in &cached {
+            let key = (repo.name.clone(), template.effective_branch(repo).to_string());
+            let (upstream, status) = match heads.get(&key) {
+                _ if template.git_ref.is_some() => ("-".to_string(), "pinned".dimmed()),
+                // 本地目录和归档仓库没有提交可比较
+                None => ("-".to_string(), "untracked".dimmed()),
+                Some(Ok(Some(head))) if commit.is_empty() => (short_sha(head).to_string(), "unknown".warning()),
+                Some(Ok(Some(head))) if head == commit => (short_sha(head).to_string(), "up to date".success()),
+                Some(Ok(Some(head))) => {
+                    outdated += 1;
+                    (short_sha(head).to_string(), "outdated".warning())
+                }
+                Some(Ok(None)) => ("-".to_string(), "branch missing".error()),
+                Some(Err(e)) => {
+                    errors.push(format!("{}: {}", repo.name, e));
+                    ("-".to_string(), "error".error())
+                }
+            };
+            let cached_commit = if commit.is_empty() { "-" } else { short_sha(commit) };
+            output!(
+                "{:<width$}  {:<8}  {:<8}  {}",
+                template.qualified_id(),
+                cached_commit,
+                upstream,
+                status,
+                width = width
+            );
+        }
+        
+        output!();
+        errors.dedup();
+        for error in &errors {
+            output!("{} {}", "❌".error(), error);
+        }
+        if outdated == 0 {
+            status!("{}", "🎉 All cached templates are up to date".success());
+        } else {
+            output!(
+                "{}",
+                plain_text(&format!(
+                    "💡 {} templates are behind upstream, refresh them with: mammoth-cli template download <id> --force",
+                    outdated
+                ))
+            );
+        }
         Ok(())
     }
     
-    pub fn list_repos(&self) {
-        println!("{}", "📦 Configured Template Repositories".bold().blue());
-        println!();
+    /// Check repositories concurrently with `git ls-remote` and print a health table.
+    /// Fails when any repository is unhealthy so scheduled audits can alert on it.
+    pub async fn verify_repos(&self, name: Option<&str>) -> Result<()> {
+        let repos: Vec<Repo> = match name {
+            Some(name) => vec![self
+                .get_repo_by_name(name)
+                .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", name))?
+                .clone()],
+            None => self.config.repos.clone(),
+        };
+        if repos.is_empty() {
+            output!("No repositories configured. Add repositories first.");
+            return Ok(());
+        }
+        
+        status!("🩺 Verifying {} repositories...", repos.len());
+        let mut checks = tokio::task::JoinSet::new();
+        for (index, repo) in repos.into_iter().enumerate() {
+            checks.spawn(async move { (index, check_repo_health(&repo).await) });
+        }
+        let mut results = Vec::new();
+        while let Some(check) = checks.join_next().await {
+            results.push(check?);
+        }
+        results.sort_by_key(|(index, _)| *index);
+        let results: Vec<RepoHealth> = results.into_iter().map(|(_, health)| health).collect();
+        
+        let width = results.iter().map(|h| h.repo.len()).max().unwrap_or(0).max(10);
+        let mark = |ok: bool| match (ok, is_plain_mode()) {
+            (true, true) => "yes".normal(),
+            (false, true) => "no".normal(),
+            (true, false) => "✅".success(),
+            (false, false) => "❌".error(),
+        };
+        output!();
+        output!(
+            "{:<width$}  {:<9}  {:<4}  {:<6}  {:>8}",
+            "Repository", "Reachable", "Auth", "Branch", "Latency",
+            width = width
+        );
+        for health in &results {
+            output!(
+                "{:<width$}  {:<9}  {:<4}  {:<6}  {:>6}ms",
+                health.repo,
+                mark(health.reachable),
+                mark(health.auth_ok),
+                mark(health.branch_exists),
+                health.latency_ms,
+                width = width
+            );
+        }
+        
+        let failed: Vec<&RepoHealth> = results.iter().filter(|h| !h.is_healthy()).collect();
+        if failed.is_empty() {
+            output!();
+            status!("{}", "🎉 All repositories are healthy".success());
+            return Ok(());
+        }
+        output!();
+        for health in &failed {
+            if let Some(error) = &health.error {
+                output!("{} {}: {}", "❌".error(), health.repo.bold(), error);
+            }
+        }
+        anyhow::bail!("{} of {} repositories failed verification", failed.len(), results.len())
+    }
+    
+    pub fn list_repos(&self, json: bool) -> Result<()> {
+        if json {
+            // 令牌以占位符输出，只标明仓库是否私有
+            let repos = self
+                .config
+                .redacted()
+                .repos
+                .into_iter()
+                .zip(&self.config.repos)
+                .map(|(redacted, repo)| {
+                    let mut value = serde_json::to_value(redacted)?;
+                    value["private"] = (repo.keyring.is_some() || repo_credentials(repo).1.is_some()).into();
+                    value["templates"] = self.config.templates.iter().filter(|t| t.repo == repo.name).count().into();
+                    Ok(value)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            output!("{}", serde_json::to_string_pretty(&repos)?);
+            return Ok(());
+        }
+        output!("{}", "📦 Configured Template Repositories".header());
+        output!();
         if self.config.repos.is_empty() {
-            println!("No repositories configured. Add repositories first.");
-            return;
+            output!("No repositories configured. Add repositories first.");
+            return Ok(());
         }
         for repo in &self.config.repos {
-            let auth_status = if repo.auth_token.is_some() {
-                "🔐 Private".green()
+            let auth_status = if repo.keyring.is_some() || repo_credentials(repo).1.is_some() {
+                "🔐 Private".success()
             } else {
-                "🌐 Public".blue()
+                "🌐 Public".highlight()
             };
-            println!("{} - {} ({})", repo.name.bold(), repo.url, auth_status);
-            println!("   🪐Branch: {}", repo.branch);
+            output!("{} - {} ({})", repo.name.bold(), repo.url, auth_status);
+            if repo.disabled {
+                output!("{}", plain_text("   ⏸️ Disabled"));
+            }
+            output!("{}", plain_text(&format!("   🪐Branch: {}", repo.branch)));
+            if let Some(prefix) = &repo.path_prefix {
+                output!("{}", plain_text(&format!("   📂Path prefix: {}", prefix)));
+            }
+            if let Some(hint) = &repo.size_hint {
+                output!("{}", plain_text(&format!("   💽Size hint: {}", hint)));
+            }
+            if let Some(location) = RepoLocation::of(repo) {
+                output!("{}", plain_text(&format!("   🏠Provider: {}", location.provider)));
+            }
             if let Some(username) = &repo.username {
-                println!("   👤Username: {}", username);
+                output!("{}", plain_text(&format!("   👤Username: {}", username)));
+            }
+            if let Some(key) = &repo.keyring {
+                output!("{}", plain_text(&format!("   🗝️Keyring: {}", key)));
+            }
+            if let Some(key) = &repo.ssh_key {
+                output!("{}", plain_text(&format!("   🔑SSH key: {}", key)));
+            }
+            output!();
+        }
+        Ok(())
+    }
+    
+    pub fn export_config(
+        &self,
+        output: &str,
+        include_cache: bool,
+        reveal_secrets: bool,
+        filter: &ExportFilter,
+    ) -> Result<()> {
+        status!("📤 Exporting configuration to: {}", output);
+        
+        let selected = filter.apply(&self.config)?;
+        let export_config = if reveal_secrets {
+            warning!("⚠️  Exporting authentication tokens in clear text");
+            selected
+        } else {
+            selected.redacted()
+        };
+        
+        // 如果包含缓存信息，添加缓存状态
+        if include_cache {
+            status!("📦 Including cache information...");
+            // 这里可以添加缓存相关的元数据
+        }
+        
+        let content = serde_json::to_string_pretty(&export_config)
+            .context("Failed to serialize configuration")?;
+        
+        fs::write(output, content)
+            .with_context(|| format!("Failed to write configuration to: {}", output))?;
+        
+        status!("✅ Configuration exported successfully!");
+        output!(
+            "{}",
+            plain_text(&format!(
+                "📊 Exported {} repositories and {} templates",
+                export_config.repos.len(),
+                export_config.templates.len()
+            ))
+        );
+        
+        Ok(())
+    }
+    
+    /// Import a config file or URL, refusing it if `sha256` is given and does not match
+    pub async fn import_config(
+        &mut self,
+        file: &str,
+        mode: &str,
+        skip_validation: bool,
+        sha256: Option<&str>,
+    ) -> Result<()> {
+        status!("📥 Importing configuration from: {}", file);
+        
+        let content = Self::read_config_source(file).await?;
+        if let Some(expected) = sha256 {
+            verify_sha256(&content, expected, file)?;
+            status!("🔒 Checksum verified");
+        }
+        let config_content = String::from_utf8(content).context("Configuration is not valid UTF-8")?;
+        
+        let mut import_config = Config::parse(&config_content).context("Failed to parse configuration file")?;
+        
+        if !skip_validation {
+            self.validate_import_config(&import_config)?;
+        }
+        
+        // 统一语言写法，例如 "VueJS" -> "vue"
+        for template in &mut import_config.templates {
+            template.language = canonical_language(&template.language);
+        }
+        
+        match mode.to_lowercase().as_str() {
+            "merge" => {
+                status!("🔄 Merging configuration...");
+                self.merge_config(import_config)?;
+            }
+            "overwrite" => {
+                warning!("⚠️  Overwriting configuration...");
+                for repo in &mut import_config.repos {
+                    if repo.has_redacted_token() {
+                        repo.auth_token = None;
+                    }
+                }
+                for auth in import_config.hosts.values_mut() {
+                    if auth.has_redacted_token() {
+                        auth.auth_token = None;
+                    }
+                }
+                self.config = import_config;
+            }
+            _ => {
+                anyhow::bail!("Invalid import mode: {}. Use 'merge' or 'overwrite'", mode);
+            }
+        }
+        
+        self.commit_change(&format!("config import {}", file))?;
+        
+        status!("✅ Configuration imported successfully!");
+        output!(
+            "{}",
+            plain_text(&format!(
+                "📊 Current configuration: {} repositories and {} templates",
+                self.config.repos.len(),
+                self.config.templates.len()
+            ))
+        );
+        
+        Ok(())
+    }
+    
+    /// Content of a config file or http(s) URL
+    async fn read_config_source(source: &str) -> Result<Vec<u8>> {
+        if source.starts_with("https://") || source.starts_with("http://") {
+            ensure_online(&format!("download config {}", source))?;
+            let content = http_client(CONFIG_DOWNLOAD_TIMEOUT)?
+                .get(source)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await
+                .with_context(|| format!("Failed to download configuration: {}", source))?;
+            return Ok(content.to_vec());
+        }
+        fs::read(source).with_context(|| format!("Failed to read configuration file: {}", source))
+    }
+    
+    pub async fn add_registry(&mut self, name: &str, url: &str, sync: bool) -> Result<()> {
+        if self.config.registries.iter().any(|r| r.name == name) {
+            anyhow::bail!("Registry '{}' already exists, remove it first or pick another name", name);
+        }
+        self.config.registries.push(Registry {
+            name: name.to_string(),
+            url: url.to_string(),
+            synced_at: None,
+            repos: Vec::new(),
+            templates: Vec::new(),
+        });
+        self.commit_change(&format!("registry add {}", name))?;
+        status!("✅ Registry '{}' added", name);
+        
+        if sync {
+            self.sync_registries(Some(name)).await?;
+        } else {
+            output!("{}", plain_text(&format!("💡 Fetch its templates with: mammoth-cli registry sync {}", name)));
+        }
+        Ok(())
+    }
+    
+    /// Pull the index of one or every registry and merge its repositories and templates
+    /// into the config. Entries an earlier sync added and the index no longer lists are
+    /// removed; entries of other registries and your own entries are left alone.
+    pub async fn sync_registries(&mut self, name: Option<&str>) -> Result<()> {
+        let names: Vec<String> = match name {
+            Some(name) => {
+                if !self.config.registries.iter().any(|r| r.name == name) {
+                    anyhow::bail!("Registry '{}' not found", name);
+                }
+                vec![name.to_string()]
+            }
+            None => self.config.registries.iter().map(|r| r.name.clone()).collect(),
+        };
+        if names.is_empty() {
+            anyhow::bail!("No registries configured. Add one with 'registry add <name> <url>'");
+        }
+        
+        let mut failed = 0;
+        for name in &names {
+            if let Err(e) = self.sync_registry(name).await {
+                warning!("❌ Failed to sync registry '{}': {:#}", name, e);
+                failed += 1;
             }
-            println!();
         }
+        if failed > 0 {
+            anyhow::bail!("{} of {} registries failed to sync", failed, names.len());
+        }
+        Ok(())
     }
     
-    pub fn export_config(&self, output: &str, include_cache: bool) -> Result<()> {
-        println!("📤 Exporting configuration to: {}", output);
-        
-        let export_config = Config {
-            repos: self.config.repos.clone(),
-            templates: self.config.templates.clone(),
-        };
+    async fn sync_registry(&mut self, name: &str) -> Result<()> {
+        let index = self
+            .config
+            .registries
+            .iter()
+            .position(|r| r.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Registry '{}' not found", name))?;
+        let registry = self.config.registries[index].clone();
+        status!("🔄 Syncing registry '{}' from {}", registry.name, registry.url);
         
-        // 如果包含缓存信息，添加缓存状态
-        if include_cache {
-            println!("📦 Including cache information...");
-            // 这里可以添加缓存相关的元数据
+        let content = Self::read_config_source(&registry.url).await?;
+        let content = String::from_utf8(content).context("Registry index is not valid UTF-8")?;
+        let mut catalog = Config::parse(&content).context("Failed to parse registry index")?;
+        self.validate_import_config(&catalog)?;
+        for template in &mut catalog.templates {
+            template.language = canonical_language(&template.language);
         }
         
-        let content = serde_json::to_string_pretty(&export_config)
-            .context("Failed to serialize configuration")?;
-        
-        fs::write(output, content)
-            .with_context(|| format!("Failed to write configuration to: {}", output))?;
+        let repos: Vec<String> = catalog.repos.iter().map(|r| r.name.clone()).collect();
+        let templates: Vec<String> = catalog.templates.iter().map(Template::qualified_id).collect();
+        // 移除上次同步添加、索引中已不存在的条目
+        let stale_templates: Vec<&String> = registry.templates.iter().filter(|id| !templates.contains(id)).collect();
+        self.config
+            .templates
+            .retain(|t| !stale_templates.contains(&&t.qualified_id()));
+        let stale_repos: Vec<&String> = registry
+            .repos
+            .iter()
+            .filter(|name| !repos.contains(name))
+            .filter(|name| !self.config.templates.iter().any(|t| &&t.repo == name))
+            .collect();
+        self.config.repos.retain(|r| !stale_repos.contains(&&r.name));
         
-        println!("✅ Configuration exported successfully!");
-        println!(
-            "📊 Exported {} repositories and {} templates",
-            export_config.repos.len(),
-            export_config.templates.len()
-        );
+        self.merge_config(Config {
+            repos: catalog.repos,
+            templates: catalog.templates,
+            ..Config::default()
+        })?;
+        if !stale_templates.is_empty() || !stale_repos.is_empty() {
+            status!(
+                "🗑️  Removed {} repositories and {} templates no longer in the registry",
+                stale_repos.len(),
+                stale_templates.len()
+            );
+        }
         
+        let registry = &mut self.config.registries[index];
+        registry.synced_at = Some(unix_now());
+        registry.repos = repos;
+        registry.templates = templates;
+        self.commit_change(&format!("registry sync {}", name))?;
+        status!("✅ Registry '{}' synced", name);
         Ok(())
     }
     
-    pub fn import_config(&mut self, file: &str, mode: &str, skip_validation: bool) -> Result<()> {
-        println!("📥 Importing configuration from: {}", file);
-        
-        let config_content = fs::read_to_string(file)
-            .with_context(|| format!("Failed to read configuration file: {}", file))?;
-        
-        let import_config: Config =
-            serde_json::from_str(&config_content).context("Failed to parse configuration file")?;
-        
-        if !skip_validation {
-            self.validate_import_config(&import_config)?;
+    /// Forget a registry; `prune` also removes the repositories and templates it added
+    pub fn remove_registry(&mut self, name: &str, prune: bool) -> Result<()> {
+        let index = self
+            .config
+            .registries
+            .iter()
+            .position(|r| r.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Registry '{}' not found", name))?;
+        let registry = self.config.registries.remove(index);
+        if prune {
+            self.config
+                .templates
+                .retain(|t| !registry.templates.contains(&t.qualified_id()));
+            self.config.repos.retain(|r| {
+                !registry.repos.contains(&r.name) || self.config.templates.iter().any(|t| t.repo == r.name)
+            });
+        }
+        self.commit_change(&format!("registry remove {}", name))?;
+        if prune {
+            status!(
+                "🗑️  Registry '{}' removed with {} repositories and {} templates",
+                name,
+                registry.repos.len(),
+                registry.templates.len()
+            );
+        } else {
+            status!("🗑️  Registry '{}' removed, its repositories and templates are kept", name);
+        }
+        Ok(())
+    }
+    
+    pub fn list_registries(&self, json: bool) -> Result<()> {
+        if json {
+            output!("{}", serde_json::to_string_pretty(&self.config.registries)?);
+            return Ok(());
         }
         
-        match mode.to_lowercase().as_str() {
-            "merge" => {
-                println!("🔄 Merging configuration...");
-                self.merge_config(import_config)?;
-            }
-            "overwrite" => {
-                println!("⚠️  Overwriting configuration...");
-                self.config = import_config;
-            }
-            _ => {
-                anyhow::bail!("Invalid import mode: {}. Use 'merge' or 'overwrite'", mode);
+        output!("{}", "📚 Registries".header());
+        output!();
+        if self.config.registries.is_empty() {
+            output!("No registries configured. Add one with 'registry add <name> <url>'.");
+            return Ok(());
+        }
+        for registry in &self.config.registries {
+            output!("{} - {}", registry.name.bold(), registry.url);
+            match registry.synced_at {
+                Some(synced_at) => output!(
+                    "{}",
+                    plain_text(&format!(
+                        "   🔄Synced {} ago: {} repositories, {} templates",
+                        format_duration(Duration::from_secs(unix_now().saturating_sub(synced_at))),
+                        registry.repos.len(),
+                        registry.templates.len()
+                    ))
+                ),
+                None => output!("{}", plain_text("   🔄Never synced")),
             }
+            output!();
         }
-        
-        self.save_config()?;
-        
-        println!("✅ Configuration imported successfully!");
-        println!(
-            "📊 Current configuration: {} repositories and {} templates",
-            self.config.repos.len(),
-            self.config.templates.len()
-        );
-        
         Ok(())
     }
     
     pub fn validate_config_file(&self, file: &str) -> Result<()> {
-        println!("🔍 Validating configuration file: {}", file);
+        status!("🔍 Validating configuration file: {}", file);
         
         let config_content = fs::read_to_string(file)
             .with_context(|| format!("Failed to read configuration file: {}", file))?;
         
-        let config: Config =
-            serde_json::from_str(&config_content).context("Failed to parse configuration file")?;
+        let config = Config::parse(&config_content).context("Failed to parse configuration file")?;
         
         self.validate_import_config(&config)?;
         
-        println!("✅ Configuration file is valid!");
-        println!(
-            "📊 Contains {} repositories and {} templates",
-            config.repos.len(),
-            config.templates.len()
+        status!("✅ Configuration file is valid!");
+        output!(
+            "{}",
+            plain_text(&format!(
+                "📊 Contains {} repositories and {} templates",
+                config.repos.len(),
+                config.templates.len()
+            ))
         );
         
         Ok(())
     }
     
     fn validate_import_config(&self, import_config: &Config) -> Result<()> {
+        let (validation_errors, validation_warnings) = self.config_problems(import_config);
+        
+        // 报告错误和警告
+        if !validation_errors.is_empty() {
+            output!("{}", plain_text("❌ Validation errors:"));
+            for error in validation_errors {
+                output!("  {}", error);
+            }
+            anyhow::bail!("Configuration validation failed");
+        }
+        
+        if !validation_warnings.is_empty() {
+            output!("{}", plain_text("⚠️  Validation warnings:"));
+            for warning in validation_warnings {
+                output!("  {}", warning);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Errors and warnings found in `import_config`
+    fn config_problems(&self, import_config: &Config) -> (Vec<String>, Vec<String>) {
         let mut validation_errors = Vec::new();
         let mut validation_warnings = Vec::new();
         
@@ -618,9 +3530,41 @@ impl TemplateManager {
                 validation_errors
                     .push(format!("Repository '{}' branch cannot be empty", repo.name));
             }
+            if let Some(hint) = &repo.size_hint {
+                if let Err(e) = parse_size(hint) {
+                    validation_errors.push(format!("Repository '{}' size_hint: {}", repo.name, e));
+                }
+            }
+            if let Some(provider) = &repo.provider {
+                if let Err(e) = provider.parse::<Provider>() {
+                    validation_errors.push(format!("Repository '{}' provider: {}", repo.name, e));
+                }
+            }
+            if repo.has_redacted_token() {
+                validation_warnings.push(format!(
+                    "Repository '{}' auth token was redacted on export, set it again with 'repo add'",
+                    repo.name
+                ));
+            }
+        }
+        
+        for (host, auth) in &import_config.hosts {
+            if auth.has_redacted_token() {
+                validation_warnings.push(format!(
+                    "Host '{}' auth token was redacted on export, set it again in the hosts section",
+                    host
+                ));
+            }
         }
         
         // 验证模板配置
+        let custom_languages: Vec<String> = self
+            .config
+            .languages
+            .iter()
+            .chain(&import_config.languages)
+            .cloned()
+            .collect();
         for template in &import_config.templates {
             if template.id.is_empty() {
                 validation_errors.push("Template ID cannot be empty".to_string());
@@ -637,6 +3581,29 @@ impl TemplateManager {
             if template.path.is_empty() {
                 validation_errors.push(format!("Template '{}' path cannot be empty", template.id));
             }
+            if let Some(Err(e)) = template.color.as_deref().map(parse_color) {
+                validation_errors.push(format!("Template '{}': {}", template.id, e));
+            }
+            match normalize_language(&template.language, &custom_languages) {
+                Ok(language) if language != template.language => {
+                    validation_warnings.push(format!(
+                        "Template '{}' language '{}' will be imported as '{}'",
+                        template.id, template.language, language
+                    ));
+                }
+                Ok(_) => {}
+                Err(e) => validation_errors.push(format!("Template '{}': {}", template.id, e)),
+            }
+            
+            // 检查模板依赖是否存在
+            for dependency in &template.dependencies {
+                if import_config.template_index(dependency).is_err() {
+                    validation_warnings.push(format!(
+                        "Template '{}' depends on non-existent template '{}'",
+                        template.id, dependency
+                    ));
+                }
+            }
             
             // 检查模板引用的仓库是否存在
             if !import_config.repos.iter().any(|r| r.name == template.repo) {
@@ -647,23 +3614,39 @@ impl TemplateManager {
             }
         }
         
-        // 报告错误和警告
-        if !validation_errors.is_empty() {
-            println!("❌ Validation errors:");
-            for error in validation_errors {
-                println!("  {}", error);
+        // 验证缓存设置
+        if let Some(max_age) = &import_config.cache.max_age {
+            if let Err(e) = parse_duration(max_age) {
+                validation_errors.push(format!("cache.max_age: {}", e));
             }
-            anyhow::bail!("Configuration validation failed");
         }
         
-        if !validation_warnings.is_empty() {
-            println!("⚠️  Validation warnings:");
-            for warning in validation_warnings {
-                println!("  {}", warning);
+        // 验证提示超时
+        if let Some(timeout) = &import_config.prompt_timeout {
+            if let Err(e) = parse_duration(timeout) {
+                validation_errors.push(format!("prompt_timeout: {}", e));
             }
         }
         
-        Ok(())
+        // 验证主题设置
+        if let Err(e) = Theme::from_settings(&import_config.theme) {
+            validation_errors.push(format!("theme: {}", e));
+        }
+        
+        // 验证默认社区文件
+        if let Err(e) = parse_community_files(&import_config.defaults.community_files.join(",")) {
+            validation_errors.push(format!("defaults.community_files: {}", e));
+        }
+        if let Some(order) = &import_config.settings.picker_order {
+            if let Err(e) = validate_picker_order(order) {
+                validation_errors.push(format!("settings.picker_order: {}", e));
+            }
+        }
+        if let Err(e) = import_config.settings.package_manager() {
+            validation_errors.push(format!("settings.package_manager: {}", e));
+        }
+        
+        (validation_errors, validation_warnings)
     }
     
     fn merge_config(&mut self, import_config: Config) -> Result<()> {
@@ -681,9 +3664,16 @@ impl TemplateManager {
                 // 更新现有仓库
                 existing_repo.url = import_repo.url;
                 existing_repo.branch = import_repo.branch;
+                existing_repo.path_prefix = import_repo.path_prefix;
+                existing_repo.size_hint = import_repo.size_hint;
+                existing_repo.provider = import_repo.provider;
                 merged_repos += 1;
             } else {
-                // 添加新仓库
+                // 添加新仓库，脱敏的凭据不能当作真实令牌使用
+                let mut import_repo = import_repo;
+                if import_repo.has_redacted_token() {
+                    import_repo.auth_token = None;
+                }
                 self.config.repos.push(import_repo);
                 merged_repos += 1;
             }
@@ -695,7 +3685,7 @@ impl TemplateManager {
                 .config
                 .templates
                 .iter_mut()
-                .find(|t| t.id == import_template.id)
+                .find(|t| t.id == import_template.id && t.repo == import_template.repo)
             {
                 // 更新现有模板
                 *existing_template = import_template;
@@ -707,15 +3697,308 @@ impl TemplateManager {
             }
         }
         
-        println!(
-            "📊 Merged {} repositories and {} templates",
-            merged_repos, merged_templates
+        // 合并主机凭据，脱敏的令牌不覆盖本地令牌
+        for (host, mut auth) in import_config.hosts {
+            if auth.has_redacted_token() {
+                auth.auth_token = self.config.hosts.get(&host).and_then(|a| a.auth_token.clone());
+            }
+            self.config.hosts.insert(host, auth);
+        }
+        
+        // 合并自定义语言
+        for language in import_config.languages {
+            if !self.config.languages.contains(&language) {
+                self.config.languages.push(language);
+            }
+        }
+        
+        // 合并缓存设置
+        if import_config.cache.has_max_age() {
+            self.config.cache = import_config.cache;
+        }
+        
+        output!(
+            "{}",
+            plain_text(&format!(
+                "📊 Merged {} repositories and {} templates",
+                merged_repos, merged_templates
+            ))
         );
         
         Ok(())
     }
     
-    pub fn clean_templates(&mut self, all: bool, force: bool) -> Result<()> {
+    /// Cached templates and repository clones on disk, including entries of templates
+    /// and repositories that are no longer configured
+    fn cache_entries(&self) -> Result<Vec<CacheEntry>> {
+        let usage = Self::load_usage();
+        let modified = |path: &Path| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|age| age.as_secs())
+        };
+        let mut entries = Vec::new();
+        if !self.cache_dir.exists() {
+            return Ok(entries);
+        }
+        
+        for repo_dir in fs::read_dir(&self.cache_dir)? {
+            let repo_dir = repo_dir?.path();
+            let repo = repo_dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if !repo_dir.is_dir() || repo.starts_with('.') {
+                continue;
+            }
+            for template_dir in fs::read_dir(&repo_dir)? {
+                let path = template_dir?.path();
+                if !path.is_dir() {
+                    continue;
+                }
+                let id = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                let key = format!("{}/{}", repo, id);
+                let meta: Option<CacheMeta> = fs::read_to_string(repo_dir.join(format!("{}.meta.json", id)))
+                    .ok()
+                    .and_then(|content| serde_json::from_str(&content).ok());
+                entries.push(CacheEntry {
+                    configured: self.config.templates.iter().any(|t| t.qualified_id() == key),
+                    size: dir_size(&path),
+                    fetched_at: meta.map(|meta| meta.fetched_at).or_else(|| modified(&path)),
+                    last_used: usage.get(&key).map(|usage| usage.last_used),
+                    kind: CacheEntryKind::Template,
+                    key,
+                    path,
+                });
+            }
+        }
+        
+        let clones_dir = self.cache_dir.join(".repos");
+        if clones_dir.is_dir() {
+            for clone in fs::read_dir(&clones_dir)? {
+                let path = clone?.path();
+                let repo = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                // 克隆的最近使用时间取该仓库模板中最新的一个
+                let prefix = format!("{}/", repo);
+                let last_used = entries
+                    .iter()
+                    .filter(|e| e.key.starts_with(&prefix))
+                    .filter_map(|e| e.last_used)
+                    .max();
+                entries.push(CacheEntry {
+                    configured: self.config.repos.iter().any(|r| r.name == repo),
+                    size: dir_size(&path),
+                    fetched_at: modified(&path),
+                    last_used,
+                    kind: CacheEntryKind::Clone,
+                    key: repo,
+                    path,
+                });
+            }
+        }
+        Ok(entries)
+    }
+    
+    /// Print disk usage and last use of every cache entry, largest first
+    pub fn cache_stats(&self, json: bool) -> Result<()> {
+        let mut entries = self.cache_entries()?;
+        entries.sort_by(|a, b| b.size.cmp(&a.size));
+        if json {
+            output!("{}", serde_json::to_string_pretty(&entries)?);
+            return Ok(());
+        }
+        
+        output!("{}", "💾 Cache".header());
+        output!();
+        if entries.is_empty() {
+            output!("Cache is empty ({})", self.cache_dir.display());
+            return Ok(());
+        }
+        let now = unix_now();
+        let ago = |time: Option<u64>| match time {
+            Some(time) => format!("{} ago", format_duration(Duration::from_secs(now.saturating_sub(time)))),
+            None => "never".to_string(),
+        };
+        let width = entries.iter().map(|e| e.label().len()).max().unwrap_or(0).max(8);
+        output!("{:<width$}  {:>10}  {:<10}  {:<10}", "Entry", "Size", "Fetched", "Last used", width = width);
+        for entry in &entries {
+            let label = format!("{:<width$}", entry.label(), width = width);
+            output!(
+                "{}  {:>10}  {:<10}  {:<10}",
+                if entry.configured { label.normal() } else { label.dimmed() },
+                format_size(entry.size),
+                ago(entry.fetched_at),
+                ago(entry.last_used)
+            );
+        }
+        
+        let total: u64 = entries.iter().map(|e| e.size).sum();
+        let orphaned: Vec<&CacheEntry> = entries.iter().filter(|e| !e.configured).collect();
+        output!();
+        output!("Total: {} in {} entries ({})", format_size(total), entries.len(), self.cache_dir.display());
+        if !orphaned.is_empty() {
+            output!(
+                "{}",
+                plain_text(&format!(
+                    "💡 {} entries ({}) belong to removed templates or repositories, prune them with: mammoth-cli cache gc",
+                    orphaned.len(),
+                    format_size(orphaned.iter().map(|e| e.size).sum())
+                ))
+            );
+        }
+        Ok(())
+    }
+    
+    /// Remove cache entries of removed templates and repositories, entries not used within
+    /// `unused_for`, and then the least recently used entries until the cache fits `max_size`
+    pub fn cache_gc(
+        &self,
+        max_size: Option<&str>,
+        unused_for: Option<&str>,
+        dry_run: bool,
+        trash: Option<bool>,
+    ) -> Result<()> {
+        let max_size = max_size.map(parse_size).transpose()?;
+        let unused_for = unused_for.map(parse_duration).transpose()?;
+        let now = unix_now();
+        
+        let mut entries = self.cache_entries()?;
+        // 最久未使用的排在前面
+        entries.sort_by_key(|e| e.last_activity());
+        let mut total: u64 = entries.iter().map(|e| e.size).sum();
+        let mut pruned: Vec<(CacheEntry, &str)> = Vec::new();
+        let mut kept = Vec::new();
+        for entry in entries {
+            let stale = unused_for.is_some_and(|max_age| now.saturating_sub(entry.last_activity()) > max_age.as_secs());
+            let reason = if !entry.configured {
+                Some("no longer configured")
+            } else if stale {
+                Some("unused")
+            } else {
+                None
+            };
+            match reason {
+                Some(reason) => {
+                    total -= entry.size;
+                    pruned.push((entry, reason));
+                }
+                None => kept.push(entry),
+            }
+        }
+        if let Some(max_size) = max_size {
+            for entry in kept {
+                if total <= max_size {
+                    break;
+                }
+                total -= entry.size;
+                pruned.push((entry, "over size limit"));
+            }
+        }
+        
+        if pruned.is_empty() {
+            status!("✨ Nothing to prune, the cache uses {}", format_size(total));
+            return Ok(());
+        }
+        let freed: u64 = pruned.iter().map(|(e, _)| e.size).sum();
+        let use_trash = Self::default_trash(trash);
+        for (entry, reason) in &pruned {
+            if !dry_run {
+                Self::remove_cache_entry(entry, use_trash)?;
+            }
+            output!(
+                "{}",
+                plain_text(&format!("  🗑️  {} ({}, {})", entry.label(), format_size(entry.size), reason))
+            );
+        }
+        output!();
+        if dry_run {
+            output!("Would free {} in {} entries, leaving {}", format_size(freed), pruned.len(), format_size(total));
+        } else {
+            status!("🧹 Freed {} in {} entries, the cache now uses {}", format_size(freed), pruned.len(), format_size(total));
+        }
+        Ok(())
+    }
+    
+    /// Trash or permanent removal for cache pruning: the explicit choice, else the trash
+    /// on interactive runs
+    fn default_trash(trash: Option<bool>) -> bool {
+        trash.unwrap_or_else(|| is_interactive() && std::io::stdin().is_terminal())
+    }
+    
+    /// Remove one cached template or clone, including the template's metadata file
+    fn remove_cache_entry(entry: &CacheEntry, use_trash: bool) -> Result<()> {
+        remove_path(&entry.path, use_trash)
+            .with_context(|| format!("Failed to remove {}", entry.path.display()))?;
+        if entry.kind == CacheEntryKind::Template {
+            let meta = entry.path.with_file_name(format!(
+                "{}.meta.json",
+                entry.path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            if meta.exists() {
+                let _ = remove_path(&meta, use_trash);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Evict the cache of one template ("id" or "repo/id", also of removed templates), or
+    /// of every template and the clone of `repo`, leaving the rest of the cache alone
+    pub fn cache_clean(&self, template_id: Option<&str>, repo: Option<&str>, trash: Option<bool>) -> Result<()> {
+        let entries = self.cache_entries()?;
+        let (target, selected): (String, Vec<&CacheEntry>) = match (template_id, repo) {
+            (Some(id), _) => {
+                let selected: Vec<&CacheEntry> = entries
+                    .iter()
+                    .filter(|e| e.kind == CacheEntryKind::Template)
+                    .filter(|e| e.key == id || e.key.rsplit_once('/').is_some_and(|(_, entry_id)| entry_id == id))
+                    .collect();
+                if selected.len() > 1 {
+                    let keys: Vec<&str> = selected.iter().map(|e| e.key.as_str()).collect();
+                    anyhow::bail!("'{}' is cached for several repositories, use one of: {}", id, keys.join(", "));
+                }
+                if selected.is_empty() {
+                    // 未缓存的已配置模板不算错误
+                    self.find_template(id)?;
+                }
+                (format!("template '{}'", id), selected)
+            }
+            (None, Some(repo)) => {
+                let prefix = format!("{}/", repo);
+                let selected: Vec<&CacheEntry> = entries
+                    .iter()
+                    .filter(|e| match e.kind {
+                        CacheEntryKind::Template => e.key.starts_with(&prefix),
+                        CacheEntryKind::Clone => e.key == repo,
+                    })
+                    .collect();
+                if selected.is_empty() && self.get_repo_by_name(repo).is_none() {
+                    anyhow::bail!("Repository '{}' not found", repo);
+                }
+                (format!("repository '{}'", repo), selected)
+            }
+            (None, None) => anyhow::bail!("Name a template or pass --repo"),
+        };
+        
+        if selected.is_empty() {
+            status!("ℹ️  Nothing cached for {}", target);
+            return Ok(());
+        }
+        let use_trash = Self::default_trash(trash);
+        for entry in &selected {
+            Self::remove_cache_entry(entry, use_trash)?;
+            status!("  🗑️  {} ({})", entry.label(), format_size(entry.size));
+        }
+        let freed: u64 = selected.iter().map(|e| e.size).sum();
+        status!("🧹 Cache of {} cleaned, {} freed", target, format_size(freed));
+        Ok(())
+    }
+    
+    /// Clean cache (and config with `all`). `trash` chooses between the OS trash and
+    /// permanent removal, defaulting to the trash on interactive runs.
+    pub fn clean_templates(&mut self, all: bool, force: bool, trash: Option<bool>) -> Result<()> {
+        // 非交互运行（例如 CI）视为 --force
+        let force = force || !is_interactive();
+        let use_trash = trash.unwrap_or(!force && std::io::stdin().is_terminal());
+        
         if !force {
             let message = if all {
                 "⚠️  This will remove ALL templates, cache, and configuration. Are you sure?"
@@ -723,115 +4006,398 @@ impl TemplateManager {
                 "⚠️  This will remove ALL cached template files. Are you sure?"
             };
             
-            let confirm = Confirm::new()
-                .with_prompt(message)
-                .default(false)
-                .interact()?;
+            let confirm = !prompt_timed_out(message, "no")?
+                && Confirm::new()
+                    .with_prompt(message)
+                    .default(false)
+                    .interact()?;
             
             if !confirm {
-                println!("❌ Clean operation cancelled");
+                output!("{}", plain_text("❌ Clean operation cancelled"));
                 return Ok(());
             }
         }
         
-        println!("🧹 Cleaning templates...");
+        status!("🧹 Cleaning templates...");
+        if use_trash {
+            status!("🗑️  Moving files to the trash, restore them from there if needed");
+        }
         
         // 清理缓存目录
         if self.cache_dir.exists() {
-            match fs::remove_dir_all(&self.cache_dir) {
-                Ok(_) => println!("✅ Cache directory cleaned"),
-                Err(e) => println!("⚠️  Failed to clean cache directory: {}", e),
+            match remove_path(&self.cache_dir, use_trash) {
+                Ok(_) => status!("✅ Cache directory cleaned"),
+                Err(e) => warning!("⚠️  Failed to clean cache directory: {}", e),
             }
         }
         
         // 重新创建缓存目录
         fs::create_dir_all(&self.cache_dir).context("Failed to recreate cache directory")?;
+        // 缓存已经删除，审计日志写不进去也不算失败
+        if let Err(e) = self.record_audit(if all { "clean --all" } else { "clean" }) {
+            warning!("⚠️  Failed to record the audit log entry: {:#}", e);
+        }
         
         if all {
             // 清理配置文件
             let config_path = Self::get_config_path()?;
             if config_path.exists() {
-                match fs::remove_file(&config_path) {
-                    Ok(_) => println!("✅ Configuration file removed"),
-                    Err(e) => println!("⚠️  Failed to remove configuration file: {}", e),
+                self.record_snapshot("clean --all")?;
+                match remove_path(&config_path, use_trash) {
+                    Ok(_) => status!("✅ Configuration file removed"),
+                    Err(e) => warning!("⚠️  Failed to remove configuration file: {}", e),
                 }
             }
             
             // 重置配置
-            self.config = Config {
-                repos: vec![],
-                templates: vec![],
-            };
+            self.config = Config::default();
         }
         
-        println!("🎉 Clean operation completed!");
+        status!("🎉 Clean operation completed!");
         if all {
-            println!("📝 Configuration has been reset to empty state");
+            status!("📝 Configuration has been reset to empty state");
+        } else {
+            status!("💾 Configuration preserved, only cache was cleaned");
+        }
+        
+        Ok(())
+    }
+    
+    /// Print where mammoth keeps its files and which tools it found, for bug reports
+    /// and scripts that need to locate mammoth's directories
+    pub fn show_env(&self, json: bool) -> Result<()> {
+        let config_path = Self::get_config_path()?;
+        let package_managers: BTreeMap<&str, Option<String>> = ["npm", "pnpm", "yarn", "bun"]
+            .into_iter()
+            .map(|tool| (tool, tool_version(tool)))
+            .collect();
+        let env = serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "paths": {
+                "config": config_path.display().to_string(),
+                "cache": self.cache_dir.display().to_string(),
+                "history": Self::get_journal_dir()?.display().to_string(),
+                "audit_log": Self::get_audit_log_path()?.display().to_string(),
+                "usage": Self::get_usage_path()?.display().to_string(),
+            },
+            "extends": self.config.extends,
+            "git": {
+                "path": git_binary().display().to_string(),
+                "version": tool_version(&git_binary().to_string_lossy()),
+                "backend": format!("{:?}", git_backend()).to_lowercase(),
+            },
+            "node": tool_version("node"),
+            "package_managers": package_managers,
+            "platform": {
+                "os": std::env::consts::OS,
+                "arch": std::env::consts::ARCH,
+                "ci": is_ci_mode(),
+                "interactive": is_interactive(),
+                "locale": current_locale(),
+            },
+        });
+        if json {
+            output!("{}", serde_json::to_string_pretty(&env)?);
+            return Ok(());
+        }
+        
+        let missing = || "not found".dimmed().to_string();
+        let show = |value: &serde_json::Value| value.as_str().map_or_else(missing, |v| v.to_string());
+        output!("{}", "🧭 Environment".header());
+        output!();
+        output!("mammoth-cli: {}", env!("CARGO_PKG_VERSION"));
+        output!("Platform: {} ({})", std::env::consts::OS, std::env::consts::ARCH);
+        output!("CI mode: {}", is_ci_mode());
+        output!("Locale: {}", current_locale());
+        output!();
+        output!("{}", "📁 Paths".section());
+        if let Some(paths) = env["paths"].as_object() {
+            for (name, path) in paths {
+                output!("  {}: {}", name, show(path));
+            }
+        }
+        for source in &self.config.extends {
+            output!("  extends: {}", source);
+        }
+        output!();
+        output!("{}", "🛠️  Tools".section());
+        output!(
+            "  git: {} ({}, {} backend)",
+            show(&env["git"]["version"]),
+            show(&env["git"]["path"]),
+            show(&env["git"]["backend"])
+        );
+        output!("  node: {}", show(&env["node"]));
+        for (tool, version) in &package_managers {
+            output!("  {}: {}", tool, version.clone().unwrap_or_else(missing));
+        }
+        Ok(())
+    }
+    
+    /// Check git, the config file, the cache directory and every repository, printing
+    /// a fix for each problem. `loaded` is the result of loading the config, so that a
+    /// broken config file is reported instead of aborting. Fails when a check fails.
+    pub async fn doctor(loaded: Result<Self>, json: bool) -> Result<()> {
+        let mut checks = Vec::new();
+        let config_path = Self::get_config_path()?;
+        
+        let manager = match loaded {
+            Ok(manager) => {
+                let (errors, warnings) = manager.config_problems(&manager.config);
+                let summary = format!(
+                    "{} ({} repositories, {} templates)",
+                    config_path.display(),
+                    manager.config.repos.len(),
+                    manager.config.templates.len()
+                );
+                checks.push(if !errors.is_empty() {
+                    DoctorCheck::new("config", CheckStatus::Error, errors.join("; "))
+                        .with_fix(format!("Correct these entries in {}", config_path.display()))
+                } else if !warnings.is_empty() {
+                    DoctorCheck::new("config", CheckStatus::Warning, warnings.join("; "))
+                } else {
+                    DoctorCheck::new("config", CheckStatus::Ok, summary)
+                });
+                if let Some(project) = manager.project_config() {
+                    checks.push(DoctorCheck::new(
+                        "project config",
+                        CheckStatus::Ok,
+                        format!(
+                            "{} ({} repositories, {} templates)",
+                            project.path.display(),
+                            project.config.repos.len(),
+                            project.config.templates.len()
+                        ),
+                    ));
+                }
+                Some(manager)
+            }
+            Err(e) => {
+                checks.push(
+                    DoctorCheck::new("config", CheckStatus::Error, format!("{:#}", e)).with_fix(format!(
+                        "Fix the syntax in {} or move the file away to start with an empty configuration",
+                        config_path.display()
+                    )),
+                );
+                None
+            }
+        };
+        
+        checks.push(match manager.as_ref().map(|manager| manager.apply_git_settings()) {
+            Some(Err(e)) => DoctorCheck::new("git", CheckStatus::Error, format!("{:#}", e))
+                .with_fix("Check settings.git_backend and settings.git_path, or the MAMMOTH_GIT variable"),
+            _ => Self::check_git(),
+        });
+        checks.push(Self::check_cache_dir());
+        
+        match &manager {
+            Some(manager) if manager.config.repos.is_empty() => checks.push(
+                DoctorCheck::new("repositories", CheckStatus::Warning, "No repositories configured")
+                    .with_fix("Add one with: mammoth-cli repo add <name> --url <url>"),
+            ),
+            Some(_) if is_offline() => checks.push(DoctorCheck::new(
+                "repositories",
+                CheckStatus::Warning,
+                "Not checked in offline mode",
+            )),
+            Some(manager) => checks.extend(manager.check_repos().await?),
+            None => {}
+        }
+        
+        if json {
+            output!("{}", serde_json::to_string_pretty(&checks)?);
         } else {
-            println!("💾 Configuration preserved, only cache was cleaned");
+            output!("{}", "🩺 Doctor".header());
+            output!();
+            for check in &checks {
+                let mark = match (check.status, is_plain_mode()) {
+                    (CheckStatus::Ok, true) => "[ok]".normal(),
+                    (CheckStatus::Warning, true) => "[warning]".normal(),
+                    (CheckStatus::Error, true) => "[error]".normal(),
+                    (CheckStatus::Ok, false) => "✅".success(),
+                    (CheckStatus::Warning, false) => "⚠️ ".warning(),
+                    (CheckStatus::Error, false) => "❌".error(),
+                };
+                output!("{} {}: {}", mark, check.check.bold(), check.detail);
+                if let Some(fix) = &check.fix {
+                    output!("   {}", plain_text(&format!("💡 {}", fix)));
+                }
+            }
+            output!();
         }
         
+        let errors = checks.iter().filter(|c| c.status == CheckStatus::Error).count();
+        let warnings = checks.iter().filter(|c| c.status == CheckStatus::Warning).count();
+        if errors > 0 {
+            anyhow::bail!("doctor found {} problems and {} warnings", errors, warnings);
+        }
+        if !json {
+            if warnings > 0 {
+                status!("⚠️  No problems found, {} warnings", warnings);
+            } else {
+                status!("{}", "🎉 No problems found".success());
+            }
+        }
         Ok(())
     }
     
+    /// git executable and version, or the library backend in use
+    fn check_git() -> DoctorCheck {
+        let version = tool_version(&git_binary().to_string_lossy());
+        if git_backend() != GitBackend::Cli {
+            let backend = format!("{:?} backend", git_backend()).to_lowercase();
+            return match version {
+                Some(version) => DoctorCheck::new("git", CheckStatus::Ok, format!("{}, {}", backend, version)),
+                None => DoctorCheck::new("git", CheckStatus::Warning, format!("{}, git executable not found", backend))
+                    .with_fix("Install git for 'new --keep-history', 'template changelog' and 'upgrade'"),
+            };
+        }
+        let Some(version) = version else {
+            return DoctorCheck::new(
+                "git",
+                CheckStatus::Error,
+                format!("'{}' not found", git_binary().display()),
+            )
+            .with_fix("Install git from https://git-scm.com or point settings.git_path at it");
+        };
+        match parse_git_version(&version) {
+            Some(found) if found < MIN_GIT_VERSION => DoctorCheck::new("git", CheckStatus::Error, version)
+                .with_fix(format!(
+                    "Upgrade git to {}.{} or newer, older versions have no sparse checkout",
+                    MIN_GIT_VERSION.0, MIN_GIT_VERSION.1
+                )),
+            _ => DoctorCheck::new("git", CheckStatus::Ok, version),
+        }
+    }
+    
+    /// Whether the cache directory is writable, how big it is and how much space is left
+    fn check_cache_dir() -> DoctorCheck {
+        let cache_dir = match Self::get_cache_dir() {
+            Ok(cache_dir) => cache_dir,
+            Err(e) => return DoctorCheck::new("cache", CheckStatus::Error, format!("{:#}", e)),
+        };
+        let probe = cache_dir.join(".doctor-probe");
+        let writable = fs::create_dir_all(&cache_dir)
+            .and_then(|_| fs::write(&probe, b"ok"))
+            .and_then(|_| fs::remove_file(&probe));
+        if let Err(e) = writable {
+            return DoctorCheck::new(
+                "cache",
+                CheckStatus::Error,
+                format!("{} is not writable: {}", cache_dir.display(), e),
+            )
+            .with_fix(format!("Fix the permissions of {}", cache_dir.display()));
+        }
+        
+        let detail = format!("{} ({})", cache_dir.display(), format_size(dir_size(&cache_dir)));
+        match fs2::available_space(&cache_dir) {
+            Ok(available) if available < LOW_DISK_SPACE => DoctorCheck::new(
+                "cache",
+                CheckStatus::Warning,
+                format!("{}, only {} free", detail, format_size(available)),
+            )
+            .with_fix("Free disk space or remove cached templates with: mammoth-cli clean"),
+            _ => DoctorCheck::new("cache", CheckStatus::Ok, detail),
+        }
+    }
+    
+    /// Reachability, credentials and branch of every enabled repository, checked concurrently
+    async fn check_repos(&self) -> Result<Vec<DoctorCheck>> {
+        let mut lookups = tokio::task::JoinSet::new();
+        for (index, repo) in self.config.repos.iter().filter(|r| !r.disabled).cloned().enumerate() {
+            lookups.spawn(async move { (index, check_repo_health(&repo).await) });
+        }
+        let mut results = Vec::new();
+        while let Some(lookup) = lookups.join_next().await {
+            results.push(lookup?);
+        }
+        results.sort_by_key(|(index, _)| *index);
+        
+        Ok(results
+            .into_iter()
+            .map(|(_, health)| {
+                let check = format!("repo {}", health.repo);
+                if health.is_healthy() {
+                    return DoctorCheck::new(&check, CheckStatus::Ok, format!("reachable ({}ms)", health.latency_ms));
+                }
+                let error = health.error.clone().unwrap_or_default();
+                let fix = if !health.reachable {
+                    "Check the URL, your network and proxy settings (HTTPS_PROXY)".to_string()
+                } else if !health.auth_ok {
+                    format!(
+                        "Store a token with 'mammoth-cli repo auth {0}' or set an SSH key with 'mammoth-cli repo update {0} --ssh-key <path>'",
+                        health.repo
+                    )
+                } else {
+                    format!("Set an existing branch with: mammoth-cli repo update {} --branch <branch>", health.repo)
+                };
+                DoctorCheck::new(&check, CheckStatus::Error, error).with_fix(fix)
+            })
+            .collect())
+    }
+    
     pub fn show_info(&self, json: bool) -> Result<()> {
         if json {
             // 以JSON格式显示配置
-            let config_json = serde_json::to_string_pretty(&self.config)
+            let config_json = serde_json::to_string_pretty(&self.config.redacted())
                 .context("Failed to serialize configuration")?;
-            println!("{}", config_json);
+            output!("{}", config_json);
         } else {
             // 以友好格式显示配置信息
-            println!("{}", "📋 Current Configuration".bold().blue());
-            println!();
+            output!("{}", "📋 Current Configuration".header());
+            output!();
+            
+            // 显示继承的配置
+            if !self.config.extends.is_empty() {
+                output!("{}", "📚 Extends".section());
+                for source in &self.config.extends {
+                    output!("  {}", source);
+                }
+                output!();
+            }
             
             // 显示仓库信息
-            println!("{}", "📦 Repositories".bold().yellow());
+            output!("{}", "📦 Repositories".section());
             if self.config.repos.is_empty() {
-                println!("  No repositories configured");
+                output!("  No repositories configured");
             } else {
                 for repo in &self.config.repos {
-                    let auth_status = if repo.auth_token.is_some() {
-                        "🔐 Private".green()
+                    let auth_status = if repo.keyring.is_some() || repo_credentials(repo).1.is_some() {
+                        "🔐 Private".success()
                     } else {
-                        "🌐 Public".blue()
+                        "🌐 Public".highlight()
                     };
-                    println!("  {} - {} ({})", repo.name.bold(), repo.url, auth_status);
-                    println!("    Branch: {}", repo.branch);
+                    output!("  {} - {} ({})", repo.name.bold(), repo.url, auth_status);
+                    output!("    Branch: {}", repo.branch);
                     if let Some(username) = &repo.username {
-                        println!("    Username: {}", username);
+                        output!("    Username: {}", username);
                     }
                 }
             }
-            println!();
+            output!();
             
             // 显示模板信息
-            println!("{}", "🎨 Templates".bold().yellow());
+            output!("{}", "🎨 Templates".section());
             if self.config.templates.is_empty() {
-                println!("  No templates configured");
+                output!("  No templates configured");
             } else {
                 for template in &self.config.templates {
                     let cache_path = self.get_template_cache_path(template);
-                    let status = if cache_path.exists() {
-                        "✅".green()
-                    } else {
-                        "❌".red()
-                    };
+                    let status = self.status_marker(template);
                     
-                    println!("  {} {} - {}", status, template.id.bold(), template.name);
-                    println!("    Description: {}", template.description);
-                    println!("    Language: {}", template.language);
-                    println!("    Repository: {}", template.repo);
-                    println!("    Path: {}", template.path);
-                    println!("    Tags: {}", template.tags.join(", "));
-                    println!();
+                    output!("  {} {} - {}", status, self.styled_id(template), template.name);
+                    if cache_path.exists() {
+                        output!("    Size: {}", format_size(dir_size(&cache_path)));
+                    }
+                    self.print_template_details(template, "    ");
+                    output!();
                 }
             }
             
             // 显示统计信息
-            println!("{}", "📊 Statistics".bold().yellow());
-            println!("  Repositories: {}", self.config.repos.len());
-            println!("  Templates: {}", self.config.templates.len());
+            output!("{}", "📊 Statistics".section());
+            output!("  Repositories: {}", self.config.repos.len());
+            output!("  Templates: {}", self.config.templates.len());
             
             // 显示缓存状态
             let cached_count = self
@@ -840,20 +4406,28 @@ impl TemplateManager {
                 .iter()
                 .filter(|t| self.get_template_cache_path(t).exists())
                 .count();
-            println!(
+            output!(
                 "  Cached templates: {}/{}",
                 cached_count,
                 self.config.templates.len()
             );
             
+            // 显示缓存占用
+            let clones_size = dir_size(&self.cache_dir.join(".repos"));
+            output!(
+                "  Cache size: {} (repository clones: {})",
+                format_size(dir_size(&self.cache_dir)),
+                format_size(clones_size)
+            );
+            
             // 显示配置路径
-            println!();
-            println!("{}", "📁 Paths".bold().yellow());
+            output!();
+            output!("{}", "📁 Paths".section());
             match Self::get_config_path() {
-                Ok(path) => println!("  Config: {}", path.display()),
-                Err(_) => println!("  Config: Unable to determine path"),
+                Ok(path) => output!("  Config: {}", path.display()),
+                Err(_) => output!("  Config: Unable to determine path"),
             }
-            println!("  Cache: {}", self.cache_dir.display());
+            output!("  Cache: {}", self.cache_dir.display());
         }
         
         Ok(())
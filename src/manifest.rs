@@ -0,0 +1,316 @@
+use crate::codemod::CodePatch;
+use anyhow::{Context, Result};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Manifest file name at the root of a template
+pub const MANIFEST_FILE: &str = "mammoth.json";
+
+/// TOML spelling of the manifest, read when there is no `mammoth.json`
+pub const MANIFEST_TOML_FILE: &str = "mammoth.toml";
+
+/// Whether a template-relative path is a manifest, which never ends up in projects
+pub fn is_manifest_file(relative: &Path) -> bool {
+    relative == Path::new(MANIFEST_FILE) || relative == Path::new(MANIFEST_TOML_FILE)
+}
+
+/// Kind of value a template variable holds, decides how it is prompted for
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableKind {
+    #[default]
+    String,
+    Bool,
+    Number,
+    /// One of `choices`
+    Select,
+}
+
+/// Extra variable a template asks for when a project is generated
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TemplateVariable {
+    /// Name used as `{{name}}` in placeholders and next steps
+    pub name: String,
+    /// Question shown to the user, the name when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Default answer, a string, number or boolean
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
+    #[serde(default, rename = "type")]
+    pub kind: VariableKind,
+    /// Allowed answers of a `select` variable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+}
+
+/// A string, number or boolean from a manifest or values file as answer text
+pub fn value_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Variables every project gets, templates cannot redeclare them
+pub const BUILTIN_VARIABLES: [&str; 8] = [
+    "name",
+    "author",
+    "author_email",
+    "author_url",
+    "description",
+    "output_dir",
+    "template",
+    "language",
+];
+
+impl TemplateVariable {
+    pub fn prompt_text(&self) -> &str {
+        self.prompt.as_deref().unwrap_or(&self.name)
+    }
+    
+    /// Default answer as text, the first choice for `select` variables without one
+    pub fn default_value(&self) -> Option<String> {
+        match &self.default {
+            Some(serde_json::Value::Null) | None => match self.kind {
+                VariableKind::Select => self.choices.first().cloned(),
+                VariableKind::Bool => Some("false".to_string()),
+                _ => None,
+            },
+            Some(value) => Some(value_text(value)),
+        }
+    }
+    
+    /// Check an answer against the variable type, returning its normalized form
+    pub fn parse_value(&self, value: &str) -> Result<String> {
+        let value = value.trim();
+        match self.kind {
+            VariableKind::String => Ok(value.to_string()),
+            VariableKind::Bool => match value.to_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" => Ok("true".to_string()),
+                "false" | "no" | "n" | "0" => Ok("false".to_string()),
+                _ => anyhow::bail!("'{}' expects yes or no, got '{}'", self.name, value),
+            },
+            VariableKind::Number => {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| anyhow::anyhow!("'{}' expects a number, got '{}'", self.name, value))?;
+                Ok(value.to_string())
+            }
+            VariableKind::Select => {
+                if !self.choices.iter().any(|choice| choice == value) {
+                    anyhow::bail!(
+                        "'{}' must be one of {}, got '{}'",
+                        self.name,
+                        self.choices.join(", "),
+                        value
+                    );
+                }
+                Ok(value.to_string())
+            }
+        }
+    }
+    
+    fn validate(&self) -> Result<()> {
+        if BUILTIN_VARIABLES.contains(&self.name.as_str()) {
+            anyhow::bail!("Template variable '{}' is built in and cannot be redeclared", self.name);
+        }
+        if self.name.is_empty() || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!("Template variable name '{}' may only contain letters, digits and '_'", self.name);
+        }
+        if self.kind == VariableKind::Select && self.choices.is_empty() {
+            anyhow::bail!("Template variable '{}' is a select without choices", self.name);
+        }
+        if let Some(default) = self.default_value() {
+            self.parse_value(&default)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parsed file condition: `name`, `!name`, `name == value` or `name != value`
+enum Condition<'a> {
+    Set(&'a str),
+    Unset(&'a str),
+    Equals(&'a str, &'a str),
+    NotEquals(&'a str, &'a str),
+}
+
+impl<'a> Condition<'a> {
+    fn parse(condition: &'a str) -> Result<Self> {
+        let unquote = |value: &'a str| value.trim().trim_matches(|c| c == '\'' || c == '"');
+        let trimmed = condition.trim();
+        let parsed = if let Some((name, value)) = trimmed.split_once("!=") {
+            Condition::NotEquals(name.trim(), unquote(value))
+        } else if let Some((name, value)) = trimmed.split_once("==") {
+            Condition::Equals(name.trim(), unquote(value))
+        } else if let Some(name) = trimmed.strip_prefix('!') {
+            Condition::Unset(name.trim())
+        } else {
+            Condition::Set(trimmed)
+        };
+        let name = parsed.variable();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!(
+                "Condition '{}' must be 'variable', '!variable', 'variable == value' or 'variable != value'",
+                condition
+            );
+        }
+        Ok(parsed)
+    }
+    
+    fn variable(&self) -> &'a str {
+        match *self {
+            Condition::Set(name)
+            | Condition::Unset(name)
+            | Condition::Equals(name, _)
+            | Condition::NotEquals(name, _) => name,
+        }
+    }
+}
+
+/// Whether a file condition holds for the project variables. A bare variable holds when
+/// it is set to something other than "", "false" or "0"; unknown variables are unset.
+pub fn evaluate_condition(condition: &str, vars: &HashMap<String, String>) -> Result<bool> {
+    let value = |name: &str| vars.get(name).map(String::as_str).unwrap_or_default();
+    let is_set = |name: &str| !matches!(value(name), "" | "false" | "0");
+    Ok(match Condition::parse(condition)? {
+        Condition::Set(name) => is_set(name),
+        Condition::Unset(name) => !is_set(name),
+        Condition::Equals(name, expected) => value(name) == expected,
+        Condition::NotEquals(name, expected) => value(name) != expected,
+    })
+}
+
+/// Variable a file condition tests, for checking that it is declared
+pub fn condition_variable(condition: &str) -> Result<&str> {
+    Ok(Condition::parse(condition)?.variable())
+}
+
+/// How a file of a template layer combines with the same file written by an earlier
+/// layer, e.g. a base template and the addons applied over it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeRule {
+    /// The later layer's file wins (default)
+    #[default]
+    Replace,
+    /// The earlier layer's file is kept
+    Keep,
+    /// The later layer's content is appended, e.g. .gitignore or .env.example
+    Append,
+    /// JSON files are deep merged, the later layer winning on conflicting keys
+    Json,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TemplateManifest {
+    /// Version of the template itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Display color, used when the config does not set one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Display emoji/icon, used when the config does not set one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Version requirement on mammoth-cli, e.g. ">=0.3"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires_mammoth: Option<String>,
+    /// JSON merge patch applied to the project's package.json, e.g. extra scripts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_json: Option<serde_json::Value>,
+    /// Packages added to the project's package.json dependencies, name → version spec
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, String>,
+    /// Packages added to the project's package.json devDependencies
+    #[serde(default, alias = "devDependencies", skip_serializing_if = "BTreeMap::is_empty")]
+    pub dev_dependencies: BTreeMap<String, String>,
+    /// Structured edits of JS/TS files such as vite.config.ts, e.g. registering a plugin
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub code_patches: Vec<CodePatch>,
+    /// SPDX license inserted as a header into generated source files, e.g. "MIT"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license_header: Option<String>,
+    /// Text replaced in every text file of the generated project, placeholder → value with
+    /// `{{variable}}` interpolation, e.g. `"vite-vue-starter": "{{name}}"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub placeholders: BTreeMap<String, String>,
+    /// Files and directories of this template left out of generated projects, e.g.
+    /// "docs", ".github", "screenshots/*.png"; `*` matches within one path segment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prune: Vec<String>,
+    /// Merge rules for files an earlier layer already wrote, path pattern → rule, e.g.
+    /// `".gitignore": "append"`; package.json is always merged
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub merge: BTreeMap<String, MergeRule>,
+    /// Files and directories of this template only generated when a condition on the
+    /// variables holds, path pattern → condition, e.g. `"src/router": "use_router"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub conditions: BTreeMap<String, String>,
+    /// Files and directories of this template renamed in generated projects, path → new
+    /// path with `{{variable}}` interpolation, e.g. `"_gitignore": ".gitignore"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rename: BTreeMap<String, String>,
+    /// Extra variables prompted for when a project is generated
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<TemplateVariable>,
+    /// Hints printed after generation, supports `{{variable}}` interpolation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub next_steps: Vec<String>,
+}
+
+impl TemplateManifest {
+    /// Load `mammoth.json` or else `mammoth.toml` from a template directory, `None` if
+    /// the template has neither
+    pub fn load(template_dir: &Path) -> Result<Option<Self>> {
+        let manifest_path = [MANIFEST_FILE, MANIFEST_TOML_FILE]
+            .iter()
+            .map(|file| template_dir.join(file))
+            .find(|path| path.exists());
+        let Some(manifest_path) = manifest_path else {
+            return Ok(None);
+        };
+        
+        let content = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
+        let manifest: Self = if manifest_path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&content)
+                .with_context(|| format!("Failed to parse manifest: {}", manifest_path.display()))?
+        } else {
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse manifest: {}", manifest_path.display()))?
+        };
+        for variable in &manifest.variables {
+            variable
+                .validate()
+                .with_context(|| format!("Invalid manifest: {}", manifest_path.display()))?;
+        }
+        Ok(Some(manifest))
+    }
+    
+    /// Refuse templates whose manifest needs a newer mammoth-cli than the running one
+    pub fn check_compatibility(&self) -> Result<()> {
+        let requirement = match &self.requires_mammoth {
+            Some(requirement) => requirement,
+            None => return Ok(()),
+        };
+        
+        let version_req = VersionReq::parse(requirement)
+            .with_context(|| format!("Invalid requires_mammoth in manifest: '{}'", requirement))?;
+        let current = Version::parse(env!("CARGO_PKG_VERSION"))?;
+        
+        if !version_req.matches(&current) {
+            anyhow::bail!(
+                "This template requires mammoth-cli {} but {} is installed. Upgrade with: cargo install mammoth-cli --force",
+                requirement,
+                current
+            );
+        }
+        
+        Ok(())
+    }
+}
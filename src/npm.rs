@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::output;
+use crate::ui::{ensure_online, plain_text};
+
+/// Default registry, overridden by `NPM_CONFIG_REGISTRY`
+const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org";
+
+/// package.json sections whose version specs are refreshed
+const DEPENDENCY_SECTIONS: [&str; 2] = ["dependencies", "devDependencies"];
+
+#[derive(Debug, Deserialize)]
+struct PackageDocument {
+    #[serde(rename = "dist-tags", default)]
+    dist_tags: BTreeMap<String, String>,
+    #[serde(default)]
+    versions: BTreeMap<String, serde::de::IgnoredAny>,
+}
+
+fn registry_url() -> String {
+    std::env::var("NPM_CONFIG_REGISTRY")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| DEFAULT_REGISTRY.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+async fn fetch_package(client: &reqwest::Client, name: &str) -> Result<PackageDocument> {
+    // 作用域包的 "/" 需要编码，例如 @vue/cli -> @vue%2Fcli
+    let url = format!("{}/{}", registry_url(), name.replace('/', "%2F"));
+    let body = client
+        .get(&url)
+        .header("Accept", "application/vnd.npm.install-v1+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    serde_json::from_str(&body).with_context(|| format!("Invalid registry response for {}", name))
+}
+
+/// Newest version allowed by `spec`, rewritten with the same range operator, e.g.
+/// "^1.2.0" -> "^1.6.3". Returns `None` for specs that are left alone (git URLs,
+/// workspace/file references, compound ranges) or when nothing newer satisfies them.
+fn latest_spec(spec: &str, document: &PackageDocument) -> Option<String> {
+    let spec = spec.trim();
+    let latest_tag = || document.dist_tags.get("latest").map(|v| format!("^{}", v));
+    if spec.is_empty() || spec == "*" || spec == "latest" {
+        return latest_tag().filter(|latest| latest != spec);
+    }
+    
+    // 精确版本不放宽，只对 ^ 和 ~ 范围取最新
+    let (operator, version) = match spec.chars().next()? {
+        '^' | '~' => spec.split_at(1),
+        _ => return None,
+    };
+    let current = Version::parse(version).ok()?;
+    let requirement = VersionReq::parse(spec).ok()?;
+    
+    let newest = document
+        .versions
+        .keys()
+        .filter_map(|v| Version::parse(v).ok())
+        .filter(|v| v.pre.is_empty() && requirement.matches(v))
+        .max()?;
+    (newest > current).then(|| format!("{}{}", operator, newest))
+}
+
+/// Rewrite the dependency ranges in the project's package.json to the newest versions
+/// they allow. Registry failures (e.g. offline) keep the template versions and are
+/// reported as warnings. Returns the number of updated packages.
+pub async fn resolve_latest_dependencies(project_path: &Path) -> Result<usize> {
+    let package_json_path = project_path.join("package.json");
+    if !package_json_path.exists() {
+        return Ok(0);
+    }
+    let mut package_json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&package_json_path)?)
+            .context("Failed to parse package.json")?;
+    
+    let mut packages: Vec<String> = DEPENDENCY_SECTIONS
+        .iter()
+        .filter_map(|section| package_json.get(section).and_then(|v| v.as_object()))
+        .flat_map(|deps| deps.keys().cloned())
+        .collect();
+    packages.sort();
+    packages.dedup();
+    if packages.is_empty() {
+        return Ok(0);
+    }
+    
+    ensure_online("look up the latest dependency versions on npm")?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let mut lookups = tokio::task::JoinSet::new();
+    for name in packages {
+        let client = client.clone();
+        lookups.spawn(async move {
+            let document = fetch_package(&client, &name).await;
+            (name, document)
+        });
+    }
+    
+    let mut documents = BTreeMap::new();
+    let mut failed = Vec::new();
+    while let Some(lookup) = lookups.join_next().await {
+        match lookup? {
+            (name, Ok(document)) => {
+                documents.insert(name, document);
+            }
+            (name, Err(_)) => failed.push(name),
+        }
+    }
+    if !failed.is_empty() {
+        failed.sort();
+        output!(
+            "{}",
+            plain_text(&format!(
+                "⚠️  Could not reach the npm registry for {} packages, keeping template versions: {}",
+                failed.len(),
+                failed.join(", ")
+            ))
+        );
+    }
+    
+    let mut updated = 0;
+    for section in DEPENDENCY_SECTIONS {
+        let deps = match package_json.get_mut(section).and_then(|v| v.as_object_mut()) {
+            Some(deps) => deps,
+            None => continue,
+        };
+        for (name, spec) in deps.iter_mut() {
+            let (Some(current), Some(document)) = (spec.as_str(), documents.get(name)) else {
+                continue;
+            };
+            if let Some(latest) = latest_spec(current, document) {
+                output!("{}", plain_text(&format!("  ⬆️  {} {} → {}", name, current, latest)));
+                *spec = serde_json::Value::String(latest);
+                updated += 1;
+            }
+        }
+    }
+    
+    if updated > 0 {
+        fs::write(&package_json_path, serde_json::to_string_pretty(&package_json)?)?;
+    }
+    Ok(updated)
+}
@@ -0,0 +1,201 @@
+use anyhow::{Context, Result};
+use std::fmt;
+use std::path::Path;
+use std::process::Stdio;
+use std::str::FromStr;
+
+use crate::ui::{ensure_online, is_quiet};
+use crate::utils::tool_program;
+
+/// JavaScript package manager used to install a generated project
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Npm,
+    Pnpm,
+    Yarn,
+    Bun,
+}
+
+/// Lockfiles in the order they are checked
+const LOCKFILES: [(&str, PackageManager); 5] = [
+    ("pnpm-lock.yaml", PackageManager::Pnpm),
+    ("yarn.lock", PackageManager::Yarn),
+    ("bun.lockb", PackageManager::Bun),
+    ("bun.lock", PackageManager::Bun),
+    ("package-lock.json", PackageManager::Npm),
+];
+
+impl FromStr for PackageManager {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "npm" => Ok(PackageManager::Npm),
+            "pnpm" => Ok(PackageManager::Pnpm),
+            "yarn" => Ok(PackageManager::Yarn),
+            "bun" => Ok(PackageManager::Bun),
+            _ => anyhow::bail!("Invalid package manager: {}. Use 'npm', 'pnpm', 'yarn' or 'bun'", s),
+        }
+    }
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Yarn => "yarn",
+            PackageManager::Bun => "bun",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl PackageManager {
+    /// Package manager of a project: the `packageManager` field of package.json
+    /// (e.g. "pnpm@9.1.0"), then the lockfile, else npm. `None` without a package.json.
+    pub fn detect(project_path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(project_path.join("package.json")).ok()?;
+        let declared = serde_json::from_str::<serde_json::Value>(&content)
+            .ok()
+            .and_then(|package| package["packageManager"].as_str().map(str::to_string))
+            .and_then(|field| field.split('@').next().and_then(|name| name.parse().ok()));
+        let locked = || {
+            LOCKFILES
+                .iter()
+                .find(|(lockfile, _)| project_path.join(lockfile).exists())
+                .map(|(_, manager)| *manager)
+        };
+        Some(declared.or_else(locked).unwrap_or(PackageManager::Npm))
+    }
+    
+    pub fn install_command(&self) -> String {
+        format!("{} install", self)
+    }
+    
+    /// Command running a package.json script, e.g. `npm run dev` or `pnpm dev`
+    pub fn run_command(&self, script: &str) -> String {
+        match self {
+            PackageManager::Npm | PackageManager::Bun => format!("{} run {}", self, script),
+            PackageManager::Pnpm | PackageManager::Yarn => format!("{} {}", self, script),
+        }
+    }
+    
+    /// Switch a generated project to this package manager: lockfiles and a
+    /// `packageManager` field of other managers are removed and `<other> run` and
+    /// `npx` in package.json scripts are rewritten. Returns whether anything changed.
+    pub fn adopt(&self, project_path: &Path) -> Result<bool> {
+        let mut changed = false;
+        for (lockfile, manager) in LOCKFILES {
+            let path = project_path.join(lockfile);
+            if manager != *self && path.exists() {
+                std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", lockfile))?;
+                changed = true;
+            }
+        }
+        
+        let package_path = project_path.join("package.json");
+        let Ok(content) = std::fs::read_to_string(&package_path) else {
+            return Ok(changed);
+        };
+        let mut package: serde_json::Value = serde_json::from_str(&content).context("Failed to parse package.json")?;
+        let Some(object) = package.as_object_mut() else {
+            return Ok(changed);
+        };
+        let mut rewritten = false;
+        let foreign_field = object
+            .get("packageManager")
+            .and_then(|field| field.as_str())
+            .and_then(|field| field.split('@').next())
+            .is_some_and(|name| name != self.to_string());
+        if foreign_field {
+            object.remove("packageManager");
+            rewritten = true;
+        }
+        if let Some(scripts) = object.get_mut("scripts").and_then(|s| s.as_object_mut()) {
+            for script in scripts.values_mut() {
+                let Some(command) = script.as_str() else {
+                    continue;
+                };
+                let updated = self.rewrite_script(command);
+                if updated != command {
+                    *script = serde_json::Value::String(updated);
+                    rewritten = true;
+                }
+            }
+        }
+        if rewritten {
+            let content = serde_json::to_string_pretty(&package)?;
+            std::fs::write(&package_path, content + "\n").context("Failed to write package.json")?;
+        }
+        Ok(changed || rewritten)
+    }
+    
+    /// Replace `<other> run` and `npx` in a script. Each command of a chain such as
+    /// `npm run lint && npm run build` is rewritten on its own, and only when its first
+    /// word is exactly the other package manager or `npx`.
+    fn rewrite_script(&self, script: &str) -> String {
+        let mut rewritten = String::with_capacity(script.len());
+        let mut rest = script;
+        // 在 &&、||、; 和 | 处切分，分隔符原样保留
+        while !rest.is_empty() {
+            let end = rest.find(['&', '|', ';']).unwrap_or(rest.len());
+            let (command, tail) = rest.split_at(end);
+            rewritten.push_str(&self.rewrite_command(command));
+            let separator = tail.len() - tail.trim_start_matches(['&', '|', ';']).len();
+            rewritten.push_str(&tail[..separator]);
+            rest = &tail[separator..];
+        }
+        rewritten
+    }
+    
+    fn rewrite_command(&self, command: &str) -> String {
+        let trimmed = command.trim_start();
+        let indent = &command[..command.len() - trimmed.len()];
+        let (program, args) = match trimmed.find(char::is_whitespace) {
+            Some(end) => trimmed.split_at(end),
+            None => (trimmed, ""),
+        };
+        let replacement = match program {
+            "npx" if *self != PackageManager::Npm => match self {
+                PackageManager::Pnpm => "pnpm dlx",
+                PackageManager::Yarn => "yarn dlx",
+                _ => "bunx",
+            }
+            .to_string(),
+            "npm" | "pnpm" | "yarn" | "bun"
+                if program != self.to_string() && args.split_whitespace().next() == Some("run") =>
+            {
+                self.to_string()
+            }
+            _ => return command.to_string(),
+        };
+        format!("{}{}{}", indent, replacement, args)
+    }
+    
+    /// Run `<manager> install` in `project_path`. With `quiet` the installer output goes
+    /// to stderr so stdout stays clean for `--json`.
+    pub async fn install(&self, project_path: &Path, quiet: bool) -> Result<()> {
+        ensure_online("install dependencies")?;
+        let program = tool_program(&self.to_string());
+        tracing::info!("{} install (in {})", program, project_path.display());
+        let stdout = if is_quiet() {
+            Stdio::null()
+        } else if quiet {
+            Stdio::from(std::io::stderr())
+        } else {
+            Stdio::inherit()
+        };
+        let status = tokio::process::Command::new(&program)
+            .arg("install")
+            .current_dir(project_path)
+            .stdout(stdout)
+            .status()
+            .await
+            .with_context(|| format!("Failed to run {}, is it installed?", program))?;
+        if !status.success() {
+            anyhow::bail!("{} install failed with {}", self, status);
+        }
+        Ok(())
+    }
+}
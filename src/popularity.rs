@@ -0,0 +1,57 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::Repo;
+use crate::provider::{fetch_last_commit_date, fetch_repo_info};
+
+/// Activity of a template on its hosting service
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct Popularity {
+    pub stars: Option<u64>,
+    /// ISO 8601 date of the last commit touching the template path
+    pub last_commit: Option<String>,
+}
+
+/// Size of a repository in bytes as reported by its provider API
+pub async fn fetch_repo_size(repo: &Repo) -> Result<u64> {
+    fetch_repo_info(repo)
+        .await?
+        .size
+        .ok_or_else(|| anyhow::anyhow!("Provider did not report the size of '{}'", repo.name))
+}
+
+/// Stars of the repository and last commit date of `path` on `branch`.
+/// Fails for repositories without a supported hosting API.
+pub async fn fetch_popularity(repo: &Repo, branch: &str, path: &str) -> Result<Popularity> {
+    let info = fetch_repo_info(repo).await?;
+    let last_commit = fetch_last_commit_date(repo, branch, path).await?;
+
+    Ok(Popularity {
+        stars: info.stars,
+        last_commit: last_commit.map(|date| date.chars().take(10).collect()),
+    })
+}
+
+/// `fetch_popularity` for several `(branch, path)` templates of one repository, in the
+/// same order. The repository is queried once for its stars; a failure there fails
+/// every template, a missing commit date only its own.
+pub async fn fetch_repo_popularity(
+    repo: &Repo,
+    templates: &[(String, String)],
+) -> Result<Vec<Result<Popularity>>> {
+    let info = fetch_repo_info(repo).await?;
+    let lookups = templates
+        .iter()
+        .map(|(branch, path)| fetch_last_commit_date(repo, branch, path));
+
+    Ok(futures::future::join_all(lookups)
+        .await
+        .into_iter()
+        .map(|last_commit| {
+            Ok(Popularity {
+                stars: info.stars,
+                last_commit: last_commit?.map(|date| date.chars().take(10).collect()),
+            })
+        })
+        .collect())
+}
@@ -1,47 +1,339 @@
 use anyhow::{Context, Result};
 use colored::*;
-use dialoguer::{Input, Select};
-use indicatif::{ProgressBar, ProgressStyle};
+use dialoguer::{Confirm, FuzzySelect, Input, MultiSelect, Select};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-use crate::config::ProjectConfig;
+use crate::community::write_community_files;
+use crate::config::{canonical_language, group_by_category, ExistingDirPolicy, GenerateOptions, ProjectConfig, Template};
+use crate::conflict::{apply_resolution, choose_resolution, merge_file, with_suffix, ConflictPolicy, Resolution};
+use crate::diff::{compare_dirs, print_changes, ChangeKind, DiffFormat, FileChange};
+use crate::ecosystem::update_project_manifests;
+use crate::license::insert_license_headers;
+use crate::lockfile::{ProjectLock, LOCK_FILE};
 use crate::manager::TemplateManager;
-use crate::utils::{init_git_repository, update_package_json};
+use crate::manifest::{value_text, TemplateManifest, TemplateVariable, VariableKind};
+use crate::npm::resolve_latest_dependencies;
+use crate::package_manager::PackageManager;
+use crate::theme::Themed;
+use crate::timings::{record_timing, timings, timings_enabled};
+use crate::errors::MammothError;
+use crate::events::{Progress, ProgressKind};
+use crate::{output, status, warning};
+use crate::ui::{is_interactive, is_offline, is_plain_mode, is_quiet, missing_input, missing_inputs, plain_text, prompt_timed_out};
+use crate::utils::{git_config_value, init_git_repository, interpolate, is_binary, list_files, patch_package_json, replace_placeholders, update_package_json, RenderReport};
 
+/// Outcome of `new_project`, also printed by `new --json`
+#[derive(Debug, Clone, Serialize)]
+pub struct GeneratedProject {
+    pub path: PathBuf,
+    /// Qualified ID of the template, e.g. "frontend/admin"
+    pub template: String,
+    pub variables: BTreeMap<String, String>,
+    pub rendering: RenderReport,
+    pub package_manager: Option<String>,
+    pub installed: bool,
+}
+
+/// Ask for whatever `options` leaves open, generate the project and install its
+/// dependencies. Progress is reported through `status!`/`output!` events.
 pub async fn new_project(
     manager: &mut TemplateManager,
     template_id: Option<&str>,
     name: Option<&str>,
     output: &str,
-) -> Result<()> {
-    println!(
-        "{}",
-        "🚀 Welcome to mammoth-cli Frontend Scaffolding Tool!"
-            .bold()
-            .green()
-    );
-    println!();
+    options: &GenerateOptions,
+) -> Result<GeneratedProject> {
+    let quiet = options.json || is_quiet();
+    if !quiet {
+        output!(
+            "{}",
+            "🚀 Welcome to mammoth-cli Frontend Scaffolding Tool!"
+                .success()
+                .bold()
+        );
+        output!();
+    }
     
     // Get project configuration through interactive prompts
-    let config = get_project_config(manager, template_id, name, output).await?;
+    let config = get_project_config(manager, template_id, name, output, options).await?;
     
     // Generate the project
-    generate_project(manager, &config).await?;
+    let (manifest, report) = generate_project(manager, &config, options).await?;
+    let project_path = Path::new(&config.output_dir).join(&config.name);
+    // 项目已经生成，审计日志和使用统计写不进去也不算失败
+    let operation = format!("new {} from template {}", project_path.display(), config.template.id);
+    if let Err(e) = manager.record_audit(&operation) {
+        warning!("⚠️  Failed to record the audit log entry: {:#}", e);
+    }
+    if let Err(e) = manager.record_usage(&config.template) {
+        warning!("⚠️  Failed to record template usage: {:#}", e);
+    }
+    
+    let package_manager = project_package_manager(manager, &project_path)?;
+    let installed = match package_manager {
+        Some(package_manager) if should_install(options, package_manager)? => {
+            if !quiet {
+                output!("{}", plain_text(&format!("📦 Installing dependencies with {}...", package_manager)));
+            }
+            let started = Instant::now();
+            package_manager.install(&project_path, quiet).await?;
+            record_timing("install", started);
+            true
+        }
+        _ => false,
+    };
     
-    println!();
-    println!("{}", "🎉 Project generated successfully!".bold().green());
-    println!(
-        "📁 Project location: {}",
-        Path::new(&config.output_dir).join(&config.name).display()
+    let generated = GeneratedProject {
+        path: project_path,
+        template: config.template.qualified_id(),
+        variables: config.variables().into_iter().collect(),
+        rendering: report,
+        package_manager: package_manager.map(|pm| pm.to_string()),
+        installed,
+    };
+    
+    if options.json {
+        let mut summary = serde_json::to_value(&generated)?;
+        if timings_enabled() {
+            summary["timings"] = serde_json::to_value(timings())?;
+        }
+        output!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(generated);
+    }
+    if is_quiet() {
+        return Ok(generated);
+    }
+    if options.verbose {
+        print_render_report(&generated.rendering);
+    }
+    
+    output!();
+    output!("{}", "🎉 Project generated successfully!".success().bold());
+    output!(
+        "{}",
+        plain_text(&format!("📁 Project location: {}", generated.path.display()))
     );
-    println!();
-    println!("Next steps:");
-    println!("  cd {}", config.name);
-    println!("  npm install  # or pnpm install");
-    println!("  npm run dev  # or pnpm dev");
+    output!();
+    print_next_steps(&manifest, &config, package_manager, installed);
     
-    Ok(())
+    Ok(generated)
+}
+
+/// `settings.package_manager` for JavaScript projects, else the one the template uses
+fn project_package_manager(manager: &TemplateManager, project_path: &Path) -> Result<Option<PackageManager>> {
+    let detected = PackageManager::detect(project_path);
+    Ok(match manager.config.settings.package_manager()? {
+        Some(preferred) if detected.is_some() => Some(preferred),
+        _ => detected,
+    })
+}
+
+/// `--install`/`--no-install`, otherwise ask on interactive runs
+fn should_install(options: &GenerateOptions, package_manager: PackageManager) -> Result<bool> {
+    if let Some(install) = options.install {
+        return Ok(install);
+    }
+    if !is_interactive() || is_offline() || options.json {
+        return Ok(false);
+    }
+    let prompt = format!("Install dependencies with {}?", package_manager);
+    Ok(prompt_timed_out(&prompt, "yes")?
+        || Confirm::new()
+            .with_prompt(prompt)
+            .default(true)
+            .interact()?)
+}
+
+const DEFAULT_NEXT_STEPS: [&str; 3] = [
+    "cd {{name}}",
+    "npm install  # or pnpm install",
+    "npm run dev  # or pnpm dev",
+];
+
+fn print_next_steps(
+    manifest: &TemplateManifest,
+    config: &ProjectConfig,
+    package_manager: Option<PackageManager>,
+    installed: bool,
+) {
+    let steps: Vec<String> = match package_manager {
+        _ if !manifest.next_steps.is_empty() => manifest.next_steps.clone(),
+        // 检测到包管理器时给出确切的命令，已安装则跳过安装步骤
+        Some(package_manager) => {
+            let mut steps = vec!["cd {{name}}".to_string()];
+            if !installed {
+                steps.push(package_manager.install_command());
+            }
+            steps.push(package_manager.run_command("dev"));
+            steps
+        }
+        None => DEFAULT_NEXT_STEPS.iter().map(|s| s.to_string()).collect(),
+    };
+    
+    let vars = config.variables();
+    output!("Next steps:");
+    for step in steps {
+        output!("  {}", interpolate(&step, &vars));
+    }
+}
+
+fn print_render_report(report: &RenderReport) {
+    output!();
+    output!(
+        "{}",
+        format!(
+            "🧩 Placeholders: {} substitutions in {} files",
+            report.total_substitutions(),
+            report.modified.len()
+        )
+        .section()
+    );
+    for file in &report.modified {
+        output!("{}", plain_text(&format!("  ✏️  {} ({})", file.path, file.substitutions)));
+    }
+    for path in &report.skipped_binary {
+        output!("{}", plain_text(&format!("  ⏭️  {} {}", path, "(binary, skipped)".dimmed())));
+    }
+}
+
+/// Prompt for text, taking `default` without asking on non-interactive runs or
+/// once the prompt timeout passes
+fn prompt_text(prompt: &str, default: &str) -> Result<String> {
+    if !is_interactive() || prompt_timed_out(prompt, default)? {
+        return Ok(default.to_string());
+    }
+    Ok(Input::new()
+        .with_prompt(prompt)
+        .with_initial_text(default)
+        .interact_text()?)
+}
+
+/// Like `prompt_text`, but an empty answer is accepted
+fn prompt_optional_text(prompt: &str, default: &str) -> Result<String> {
+    if !is_interactive() || prompt_timed_out(prompt, default)? {
+        return Ok(default.to_string());
+    }
+    Ok(Input::new()
+        .with_prompt(prompt)
+        .with_initial_text(default)
+        .allow_empty(true)
+        .interact_text()?)
+}
+
+/// Ask for the variables declared by the template manifests. Non-interactive and timed
+/// out runs take the declared defaults and fail for variables without one.
+fn prompt_variables(
+    variables: &[TemplateVariable],
+    provided: &BTreeMap<String, serde_json::Value>,
+) -> Result<BTreeMap<String, String>> {
+    for name in provided.keys() {
+        if !variables.iter().any(|v| &v.name == name) {
+            warning!("⚠️  Values file sets '{}', which the template does not declare", name);
+        }
+    }
+    
+    let mut values = BTreeMap::new();
+    for variable in variables {
+        if let Some(value) = provided.get(&variable.name) {
+            let value = variable
+                .parse_value(&value_text(value))
+                .context("Invalid answer in values file")?;
+            values.insert(variable.name.clone(), value);
+            continue;
+        }
+        let prompt = variable.prompt_text();
+        let default = variable.default_value();
+        let answer = match &default {
+            Some(default) if !is_interactive() || prompt_timed_out(prompt, default)? => default.clone(),
+            None if !is_interactive() => {
+                return Err(missing_input(
+                    &format!("Template variable '{}'", variable.name),
+                    "a default in the template manifest",
+                ))
+            }
+            _ => match variable.kind {
+                VariableKind::Bool => Confirm::new()
+                    .with_prompt(prompt)
+                    .default(default.as_deref() == Some("true"))
+                    .interact()?
+                    .to_string(),
+                VariableKind::Select => {
+                    let selected = default
+                        .as_ref()
+                        .and_then(|d| variable.choices.iter().position(|c| c == d))
+                        .unwrap_or(0);
+                    let index = Select::new()
+                        .with_prompt(prompt)
+                        .items(&variable.choices)
+                        .default(selected)
+                        .interact()?;
+                    variable.choices[index].clone()
+                }
+                VariableKind::String | VariableKind::Number => {
+                    let mut input = Input::<String>::new()
+                        .with_prompt(prompt)
+                        .allow_empty(variable.kind == VariableKind::String)
+                        .validate_with(|value: &String| {
+                            variable.parse_value(value).map(|_| ()).map_err(|e| e.to_string())
+                        });
+                    if let Some(default) = &default {
+                        input = input.with_initial_text(default);
+                    }
+                    input.interact_text()?
+                }
+            },
+        };
+        values.insert(variable.name.clone(), variable.parse_value(&answer)?);
+    }
+    Ok(values)
+}
+
+/// Let the user pick one of `items`, the first one being the default
+fn select_item(prompt: &str, items: &[String]) -> Result<usize> {
+    if prompt_timed_out(prompt, &items[0])? {
+        return Ok(0);
+    }
+    Ok(Select::new()
+        .with_prompt(prompt)
+        .items(items)
+        .default(0)
+        .interact()?)
+}
+
+/// Like `select_item`, but typing filters `items` by fuzzy match
+fn fuzzy_select_item(prompt: &str, items: &[String]) -> Result<usize> {
+    if prompt_timed_out(prompt, &items[0])? {
+        return Ok(0);
+    }
+    Ok(FuzzySelect::new()
+        .with_prompt(format!("{} (type to search)", prompt))
+        .items(items)
+        .default(0)
+        .interact()?)
+}
+
+/// Picker entry of a template; the language and tags are part of it so that typing
+/// them finds the template. Not colored, the fuzzy match highlighting would break it.
+fn picker_label(manager: &TemplateManager, template: &Template) -> String {
+    let manifest = manager.load_template_manifest(template).unwrap_or_default();
+    let id = match template.icon.as_ref().or(manifest.icon.as_ref()) {
+        Some(icon) if !is_plain_mode() => format!("{} {}", icon, manager.display_id(template)),
+        _ => manager.display_id(template),
+    };
+    let mut label = format!(
+        "{} - {} [{}]",
+        id,
+        template.description,
+        canonical_language(&template.language)
+    );
+    for tag in &template.tags {
+        label.push_str(&format!(" #{}", tag));
+    }
+    label
 }
 
 pub async fn get_project_config(
@@ -49,146 +341,951 @@ pub async fn get_project_config(
     template_id: Option<&str>,
     name: Option<&str>,
     output: &str,
+    options: &GenerateOptions,
 ) -> Result<ProjectConfig> {
+    let language = options.language.as_deref();
+    let answers = &options.values;
+    let template_id = template_id.or(answers.template.as_deref());
+    let name = name.or(answers.name.as_deref());
+    
     // Template selection
     let template = if let Some(id) = template_id {
-        manager
-            .get_template_by_id(id)
-            .ok_or_else(|| anyhow::anyhow!("Template '{}' not found", id))?
+        let template = manager.find_template(id)?;
+        manager.ensure_template_enabled(template)?;
+        template
+    } else if !is_interactive() {
+        let mut missing = vec![("Template".to_string(), "--template".to_string())];
+        if name.is_none() {
+            missing.push(("Project name".to_string(), "--name".to_string()));
+        }
+        return Err(missing_inputs(&missing));
     } else {
-        println!("{}", "🎨 Step 1: Select Template".bold().blue());
+        status!("{}", "🎨 Step 1: Select Template".header());
         
         if manager.config.templates.is_empty() {
             anyhow::bail!("No templates available. Add templates first with 'template add'");
         }
         
-        let template_names: Vec<String> = manager
+        let mut templates: Vec<&Template> = manager
             .config
             .templates
             .iter()
-            .map(|t| format!("{} - {}", t.id, t.description))
+            .filter(|t| manager.is_template_enabled(t))
+            .filter(|t| language.is_none_or(|l| t.matches_language(l)))
+            .filter(|t| !is_offline() || manager.is_usable_offline(t))
             .collect();
+        if templates.is_empty() && is_offline() {
+            return Err(MammothError::Offline {
+                action: "download templates, none are cached".to_string(),
+            }
+            .into());
+        }
+        if templates.is_empty() {
+            match language {
+                Some(language) => anyhow::bail!("No enabled templates available for language '{}'", language),
+                None => anyhow::bail!("No enabled templates available. Re-enable one with 'template enable'"),
+            }
+        }
+        manager.sort_for_picker(&mut templates);
         
-        let template_selection = Select::new()
-            .with_prompt("Choose a template")
-            .items(&template_names)
-            .default(0)
-            .interact()?;
+        // 模板配置了分类时先选择分类
+        let templates = if templates.iter().any(|t| t.category.is_some()) {
+            let mut groups = group_by_category(&templates);
+            let headers: Vec<String> = groups
+                .iter()
+                .map(|(category, members)| format!("📁 {} ({})", category, members.len()))
+                .collect();
+            
+            let category_selection = select_item("Choose a category", &headers)?;
+            
+            groups.swap_remove(category_selection).1
+        } else {
+            templates
+        };
+        
+        let template_names: Vec<String> = templates.iter().map(|t| picker_label(manager, t)).collect();
+        
+        let template_selection = fuzzy_select_item("Choose a template", &template_names)?;
         
-        &manager.config.templates[template_selection]
+        templates[template_selection]
     };
     
-    println!("✨ Selected template: {}", template.id.green());
-    println!();
+    status!("✨ Selected template: {}", manager.styled_id(template));
+    let addons = select_addons(manager, template, options)?;
+    if !addons.is_empty() {
+        let ids: Vec<String> = addons.iter().map(|addon| manager.styled_id(addon)).collect();
+        status!("🧩 Addons: {}", ids.join(", "));
+    }
+    status!();
+    
+    // 非交互运行时一次性报告所有缺失的输入，而不是逐个失败
+    let variables = manager.template_variables(template, &addons).await?;
+    if !is_interactive() {
+        let mut missing = Vec::new();
+        if name.is_none() {
+            missing.push(("Project name".to_string(), "--name".to_string()));
+        }
+        for variable in variables
+            .iter()
+            .filter(|v| v.default_value().is_none() && !answers.variables.contains_key(&v.name))
+        {
+            missing.push((
+                format!("Template variable '{}'", variable.name),
+                "a default in the template manifest".to_string(),
+            ));
+        }
+        if !missing.is_empty() {
+            return Err(missing_inputs(&missing));
+        }
+    }
     
     // Project information
-    println!("{}", "📋 Step 2: Project Information".bold().blue());
+    status!("{}", "📋 Step 2: Project Information".header());
     
-    let project_name: String = if let Some(n) = name {
-        n.to_string()
-    } else {
-        Input::new()
-            .with_prompt("Project name")
-            .with_initial_text("my-awesome-project")
-            .interact_text()?
+    let project_name: String = match name {
+        Some(n) => n.to_string(),
+        None => prompt_text("Project name", "my-awesome-project")?,
     };
     
-    let author: String = Input::new()
-        .with_prompt("Author name")
-        .with_initial_text("Your Name")
-        .interact_text()?;
+    let defaults = &manager.config.defaults;
+    let default_author = defaults
+        .author
+        .clone()
+        .or_else(|| git_config_value("user.name"))
+        .unwrap_or_else(|| "Your Name".to_string());
+    let author: String = match &answers.author {
+        Some(author) => author.clone(),
+        None => prompt_text("Author name", &default_author)?,
+    };
+    let default_email = defaults
+        .email
+        .clone()
+        .or_else(|| git_config_value("user.email"))
+        .unwrap_or_default();
+    let email: String = match &answers.email {
+        Some(email) => email.clone(),
+        None => prompt_optional_text("Author email", &default_email)?,
+    };
     
-    let description: String = Input::new()
-        .with_prompt("Project description")
-        .with_initial_text("A wonderful project")
-        .interact_text()?;
+    let description: String = match &answers.description {
+        Some(description) => description.clone(),
+        None => prompt_text("Project description", "A wonderful project")?,
+    };
     
-    let output_dir: String = if output != "." {
-        output.to_string()
-    } else {
-        Input::new()
-            .with_prompt("Output directory")
-            .with_initial_text(".")
-            .interact_text()?
+    let values = prompt_variables(&variables, &answers.variables)?;
+    
+    let output_dir: String = match &answers.output {
+        _ if output != "." => output.to_string(),
+        Some(output) => output.clone(),
+        None => prompt_text("Output directory", ".")?,
     };
     
-    println!();
-    println!("{}", "📊 Project Summary".bold().yellow());
-    println!("Name: {}", project_name);
-    println!("Author: {}", author);
-    println!("Description: {}", description);
-    println!("Template: {}", template.id);
-    println!("Language: {}", template.language);
-    println!("Output Directory: {}", output_dir);
-    println!();
-    
-    // Confirmation
-    let confirm = dialoguer::Confirm::new()
-        .with_prompt("Do you want to proceed with project generation?")
-        .default(true)
-        .interact()?;
+    status!();
+    status!("{}", "📊 Project Summary".section());
+    status!("Name: {}", project_name);
+    status!("Author: {}", author);
+    if !email.is_empty() {
+        status!("Email: {}", email);
+    }
+    status!("Description: {}", description);
+    for (name, value) in &values {
+        status!("{}: {}", name, value);
+    }
+    status!("Template: {}", template.id);
+    if !addons.is_empty() {
+        let ids: Vec<&str> = addons.iter().map(|addon| addon.id.as_str()).collect();
+        status!("Addons: {}", ids.join(", "));
+    }
+    status!("Language: {}", template.language);
+    status!("Output Directory: {}", output_dir);
+    status!();
+    
+    // Confirmation, non-interactive and timed out runs proceed as if confirmed
+    let prompt = "Do you want to proceed with project generation?";
+    let confirm = !is_interactive()
+        || prompt_timed_out(prompt, "yes")?
+        || dialoguer::Confirm::new()
+            .with_prompt(prompt)
+            .default(true)
+            .interact()?;
     
     if !confirm {
-        println!("{}", "❌ Project generation cancelled".red());
+        status!("{}", "❌ Project generation cancelled".error());
         std::process::exit(0);
     }
     
     Ok(ProjectConfig {
         name: project_name,
         author,
+        email,
+        author_url: defaults.url.clone().unwrap_or_default(),
         description,
         output_dir,
         template: template.clone(),
+        addons,
+        values,
     })
 }
 
-pub async fn generate_project(manager: &TemplateManager, config: &ProjectConfig) -> Result<()> {
-    println!("{}", "🔨 Generating project...".bold().blue());
+/// Tag marking templates offered as addons by the `new` prompt
+const ADDON_TAG: &str = "addon";
+
+/// Addons from `--with` or the values file, otherwise picked on interactive runs from
+/// the templates tagged "addon"
+fn select_addons(manager: &TemplateManager, template: &Template, options: &GenerateOptions) -> Result<Vec<Template>> {
+    let ids = if options.addons.is_empty() {
+        &options.values.addons
+    } else {
+        &options.addons
+    };
+    if !ids.is_empty() {
+        let mut addons: Vec<Template> = Vec::new();
+        for id in ids {
+            let addon = manager.find_template(id)?;
+            manager.ensure_template_enabled(addon)?;
+            if addon.qualified_id() == template.qualified_id() {
+                anyhow::bail!("Template '{}' cannot be an addon of itself", template.id);
+            }
+            if !addons.iter().any(|a| a.qualified_id() == addon.qualified_id()) {
+                addons.push(addon.clone());
+            }
+        }
+        return Ok(addons);
+    }
+    if !is_interactive() {
+        return Ok(Vec::new());
+    }
     
-    let project_path = Path::new(&config.output_dir).join(&config.name);
+    let candidates: Vec<&Template> = manager
+        .config
+        .templates
+        .iter()
+        .filter(|t| t.tags.iter().any(|tag| tag == ADDON_TAG))
+        .filter(|t| t.qualified_id() != template.qualified_id())
+        .filter(|t| manager.is_template_enabled(t))
+        .filter(|t| !is_offline() || manager.is_usable_offline(t))
+        .collect();
+    let prompt = "Add addons (space to toggle, enter to confirm)";
+    if candidates.is_empty() || prompt_timed_out(prompt, "none")? {
+        return Ok(Vec::new());
+    }
+    let items: Vec<String> = candidates.iter().map(|t| picker_label(manager, t)).collect();
+    let chosen = MultiSelect::new().with_prompt(prompt).items(&items).interact()?;
+    Ok(chosen.into_iter().map(|index| candidates[index].clone()).collect())
+}
+
+/// Phases of project generation, weights add up to the progress bar length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GenerationPhase {
+    Prepare,
+    Fetch,
+    Write,
+    PostProcess,
+}
+
+impl GenerationPhase {
+    const ALL: [GenerationPhase; 4] = [
+        GenerationPhase::Prepare,
+        GenerationPhase::Fetch,
+        GenerationPhase::Write,
+        GenerationPhase::PostProcess,
+    ];
     
-    // Create progress bar
-    let pb = ProgressBar::new(100);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template(
-                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
-            )
-            .unwrap()
-            .progress_chars("#>-"),
-    );
+    fn weight(self) -> u64 {
+        match self {
+            GenerationPhase::Prepare => 5,
+            GenerationPhase::Fetch => 50,
+            GenerationPhase::Write => 35,
+            GenerationPhase::PostProcess => 10,
+        }
+    }
+    
+    fn message(self) -> &'static str {
+        match self {
+            GenerationPhase::Prepare => "Preparing project directory...",
+            GenerationPhase::Fetch => "Fetching template...",
+            GenerationPhase::Write => "Writing project files...",
+            GenerationPhase::PostProcess => "Finalizing project...",
+        }
+    }
+    
+    /// Progress position at which this phase starts
+    fn offset(self) -> u64 {
+        Self::ALL
+            .iter()
+            .take_while(|phase| **phase != self)
+            .map(|phase| phase.weight())
+            .sum()
+    }
     
-    pb.set_message("Creating project directory...");
-    pb.inc(10);
+    fn begin(self, pb: &Progress) {
+        tracing::info!("{:?} phase", self);
+        pb.set_position(self.offset());
+        pb.set_message(self.message());
+    }
     
-    // Create project directory
+    fn complete(self, pb: &Progress) {
+        pb.set_position(self.offset() + self.weight());
+    }
+}
+
+pub async fn generate_project(
+    manager: &TemplateManager,
+    config: &ProjectConfig,
+    options: &GenerateOptions,
+) -> Result<(TemplateManifest, RenderReport)> {
+    if !options.json {
+        status!("{}", "🔨 Generating project...".header());
+    }
+    
+    let target_path = Path::new(&config.output_dir).join(&config.name);
+    let target_in_use = is_non_empty_dir(&target_path)?;
+    if target_in_use {
+        match options.existing {
+            ExistingDirPolicy::Abort => anyhow::bail!(
+                "Project directory {} is not empty. Use --force to overwrite conflicting files or --merge to only add missing files",
+                target_path.display()
+            ),
+            _ if options.keep_history => {
+                anyhow::bail!("--keep-history needs an empty project directory, {} is not empty", target_path.display())
+            }
+            _ => {}
+        }
+    }
+    // 目标目录非空时先生成到临时目录，再按策略合并进去
+    let project_path = if target_in_use {
+        let staging = std::env::temp_dir()
+            .join("mammoth-cli")
+            .join(format!("staging_{}_{}", config.name, std::process::id()));
+        if staging.exists() {
+            fs::remove_dir_all(&staging).context("Failed to clear staging directory")?;
+        }
+        staging
+    } else {
+        target_path.clone()
+    };
+    
+    let total: u64 = GenerationPhase::ALL.iter().map(|phase| phase.weight()).sum();
+    let pb = Progress::start(ProgressKind::Generate, "", total);
+    
+    let layers = manager.compose_layers(&config.template, &config.addons)?;
+    
+    // Prepare: create project directory
+    GenerationPhase::Prepare.begin(&pb);
+    // 失败时删除本次创建的目录，例如模板要求更新版本的 mammoth-cli
+    let created = !project_path.exists();
     fs::create_dir_all(&project_path).with_context(|| {
         format!(
             "Failed to create project directory: {}",
             project_path.display()
         )
     })?;
+    GenerationPhase::Prepare.complete(&pb);
+    
+    // Fetch: get the template, its addons and their dependencies (from cache, or fresh temp copies with --no-cache)
+    GenerationPhase::Fetch.begin(&pb);
+    let mut fetched = Ok(());
+    if options.keep_history {
+        pb.set_message("Importing template history...");
+        fetched = manager
+            .import_template_history(&config.template, &project_path)
+            .await;
+    }
+    let mut template_dirs = Vec::new();
+    let mut fresh_dirs = Vec::new();
+    let mut commits = Vec::new();
+    for layer in &layers {
+        if fetched.is_err() {
+            break;
+        }
+        let locked = options.locked_commits.get(&layer.qualified_id());
+        if options.no_cache || locked.is_some() {
+            let dir = std::env::temp_dir()
+                .join("mammoth-cli")
+                .join(format!("fresh_{}_{}", layer.id, std::process::id()));
+            fresh_dirs.push(dir.clone());
+            // 锁定的提交临时固定到模板上，缓存里可能是别的版本
+            let mut pinned = (*layer).clone();
+            if let Some(commit) = locked {
+                pinned.git_ref = Some(commit.clone());
+            }
+            fetched = match manager.fetch_template_uncached(&pinned, &dir).await {
+                Ok(commit) if locked.is_some_and(|locked| *locked != commit) => Err(anyhow::anyhow!(
+                    "Template '{}' is at revision {} but {} expects {}",
+                    layer.id,
+                    commit,
+                    LOCK_FILE,
+                    locked.map(String::as_str).unwrap_or_default()
+                )),
+                Ok(commit) => {
+                    commits.push((*layer, Some(commit)));
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            };
+            template_dirs.push(dir);
+        } else {
+            fetched = match manager.download_template(layer, false).await {
+                Ok(_) => manager.cached_template_path(layer).map(|dir| {
+                    template_dirs.push(dir);
+                    commits.push((*layer, manager.cached_commit(layer)));
+                }),
+                Err(e) => Err(e),
+            };
+        }
+    }
+    GenerationPhase::Fetch.complete(&pb);
+    
+    let written = fetched.and_then(|_| {
+        // 写入前先检查模板清单，不兼容时不碰项目文件
+        // 插件层叠加在模板之上，后续步骤等仍以模板自身的清单为准
+        let template_dir = layers
+            .iter()
+            .position(|layer| layer.qualified_id() == config.template.qualified_id())
+            .and_then(|index| template_dirs.get(index))
+            .ok_or_else(|| anyhow::anyhow!("No template files fetched"))?;
+        let manifest = TemplateManifest::load(template_dir)?.unwrap_or_default();
+        manifest.check_compatibility()?;
+        for (layer, template_dir) in layers.iter().zip(&template_dirs) {
+            if config.addons.iter().any(|addon| addon.qualified_id() == layer.qualified_id()) {
+                if let Some(addon) = TemplateManifest::load(template_dir)? {
+                    addon.check_compatibility()?;
+                }
+            }
+        }
+        
+        // Write: resolve placeholders, copy dependencies, the template then its addons, replace placeholders and update package.json and other manifests with project information
+        GenerationPhase::Write.begin(&pb);
+        let started = Instant::now();
+        // 各层声明的占位符，后面的层覆盖前面的
+        let vars = config.variables();
+        let mut placeholders = BTreeMap::new();
+        for template_dir in &template_dirs {
+            if let Some(layer) = TemplateManifest::load(template_dir)? {
+                placeholders.extend(layer.placeholders);
+            }
+        }
+        let replacements: Vec<(String, String)> = placeholders
+            .into_iter()
+            .map(|(placeholder, value)| (placeholder, interpolate(&value, &vars)))
+            .collect();
+        record_timing("render", started);
+        let started = Instant::now();
+        for template_dir in &template_dirs {
+            manager.copy_template_dir(template_dir, &project_path, &vars)?;
+        }
+        record_timing("copy", started);
+        let started = Instant::now();
+        let report = replace_placeholders(&project_path, &replacements)
+            .context("Failed to replace template placeholders")?;
+        record_timing("render", started);
+        let started = Instant::now();
+        update_package_json(&project_path, config)?;
+        update_project_manifests(&project_path, config)?;
+        if let Some(preferred) = manager.config.settings.package_manager()? {
+            if PackageManager::detect(&project_path).is_some_and(|detected| detected != preferred) {
+                preferred.adopt(&project_path)?;
+            }
+        }
+        record_timing("package.json update", started);
+        write_community_files(&project_path, &options.community_files, &vars, options.org.as_deref())?;
+        if let Some(license) = options.license.as_ref().or(manifest.license_header.as_ref()) {
+            insert_license_headers(&project_path, license, &config.author_line())
+                .context("Failed to insert license headers")?;
+            if project_path.join("package.json").exists() {
+                patch_package_json(&project_path, &serde_json::json!({ "license": license }))?;
+            }
+        }
+        GenerationPhase::Write.complete(&pb);
+        
+        Ok((manifest, report))
+    });
+    
+    // 临时获取的模板用完即删
+    for dir in &fresh_dirs {
+        fs::remove_dir_all(dir).ok();
+    }
+    if written.is_err() && created {
+        fs::remove_dir_all(&project_path).ok();
+    }
+    let (manifest, report) = written?;
+    
+    if options.latest_deps {
+        pb.set_message("Resolving latest dependency versions...");
+        let started = Instant::now();
+        let updated = resolve_latest_dependencies(&project_path).await?;
+        record_timing("latest dependencies", started);
+        if !options.json {
+            status!("📦 Updated {} dependency versions", updated);
+        }
+    }
+    
+    // Post-process: merge into the existing directory, initialize git repository
+    GenerationPhase::PostProcess.begin(&pb);
+    if target_in_use {
+        let merged = merge_into_existing(&project_path, &target_path, options.existing);
+        fs::remove_dir_all(&project_path).ok();
+        let merged = merged?;
+        if !options.json {
+            merged.print(options.existing);
+        }
+    }
+    let project_path = target_path;
+    ProjectLock::record(manager, config, options, &commits).write(&project_path)?;
+    if !options.skip_git_init {
+        let started = Instant::now();
+        init_git_repository(&project_path)?;
+        record_timing("git init", started);
+    }
+    GenerationPhase::PostProcess.complete(&pb);
+    
+    pb.finish("Project generation completed!");
+    
+    Ok((manifest, report))
+}
+
+fn is_non_empty_dir(path: &Path) -> Result<bool> {
+    if !path.is_dir() {
+        return Ok(false);
+    }
+    Ok(fs::read_dir(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .next()
+        .is_some())
+}
+
+/// Files of a generation merged into a non-empty directory
+#[derive(Debug, Default)]
+struct MergeReport {
+    added: Vec<PathBuf>,
+    /// Existing files that differed from the template: overwritten with `--force`,
+    /// kept with `--merge`
+    conflicts: Vec<PathBuf>,
+    unchanged: usize,
+}
+
+impl MergeReport {
+    fn print(&self, policy: ExistingDirPolicy) {
+        output!(
+            "{}",
+            plain_text(&format!(
+                "📂 Merged into existing directory: {} added, {} identical, {} conflicting",
+                self.added.len(),
+                self.unchanged,
+                self.conflicts.len()
+            ))
+        );
+        let action = match policy {
+            ExistingDirPolicy::Force => "overwritten",
+            _ => "kept",
+        };
+        for path in &self.conflicts {
+            output!("  {} {} ({})", "⚠️".warning(), path.display(), action);
+        }
+    }
+}
+
+/// Copy the generated files from `staging` into `target`; files that already exist
+/// there are overwritten with `Force` and left alone with `Merge`
+fn merge_into_existing(staging: &Path, target: &Path, policy: ExistingDirPolicy) -> Result<MergeReport> {
+    let mut report = MergeReport::default();
+    for relative in list_files(staging)? {
+        let source = staging.join(&relative);
+        let dest = target.join(&relative);
+        if dest.exists() {
+            if fs::read(&source)? == fs::read(&dest).unwrap_or_default() {
+                report.unchanged += 1;
+                continue;
+            }
+            report.conflicts.push(relative.clone());
+            if policy != ExistingDirPolicy::Force {
+                continue;
+            }
+        } else {
+            report.added.push(relative.clone());
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&source, &dest).with_context(|| format!("Failed to write {}", dest.display()))?;
+    }
+    Ok(report)
+}
+
+pub fn diff_project(
+    manager: &TemplateManager,
+    template_id: &str,
+    dir: &str,
+    format: DiffFormat,
+) -> Result<()> {
+    let template = manager.find_template(template_id)?;
+    let template_dir = manager.cached_template_path(template)?;
+    let project_dir = Path::new(dir);
+    
+    if !project_dir.is_dir() {
+        anyhow::bail!("Project directory not found: {}", project_dir.display());
+    }
+    
+    let changes = compare_dirs(&template_dir, project_dir)?;
+    if changes.is_empty() {
+        status!("✨ Project is up to date with template '{}'", template.id);
+        return Ok(());
+    }
+    
+    print_changes(&changes, format);
+    Ok(())
+}
+
+pub async fn upgrade_project(
+    manager: &TemplateManager,
+    template_id: &str,
+    dir: &str,
+    policy: ConflictPolicy,
+) -> Result<()> {
+    let template = manager.find_template(template_id)?;
+    let project_dir = Path::new(dir);
+    
+    if !project_dir.is_dir() {
+        anyhow::bail!("Project directory not found: {}", project_dir.display());
+    }
     
-    pb.set_message("Getting template files...");
-    pb.inc(20);
+    // 有 mammoth.lock 时以生成时的版本为基准，只有两边都改过的文件才算冲突
+    if project_dir.join(LOCK_FILE).exists() {
+        let lock = ProjectLock::load(project_dir)?;
+        if lock.template != template.qualified_id() {
+            anyhow::bail!(
+                "{} was generated from template '{}', not '{}'",
+                project_dir.display(),
+                lock.template,
+                template.qualified_id()
+            );
+        }
+        return update_project(manager, dir, false, Some(policy)).await;
+    }
     
-    // Get template files (will download if not cached)
-    manager.download_template(&config.template, false).await?;
-    manager.copy_template_files(&config.template, &project_path)?;
+    status!("{}", format!("🔄 Upgrading project from template '{}'", template.id).header());
+    warning!(
+        "⚠️  No {} in {}, every file that differs from the template is treated as a conflict",
+        LOCK_FILE,
+        project_dir.display()
+    );
     
-    pb.set_message("Updating project configuration...");
-    pb.inc(30);
+    // 升级总是基于最新的模板
+    manager.download_template(template, true).await?;
+    let template_dir = manager.cached_template_path(template)?;
+    if let Some(manifest) = TemplateManifest::load(&template_dir)? {
+        manifest.check_compatibility()?;
+    }
     
-    // Update package.json with project information
-    update_package_json(&project_path, config)?;
+    let changes = compare_dirs(&template_dir, project_dir)?;
+    if changes.is_empty() {
+        status!("✨ Project is up to date with template '{}'", template.id);
+        return Ok(());
+    }
     
-    pb.set_message("Finalizing project...");
-    pb.inc(40);
+    let mut added = 0;
+    let mut updated = 0;
+    let mut kept = 0;
+    let mut new_files = 0;
     
-    // Initialize git repository
-    init_git_repository(&project_path)?;
+    for change in &changes {
+        let resolution = match change.kind {
+            ChangeKind::Added => Resolution::TakeTheirs,
+            ChangeKind::Modified => choose_resolution(change, policy)?,
+        };
+        apply_resolution(resolution, &change.path, &template_dir, project_dir)?;
+        
+        let (marker, label) = match (change.kind, resolution) {
+            (ChangeKind::Added, _) => {
+                added += 1;
+                ("A".success(), "added")
+            }
+            (_, Resolution::TakeTheirs) | (_, Resolution::MergeTool) => {
+                updated += 1;
+                ("M".warning(), "updated")
+            }
+            (_, Resolution::WriteNew) => {
+                new_files += 1;
+                ("N".highlight(), "template version written to .new")
+            }
+            (_, Resolution::KeepMine) => {
+                kept += 1;
+                ("K".dimmed(), "kept local version")
+            }
+        };
+        output!("  {} {} ({})", marker, change.path.display(), label);
+    }
     
-    pb.finish_with_message("Project generation completed!");
+    output!();
+    output!("{}", "🎉 Upgrade completed!".success().bold());
+    output!(
+        "{}",
+        plain_text(&format!(
+            "📊 {} added, {} updated, {} kept, {} written as .new",
+            added, updated, kept, new_files
+        ))
+    );
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Recreate a project from its `mammoth.lock`: the same template revisions, answers
+/// and options, without prompting. The project is written to `output`/<name>.
+pub async fn regenerate_project(
+    manager: &TemplateManager,
+    lock_path: &str,
+    output: &str,
+    existing: ExistingDirPolicy,
+) -> Result<PathBuf> {
+    let lock = ProjectLock::load(Path::new(lock_path))?;
+    status!(
+        "{}",
+        format!("♻️  Regenerating '{}' from {}", lock.name, LOCK_FILE).header()
+    );
+    if lock.cli_version != env!("CARGO_PKG_VERSION") {
+        warning!(
+            "⚠️  The project was generated by mammoth-cli {}, this is {}; rendering may differ",
+            lock.cli_version,
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+    if lock.latest_deps {
+        warning!("⚠️  The project used --latest-deps, dependency versions are kept as the template pins them");
+    }
+    
+    let config = lock.project_config(manager, output)?;
+    for layer in &lock.layers {
+        let configured = manager.find_template(&layer.template)?;
+        let url = manager
+            .get_repo_by_name(&configured.repo)
+            .map(|repo| repo.url.as_str())
+            .unwrap_or_default();
+        if url != layer.url {
+            warning!(
+                "⚠️  Repository of '{}' moved from {} to {}, fetching the locked revision from the new URL",
+                layer.template, layer.url, url
+            );
+        }
+        if layer.commit.is_none() {
+            warning!("⚠️  No revision recorded for '{}', using the current template", layer.template);
+        }
+    }
+    
+    let options = GenerateOptions {
+        existing,
+        ..lock.generate_options()
+    };
+    generate_project(manager, &config, &options).await?;
+    
+    let project_path = Path::new(output).join(&lock.name);
+    status!("{}", "🎉 Project regenerated from the locked revisions".success().bold());
+    output!("{}", plain_text(&format!("📁 Project location: {}", project_path.display())));
+    Ok(project_path)
+}
+
+/// Outcome of `update` for one project file
+enum UpdateAction {
+    Added,
+    Updated,
+    Merged,
+    Deleted,
+    /// Changed on both sides, the local version kept by the conflict policy
+    Kept,
+    Conflict(&'static str),
+}
+
+/// Pull template changes into a project: render it at the revisions recorded in its
+/// `mammoth.lock` and at the latest revisions, then apply the difference with a
+/// three-way merge. Local edits are kept; overlapping edits get conflict markers, or
+/// are resolved by `policy` when one is given (`upgrade --on-conflict`).
+pub async fn update_project(
+    manager: &TemplateManager,
+    dir: &str,
+    dry_run: bool,
+    policy: Option<ConflictPolicy>,
+) -> Result<()> {
+    let project_dir = Path::new(dir);
+    let lock = ProjectLock::load(project_dir)?;
+    if let Some(layer) = lock.layers.iter().find(|layer| layer.commit.is_none()) {
+        anyhow::bail!(
+            "{} has no revision for '{}', the original template cannot be reconstructed",
+            LOCK_FILE,
+            layer.template
+        );
+    }
+    let template = manager
+        .find_template(&lock.template)
+        .with_context(|| format!("Template '{}' from {} is not configured", lock.template, LOCK_FILE))?;
+    status!(
+        "{}",
+        format!("🔄 Updating {} from template '{}'", project_dir.display(), template.id).header()
+    );
+    
+    let scratch = std::env::temp_dir()
+        .join("mammoth-cli")
+        .join(format!("update_{}", std::process::id()));
+    if scratch.exists() {
+        fs::remove_dir_all(&scratch).context("Failed to clear update directory")?;
+    }
+    let result = render_and_merge(manager, &lock, project_dir, &scratch, dry_run, policy).await;
+    fs::remove_dir_all(&scratch).ok();
+    result
+}
+
+async fn render_and_merge(
+    manager: &TemplateManager,
+    lock: &ProjectLock,
+    project_dir: &Path,
+    scratch: &Path,
+    dry_run: bool,
+    policy: Option<ConflictPolicy>,
+) -> Result<()> {
+    status!("📦 Rendering the project at its locked revisions...");
+    let base_root = scratch.join("base");
+    let options = GenerateOptions {
+        skip_git_init: true,
+        ..lock.generate_options()
+    };
+    generate_project(manager, &lock.project_config(manager, &base_root.to_string_lossy())?, &options).await?;
+    
+    status!("📦 Rendering the project at the latest revisions...");
+    let theirs_root = scratch.join("theirs");
+    let options = GenerateOptions {
+        no_cache: true,
+        skip_git_init: true,
+        locked_commits: BTreeMap::new(),
+        ..lock.generate_options()
+    };
+    generate_project(manager, &lock.project_config(manager, &theirs_root.to_string_lossy())?, &options).await?;
+    
+    let base_dir = base_root.join(&lock.name);
+    let theirs_dir = theirs_root.join(&lock.name);
+    let new_lock = ProjectLock::load(&theirs_dir)?;
+    if new_lock.layers == lock.layers {
+        status!("✨ Project is already at the latest template revisions");
+        return Ok(());
+    }
+    
+    let mut files = list_files(&base_dir)?;
+    files.extend(list_files(&theirs_dir)?);
+    files.sort();
+    files.dedup();
+    let empty = scratch.join("empty");
+    fs::write(&empty, "")?;
+    
+    output!();
+    let mut counts = BTreeMap::new();
+    let mut conflicts = Vec::new();
+    for relative in files.iter().filter(|path| path.as_path() != Path::new(LOCK_FILE)) {
+        let read = |root: &Path| fs::read(root.join(relative)).ok();
+        let (base, theirs, mine) = (read(&base_dir), read(&theirs_dir), read(project_dir));
+        if base == theirs || (theirs.is_some() && mine == theirs) {
+            continue;
+        }
+        let target = project_dir.join(relative);
+        
+        let action = match (&base, &theirs, &mine) {
+            // 模板前后都没有该文件时已在上面跳过
+            (_, None, None) | (None, None, _) => continue,
+            (None, Some(theirs), None) => {
+                if !dry_run {
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(&target, theirs)?;
+                }
+                UpdateAction::Added
+            }
+            (Some(base), None, Some(mine)) if base == mine => {
+                if !dry_run {
+                    fs::remove_file(&target)?;
+                }
+                UpdateAction::Deleted
+            }
+            (Some(_), None, Some(_)) => UpdateAction::Conflict("changed in the project, removed from the template"),
+            (Some(_), Some(_), None) => UpdateAction::Conflict("removed from the project, changed in the template"),
+            (Some(base), Some(theirs), Some(mine)) if base == mine => {
+                if !dry_run {
+                    fs::write(&target, theirs)?;
+                }
+                UpdateAction::Updated
+            }
+            (_, Some(theirs), Some(mine)) if policy.is_some() && !dry_run => {
+                let text = |content: &[u8]| (!is_binary(content)).then(|| String::from_utf8_lossy(content).into_owned());
+                let change = FileChange {
+                    path: relative.clone(),
+                    kind: ChangeKind::Modified,
+                    ours: text(mine),
+                    theirs: text(theirs),
+                };
+                let resolution = choose_resolution(&change, policy.unwrap_or(ConflictPolicy::Prompt))?;
+                apply_resolution(resolution, relative, &theirs_dir, project_dir)?;
+                match resolution {
+                    Resolution::KeepMine => UpdateAction::Kept,
+                    Resolution::TakeTheirs | Resolution::MergeTool => UpdateAction::Updated,
+                    Resolution::WriteNew => UpdateAction::Conflict("changed on both sides, template version written to .new"),
+                }
+            }
+            (base, Some(theirs), Some(mine)) => {
+                if is_binary(mine) || is_binary(theirs) || base.as_deref().is_some_and(is_binary) {
+                    if !dry_run {
+                        fs::write(with_suffix(&target, ".new"), theirs)?;
+                    }
+                    UpdateAction::Conflict("binary file changed on both sides, template version written to .new")
+                } else {
+                    let base_path = match base {
+                        Some(_) => base_dir.join(relative),
+                        None => empty.clone(),
+                    };
+                    let merged = merge_file(&target, &base_path, &theirs_dir.join(relative))?;
+                    if !dry_run {
+                        fs::write(&target, &merged.content)?;
+                    }
+                    if merged.conflicts > 0 {
+                        UpdateAction::Conflict("changed on both sides, conflict markers written")
+                    } else {
+                        UpdateAction::Merged
+                    }
+                }
+            }
+        };
+        
+        let (marker, label, kind) = match action {
+            UpdateAction::Added => ("A".success(), "added", "added"),
+            UpdateAction::Updated => ("M".warning(), "updated", "updated"),
+            UpdateAction::Merged => ("G".highlight(), "merged with local changes", "merged"),
+            UpdateAction::Deleted => ("D".error(), "deleted", "deleted"),
+            UpdateAction::Kept => ("K".dimmed(), "kept local version", "kept"),
+            UpdateAction::Conflict(reason) => {
+                conflicts.push(relative.clone());
+                ("C".error(), reason, "conflicts")
+            }
+        };
+        *counts.entry(kind).or_insert(0) += 1;
+        output!("  {} {} ({})", marker, relative.display(), label);
+    }
+    
+    output!();
+    if dry_run {
+        status!("🧪 Dry run: no files were changed");
+        return Ok(());
+    }
+    new_lock.write(project_dir)?;
+    
+    let summary: Vec<String> = counts.iter().map(|(label, count)| format!("{} {}", count, label)).collect();
+    if conflicts.is_empty() {
+        output!("{}", "🎉 Project updated!".success().bold());
+    } else {
+        output!("{}", "⚠️  Project updated with conflicts".warning().bold());
+    }
+    if !summary.is_empty() {
+        output!("{}", plain_text(&format!("📊 {}", summary.join(", "))));
+    }
+    if !conflicts.is_empty() {
+        output!("Resolve these files, then commit:");
+        for path in &conflicts {
+            output!("  {}", path.display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,358 @@
+use anyhow::{Context, Result};
+use reqwest::Url;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::config::Repo;
+use crate::git::repo_credentials;
+use crate::ui::ensure_online;
+
+/// Git hosting service of a repository, decides API endpoints and auth conventions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    Gitea,
+    AzureDevOps,
+    /// Plain git server, no API
+    Generic,
+}
+
+impl FromStr for Provider {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "github" => Ok(Provider::GitHub),
+            "gitlab" => Ok(Provider::GitLab),
+            "bitbucket" => Ok(Provider::Bitbucket),
+            "gitea" | "forgejo" => Ok(Provider::Gitea),
+            "azure" | "azure-devops" => Ok(Provider::AzureDevOps),
+            "generic" | "git" => Ok(Provider::Generic),
+            _ => anyhow::bail!(
+                "Invalid provider: {}. Use 'github', 'gitlab', 'bitbucket', 'gitea', 'azure' or 'generic'",
+                s
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Provider::GitHub => "GitHub",
+            Provider::GitLab => "GitLab",
+            Provider::Bitbucket => "Bitbucket",
+            Provider::Gitea => "Gitea",
+            Provider::AzureDevOps => "Azure DevOps",
+            Provider::Generic => "git",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Provider {
+    /// Guess the provider from the host name (and Azure's `_git` path segment)
+    fn detect(host: &str, segments: &[&str]) -> Self {
+        if host == "github.com" {
+            Provider::GitHub
+        } else if host == "gitlab.com" || host.starts_with("gitlab.") {
+            Provider::GitLab
+        } else if host == "bitbucket.org" {
+            Provider::Bitbucket
+        } else if host.ends_with("dev.azure.com")
+            || host.ends_with(".visualstudio.com")
+            || segments.contains(&"_git")
+        {
+            Provider::AzureDevOps
+        } else if host == "gitea.com" || host == "codeberg.org" || host.starts_with("gitea.") {
+            Provider::Gitea
+        } else {
+            Provider::Generic
+        }
+    }
+    
+    /// Username paired with a bare token in HTTPS clone URLs
+    pub fn token_username(self) -> &'static str {
+        match self {
+            Provider::GitHub => "x-access-token",
+            Provider::Bitbucket => "x-token-auth",
+            Provider::AzureDevOps => "pat",
+            Provider::GitLab | Provider::Gitea | Provider::Generic => "oauth2",
+        }
+    }
+}
+
+/// Where a repository lives on its provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoLocation {
+    pub provider: Provider,
+    pub host: String,
+    /// Scheme, host and port of the web interface and API; `https://{host}` for SSH URLs
+    pub origin: String,
+    /// Owner, group path (GitLab subgroups), workspace, or Azure organization
+    pub owner: String,
+    /// Azure DevOps project
+    pub project: Option<String>,
+    pub name: String,
+}
+
+/// Origin, host and path segments of an HTTP(S), ssh:// or scp-style (`git@host:path`)
+/// URL. HTTP(S) URLs keep their scheme and port in the origin, the port of an SSH URL
+/// says nothing about the web interface.
+fn split_url(url: &str) -> Option<(String, String, Vec<&str>)> {
+    let (scheme, authority, path) = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+            (Some(scheme.to_lowercase()), authority, path)
+        }
+        None => {
+            let (authority, path) = url.split_once(':')?;
+            (None, authority, path)
+        }
+    };
+    let address = authority.rsplit('@').next()?.to_lowercase();
+    let host = address.split(':').next()?.to_string();
+    let origin = match scheme.as_deref() {
+        Some(scheme @ ("http" | "https")) => format!("{}://{}", scheme, address),
+        _ => format!("https://{}", host),
+    };
+    let segments = path
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    Some((origin, host, segments))
+}
+
+/// `base` with `segments` appended as percent-encoded path segments and `query` as
+/// encoded pairs
+fn api_url(base: &str, segments: &[&str], query: &[(&str, &str)]) -> Option<Url> {
+    let mut url = Url::parse(base).ok()?;
+    url.path_segments_mut().ok()?.pop_if_empty().extend(segments);
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+    Some(url)
+}
+
+impl RepoLocation {
+    /// Parse the URL of `repo`, using its `provider` setting for self-hosted servers
+    pub fn of(repo: &Repo) -> Option<Self> {
+        let (origin, host, segments) = split_url(&repo.url)?;
+        let provider = match &repo.provider {
+            Some(provider) => provider.parse().ok()?,
+            None => Provider::detect(&host, &segments),
+        };
+        
+        if provider == Provider::AzureDevOps {
+            return Self::azure(host, &segments);
+        }
+        let (name, owner) = segments.split_last()?;
+        if owner.is_empty() {
+            return None;
+        }
+        Some(RepoLocation {
+            provider,
+            host,
+            origin,
+            owner: owner.join("/"),
+            project: None,
+            name: name.to_string(),
+        })
+    }
+    
+    /// dev.azure.com/{org}/{project}/_git/{repo}, {org}.visualstudio.com/{project}/_git/{repo}
+    /// or ssh.dev.azure.com:v3/{org}/{project}/{repo}
+    fn azure(host: String, segments: &[&str]) -> Option<Self> {
+        let (owner, project, name) = match segments {
+            ["v3", org, project, name] => (org.to_string(), project, name),
+            [org, project, "_git", name] if host.ends_with("dev.azure.com") => (org.to_string(), project, name),
+            [project, "_git", name] => (host.split('.').next()?.to_string(), project, name),
+            _ => return None,
+        };
+        Some(RepoLocation {
+            provider: Provider::AzureDevOps,
+            host: "dev.azure.com".to_string(),
+            origin: "https://dev.azure.com".to_string(),
+            owner,
+            project: Some(project.to_string()),
+            name: name.to_string(),
+        })
+    }
+    
+    /// API root of a GitHub repository: api.github.com, or `/api/v3` on GitHub Enterprise
+    fn github_api(&self) -> String {
+        if self.host == "github.com" {
+            "https://api.github.com".to_string()
+        } else {
+            format!("{}/api/v3", self.origin)
+        }
+    }
+    
+    /// REST endpoint describing the repository, `None` for generic servers
+    fn repo_api(&self) -> Option<Url> {
+        let project = self.project.as_deref().unwrap_or_default();
+        match self.provider {
+            Provider::GitHub => api_url(&self.github_api(), &["repos", &self.owner, &self.name], &[]),
+            // GitLab 的项目 ID 是整段编码后的 "group/subgroup/name"
+            Provider::GitLab => api_url(
+                &self.origin,
+                &["api", "v4", "projects", &format!("{}/{}", self.owner, self.name)],
+                &[("statistics", "true")],
+            ),
+            Provider::Bitbucket => api_url(
+                "https://api.bitbucket.org",
+                &["2.0", "repositories", &self.owner, &self.name],
+                &[],
+            ),
+            Provider::Gitea => api_url(&self.origin, &["api", "v1", "repos", &self.owner, &self.name], &[]),
+            Provider::AzureDevOps => api_url(
+                &self.origin,
+                &[&self.owner, project, "_apis", "git", "repositories", &self.name],
+                &[("api-version", "7.0")],
+            ),
+            Provider::Generic => None,
+        }
+    }
+    
+    /// REST endpoint listing the newest commit touching `path` on `branch`
+    fn last_commit_api(&self, branch: &str, path: &str) -> Option<Url> {
+        let project = self.project.as_deref().unwrap_or_default();
+        match self.provider {
+            Provider::GitHub => api_url(
+                &self.github_api(),
+                &["repos", &self.owner, &self.name, "commits"],
+                &[("sha", branch), ("path", path), ("per_page", "1")],
+            ),
+            Provider::GitLab => api_url(
+                &self.origin,
+                &[
+                    "api",
+                    "v4",
+                    "projects",
+                    &format!("{}/{}", self.owner, self.name),
+                    "repository",
+                    "commits",
+                ],
+                &[("ref_name", branch), ("path", path), ("per_page", "1")],
+            ),
+            Provider::Bitbucket => api_url(
+                "https://api.bitbucket.org",
+                &["2.0", "repositories", &self.owner, &self.name, "commits", branch],
+                &[("path", path), ("pagelen", "1")],
+            ),
+            Provider::Gitea => api_url(
+                &self.origin,
+                &["api", "v1", "repos", &self.owner, &self.name, "commits"],
+                &[("sha", branch), ("path", path), ("limit", "1")],
+            ),
+            Provider::AzureDevOps => api_url(
+                &self.origin,
+                &[&self.owner, project, "_apis", "git", "repositories", &self.name, "commits"],
+                &[
+                    ("searchCriteria.itemVersion.version", branch),
+                    ("searchCriteria.itemPath", &format!("/{}", path)),
+                    ("$top", "1"),
+                    ("api-version", "7.0"),
+                ],
+            ),
+            Provider::Generic => None,
+        }
+    }
+}
+
+/// Facts about a repository reported by its provider API
+#[derive(Debug, Clone, Default)]
+pub struct RepoInfo {
+    pub stars: Option<u64>,
+    /// Repository size in bytes
+    pub size: Option<u64>,
+}
+
+/// GET a provider API endpoint with the provider's auth header
+async fn api_get(repo: &Repo, location: &RepoLocation, url: Url) -> Result<serde_json::Value> {
+    ensure_online(&format!("query the {} API", location.provider))?;
+    tracing::info!("GET {}", url);
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let mut request = client
+        .get(url.clone())
+        .header("Accept", "application/json")
+        .header("User-Agent", concat!("mammoth-cli/", env!("CARGO_PKG_VERSION")));
+    
+    let mut token = repo_credentials(repo).1;
+    // 未配置令牌时 GitHub 可以用 GITHUB_TOKEN 提高 API 限额
+    if token.is_none() && location.provider == Provider::GitHub {
+        token = std::env::var("GITHUB_TOKEN").ok();
+    }
+    if let Some(token) = token.filter(|token| !token.is_empty()) {
+        request = match location.provider {
+            Provider::GitLab => request.header("PRIVATE-TOKEN", token),
+            Provider::Gitea => request.header("Authorization", format!("token {}", token)),
+            Provider::AzureDevOps => request.basic_auth("", Some(token)),
+            _ => request.bearer_auth(token),
+        };
+    }
+    
+    let body = request.send().await?.error_for_status()?.text().await?;
+    serde_json::from_str(&body).with_context(|| format!("Invalid {} API response from {}", location.provider, url))
+}
+
+fn location_of(repo: &Repo) -> Result<RepoLocation> {
+    RepoLocation::of(repo)
+        .filter(|location| location.provider != Provider::Generic)
+        .ok_or_else(|| anyhow::anyhow!("No supported hosting API for repository '{}'", repo.name))
+}
+
+/// Stars and size of the repository from its provider API
+pub async fn fetch_repo_info(repo: &Repo) -> Result<RepoInfo> {
+    let location = location_of(repo)?;
+    let url = location
+        .repo_api()
+        .with_context(|| format!("Invalid API URL for repository '{}'", repo.name))?;
+    let info = api_get(repo, &location, url).await?;
+    let number = |pointer: &str| info.pointer(pointer).and_then(|v| v.as_u64());
+    
+    Ok(match location.provider {
+        Provider::GitHub => RepoInfo {
+            stars: number("/stargazers_count"),
+            size: number("/size").map(|kib| kib * 1024),
+        },
+        Provider::GitLab => RepoInfo {
+            stars: number("/star_count"),
+            size: number("/statistics/repository_size"),
+        },
+        Provider::Gitea => RepoInfo {
+            stars: number("/stars_count"),
+            size: number("/size").map(|kib| kib * 1024),
+        },
+        Provider::Bitbucket | Provider::AzureDevOps => RepoInfo {
+            stars: None,
+            size: number("/size"),
+        },
+        Provider::Generic => RepoInfo::default(),
+    })
+}
+
+/// ISO 8601 date of the newest commit touching `path` on `branch`
+pub async fn fetch_last_commit_date(repo: &Repo, branch: &str, path: &str) -> Result<Option<String>> {
+    let location = location_of(repo)?;
+    let url = location
+        .last_commit_api(branch, path)
+        .with_context(|| format!("Invalid API URL for repository '{}'", repo.name))?;
+    let commits = api_get(repo, &location, url).await?;
+    
+    let date = match location.provider {
+        Provider::GitHub | Provider::Gitea => commits.pointer("/0/commit/committer/date"),
+        Provider::GitLab => commits.pointer("/0/committed_date"),
+        Provider::Bitbucket => commits.pointer("/values/0/date"),
+        Provider::AzureDevOps => commits.pointer("/value/0/committer/date"),
+        Provider::Generic => None,
+    };
+    Ok(date.and_then(|v| v.as_str()).map(|date| date.to_string()))
+}
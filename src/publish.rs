@@ -0,0 +1,528 @@
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::Repo;
+use crate::git::{authenticated_url, redact_git_error, run_remote_git};
+use crate::manager::TemplateManager;
+use crate::manifest::{condition_variable, is_manifest_file, TemplateManifest, BUILTIN_VARIABLES};
+use crate::provider::{Provider, RepoLocation};
+use crate::source::RepoSource;
+use crate::theme::Themed;
+use crate::ui::{ensure_online, plain_text};
+use crate::license::validate_license;
+use crate::utils::{
+    format_size, http_client, interpolate, is_binary, list_files, matching_paths, parse_color, parse_size,
+    replace_placeholders, unix_now,
+};
+use crate::{output, status};
+
+/// Largest template file `template lint` accepts by default
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Problems `template lint` found in a template directory
+#[derive(Debug, Default, Serialize)]
+pub struct TemplateCheck {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Placeholder substitutions made while rendering the test project
+    pub substitutions: usize,
+}
+
+impl TemplateCheck {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+    
+    pub fn print(&self) {
+        for error in &self.errors {
+            output!("  {} {}", "❌".error(), error);
+        }
+        for warning in &self.warnings {
+            output!("  {} {}", "⚠️ ".warning(), warning);
+        }
+    }
+}
+
+/// Where `template publish` sends a template
+pub enum PublishTarget {
+    /// Commit to a new branch of a configured repository, to be merged by pull request
+    Repo {
+        name: String,
+        /// Template path inside the repository, before the repository's path prefix
+        path: String,
+        /// Branch to push, `publish/<path>-<timestamp>` when unset
+        branch: Option<String>,
+    },
+    /// POST a `.tar.gz` of the template to a registry endpoint
+    Registry { url: String, token: Option<String> },
+}
+
+/// Variable names referenced as `{{name}}` in `text`, or why the braces do not parse
+fn referenced_variables(text: &str) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    loop {
+        let open = rest.find("{{");
+        let close = rest.find("}}");
+        match (open, close) {
+            (None, None) => return Ok(names),
+            (None, Some(_)) => return Err("'}}' without a matching '{{'".to_string()),
+            (Some(open), Some(close)) if close < open => {
+                return Err("'}}' without a matching '{{'".to_string())
+            }
+            (Some(_), None) => return Err("'{{' is never closed".to_string()),
+            (Some(open), Some(close)) => {
+                let name = rest[open + 2..close].trim();
+                if name.contains("{{") {
+                    return Err(format!("nested '{{{{' in '{{{{{}}}}}'", name));
+                }
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(format!("'{{{{{}}}}}' is not a variable name", name));
+                }
+                names.push(name.to_string());
+                rest = &rest[close + 2..];
+            }
+        }
+    }
+}
+
+/// Machine-specific path prefixes that should not end up in a template
+const ABSOLUTE_PATH_PATTERNS: [&str; 5] = ["/Users/", "/home/", "C:\\Users\\", "C:/Users/", "/private/var/folders/"];
+
+/// Template directory of `target`: an existing directory, or else the cache of the
+/// template with that ID
+pub fn resolve_template_dir(manager: &TemplateManager, target: &str) -> Result<PathBuf> {
+    let path = Path::new(target);
+    if path.is_dir() {
+        return Ok(path.to_path_buf());
+    }
+    let template = manager
+        .find_template(target)
+        .with_context(|| format!("'{}' is neither a directory nor a template ID", target))?;
+    manager.cached_template_path(template)
+}
+
+/// Check a template directory for manifest mistakes, broken `{{variable}}` syntax,
+/// undeclared variables, machine-specific absolute paths and oversized files, and
+/// render a test project from it in a temp directory with the variable defaults
+pub fn lint_template(manager: &TemplateManager, dir: &Path, max_file_size: u64) -> Result<TemplateCheck> {
+    let mut check = TemplateCheck::default();
+    if !dir.is_dir() {
+        anyhow::bail!("Template directory not found: {}", dir.display());
+    }
+    
+    let files: Vec<PathBuf> = list_files(dir)?
+        .into_iter()
+        .filter(|path| !is_manifest_file(path))
+        .collect();
+    if files.is_empty() {
+        check.errors.push("The template has no files besides its manifest".to_string());
+    }
+    
+    let manifest = match TemplateManifest::load(dir) {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => {
+            check
+                .warnings
+                .push("No mammoth.json or mammoth.toml, projects get no variables or placeholders".to_string());
+            TemplateManifest::default()
+        }
+        Err(e) => {
+            check.errors.push(format!("Manifest: {:#}", e));
+            return Ok(check);
+        }
+    };
+    lint_manifest_fields(dir, &manifest, &mut check);
+    
+    // 用内置变量的示例值和声明变量的默认值渲染
+    let mut vars: HashMap<String, String> = BUILTIN_VARIABLES
+        .iter()
+        .map(|name| (name.to_string(), format!("sample-{}", name.replace('_', "-"))))
+        .collect();
+    for (index, variable) in manifest.variables.iter().enumerate() {
+        if manifest.variables[..index].iter().any(|v| v.name == variable.name) {
+            check.errors.push(format!("Variable '{}' is declared twice", variable.name));
+        }
+        let sample = variable.default_value().unwrap_or_else(|| "sample".to_string());
+        let value = variable.parse_value(&sample).unwrap_or(sample);
+        vars.insert(variable.name.clone(), value);
+    }
+    
+    let mut texts = Vec::new();
+    for relative in &files {
+        let path = dir.join(relative);
+        let size = fs::metadata(&path)?.len();
+        if size > max_file_size {
+            check.errors.push(format!(
+                "{} is {}, over the {} limit",
+                relative.display(),
+                format_size(size),
+                format_size(max_file_size)
+            ));
+            continue;
+        }
+        let content = fs::read(&path)?;
+        if is_binary(&content) {
+            continue;
+        }
+        let text = String::from_utf8_lossy(&content).into_owned();
+        for (number, line) in text.lines().enumerate() {
+            if let Some(pattern) = ABSOLUTE_PATH_PATTERNS.iter().find(|p| line.contains(*p)) {
+                check.warnings.push(format!(
+                    "{}:{} contains the absolute path prefix '{}', which only exists on the author's machine",
+                    relative.display(),
+                    number + 1,
+                    pattern
+                ));
+            }
+        }
+        texts.push(text);
+    }
+    
+    for (placeholder, value) in &manifest.placeholders {
+        if placeholder.trim().is_empty() {
+            check.errors.push("A placeholder is empty or only whitespace".to_string());
+            continue;
+        }
+        match referenced_variables(value) {
+            Ok(names) => {
+                for name in names.iter().filter(|name| !vars.contains_key(*name)) {
+                    check.errors.push(format!(
+                        "Placeholder '{}' uses '{{{{{}}}}}', which is not a declared variable",
+                        placeholder, name
+                    ));
+                }
+            }
+            Err(problem) => check
+                .errors
+                .push(format!("Placeholder '{}' → '{}': {}", placeholder, value, problem)),
+        }
+        if !texts.iter().any(|text| text.contains(placeholder.as_str())) {
+            check
+                .warnings
+                .push(format!("Placeholder '{}' does not occur in any text file", placeholder));
+        }
+    }
+    for step in &manifest.next_steps {
+        match referenced_variables(step) {
+            Ok(names) => {
+                for name in names.iter().filter(|name| !vars.contains_key(*name)) {
+                    check
+                        .errors
+                        .push(format!("Next step '{}' uses undeclared variable '{}'", step, name));
+                }
+            }
+            Err(problem) => check.errors.push(format!("Next step '{}': {}", step, problem)),
+        }
+    }
+    for (pattern, condition) in &manifest.conditions {
+        if let Err(e) = matching_paths(dir, pattern) {
+            check.errors.push(format!("conditions: {:#}", e));
+        }
+        match condition_variable(condition) {
+            Ok(name) if !vars.contains_key(name) => check.errors.push(format!(
+                "Condition '{}' of '{}' tests undeclared variable '{}'",
+                condition, pattern, name
+            )),
+            Ok(_) => {}
+            Err(e) => check.errors.push(format!("conditions: {:#}", e)),
+        }
+    }
+    for (from, to) in &manifest.rename {
+        if !dir.join(from.trim().trim_matches('/')).exists() {
+            check
+                .warnings
+                .push(format!("Rename of '{}' matches no file in the template", from));
+        }
+        match referenced_variables(to) {
+            Ok(names) => {
+                for name in names.iter().filter(|name| !vars.contains_key(*name)) {
+                    check
+                        .errors
+                        .push(format!("Rename of '{}' uses undeclared variable '{}'", from, name));
+                }
+            }
+            Err(problem) => check.errors.push(format!("Rename of '{}' → '{}': {}", from, to, problem)),
+        }
+    }
+    if let Some(header) = &manifest.license_header {
+        if let Err(e) = validate_license(header) {
+            check.errors.push(format!("license_header: {:#}", e));
+        }
+    }
+    
+    let render_dir = std::env::temp_dir()
+        .join("mammoth-cli")
+        .join(format!("lint_{}", std::process::id()));
+    let rendered = render_test_project(manager, dir, &render_dir, &manifest, &vars);
+    fs::remove_dir_all(&render_dir).ok();
+    match rendered {
+        Ok(substitutions) => check.substitutions = substitutions,
+        Err(e) => check.errors.push(format!("Test render failed: {:#}", e)),
+    }
+    
+    Ok(check)
+}
+
+/// Manifest fields that parse but hold values mammoth-cli cannot use
+fn lint_manifest_fields(dir: &Path, manifest: &TemplateManifest, check: &mut TemplateCheck) {
+    match &manifest.version {
+        Some(version) if semver::Version::parse(version).is_err() => check
+            .errors
+            .push(format!("version '{}' is not a semantic version like 1.2.0", version)),
+        Some(_) => {}
+        None => check
+            .warnings
+            .push("The manifest has no version, 'template outdated' cannot compare releases".to_string()),
+    }
+    if let Err(e) = manifest.check_compatibility() {
+        check.errors.push(format!("{:#}", e));
+    }
+    if let Some(color) = &manifest.color {
+        if let Err(e) = parse_color(color) {
+            check.errors.push(format!("color: {:#}", e));
+        }
+    }
+    for pattern in &manifest.prune {
+        if let Err(e) = matching_paths(dir, pattern) {
+            check.errors.push(format!("prune: {:#}", e));
+        }
+    }
+    for pattern in manifest.merge.keys() {
+        if let Err(e) = matching_paths(dir, pattern) {
+            check.errors.push(format!("merge: {:#}", e));
+        }
+    }
+    if let Some(patch) = &manifest.package_json {
+        if !patch.is_object() {
+            check.errors.push("package_json must be an object merged into package.json".to_string());
+        }
+    }
+}
+
+fn render_test_project(
+    manager: &TemplateManager,
+    dir: &Path,
+    render_dir: &Path,
+    manifest: &TemplateManifest,
+    vars: &HashMap<String, String>,
+) -> Result<usize> {
+    if render_dir.exists() {
+        fs::remove_dir_all(render_dir).context("Failed to clear the test render directory")?;
+    }
+    manager.copy_template_dir(dir, render_dir, vars)?;
+    let replacements: Vec<(String, String)> = manifest
+        .placeholders
+        .iter()
+        .map(|(placeholder, value)| (placeholder.clone(), interpolate(value, vars)))
+        .collect();
+    let report = replace_placeholders(render_dir, &replacements)?;
+    
+    let package_json = render_dir.join("package.json");
+    if package_json.exists() {
+        let content = fs::read_to_string(&package_json)?;
+        serde_json::from_str::<serde_json::Value>(&content)
+            .context("package.json is not valid JSON after placeholder replacement")?;
+    }
+    Ok(report.total_substitutions())
+}
+
+/// Lint `dir` and print the findings, failing when there are errors
+fn lint_or_fail(manager: &TemplateManager, dir: &Path, max_file_size: u64) -> Result<TemplateCheck> {
+    status!("{}", format!("🔍 Linting template {}...", dir.display()).header());
+    let check = lint_template(manager, dir, max_file_size)?;
+    check.print();
+    if !check.is_ok() {
+        anyhow::bail!("Template lint failed with {} errors", check.errors.len());
+    }
+    status!(
+        "✅ Template passed lint ({} warnings, {} substitutions in the test render)",
+        check.warnings.len(),
+        check.substitutions
+    );
+    Ok(check)
+}
+
+/// `template lint` command: lint a template directory or the cache of a template ID
+pub fn template_lint(manager: &TemplateManager, target: &str, max_file_size: Option<&str>, json: bool) -> Result<()> {
+    let dir = resolve_template_dir(manager, target)?;
+    let max_file_size = match max_file_size {
+        Some(size) => parse_size(size)?,
+        None => DEFAULT_MAX_FILE_SIZE,
+    };
+    if !json {
+        return lint_or_fail(manager, &dir, max_file_size).map(|_| ());
+    }
+    
+    let check = lint_template(manager, &dir, max_file_size)?;
+    output!("{}", serde_json::to_string_pretty(&check)?);
+    if !check.is_ok() {
+        anyhow::bail!("Template lint failed with {} errors", check.errors.len());
+    }
+    Ok(())
+}
+
+/// Lint a template directory and, unless `dry_run`, send it to `target`. Without a
+/// target only the lint runs.
+pub async fn publish_template(
+    manager: &TemplateManager,
+    dir: &str,
+    target: Option<PublishTarget>,
+    max_file_size: u64,
+    dry_run: bool,
+) -> Result<()> {
+    let dir = Path::new(dir);
+    lint_or_fail(manager, dir, max_file_size)?;
+    
+    let Some(target) = target else {
+        output!(
+            "{}",
+            plain_text("💡 Publish it with --repo <name> (pull request branch) or --registry-url <url>")
+        );
+        return Ok(());
+    };
+    match target {
+        PublishTarget::Repo { name, path, branch } => {
+            let repo = manager
+                .get_repo_by_name(&name)
+                .ok_or_else(|| anyhow::anyhow!("Repository '{}' not found", name))?;
+            if !RepoSource::of(repo).is_git() {
+                anyhow::bail!("Repository '{}' is not a git repository, templates can only be pushed to git", name);
+            }
+            let branch = branch.unwrap_or_else(|| {
+                let slug = path.trim_matches('/').replace('/', "-");
+                format!("publish/{}-{}", slug, unix_now())
+            });
+            if dry_run {
+                status!("🧪 Dry run: would push {} to branch '{}' of '{}'", dir.display(), branch, name);
+                return Ok(());
+            }
+            push_template_branch(repo, dir, &path, &branch).await
+        }
+        PublishTarget::Registry { url, token } => {
+            if dry_run {
+                status!("🧪 Dry run: would upload {} to {}", dir.display(), url);
+                return Ok(());
+            }
+            upload_template(dir, &url, token.as_deref()).await
+        }
+    }
+}
+
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Clone the repository, replace the template path with `dir` on a new branch and push it
+async fn push_template_branch(repo: &Repo, dir: &Path, path: &str, branch: &str) -> Result<()> {
+    let work_dir = std::env::temp_dir()
+        .join("mammoth-cli")
+        .join(format!("publish_{}_{}", repo.name, std::process::id()));
+    if work_dir.exists() {
+        fs::remove_dir_all(&work_dir).context("Failed to clear the publish directory")?;
+    }
+    fs::create_dir_all(&work_dir)?;
+    let pushed = push_from_clone(repo, dir, path, branch, &work_dir).await;
+    fs::remove_dir_all(&work_dir).ok();
+    pushed.map_err(|e| redact_git_error(e, repo))
+}
+
+async fn push_from_clone(repo: &Repo, dir: &Path, path: &str, branch: &str, work_dir: &Path) -> Result<()> {
+    let url = authenticated_url(repo);
+    status!("📥 Cloning {}...", repo.name);
+    run_remote_git(
+        repo,
+        work_dir,
+        &["clone", "--depth", "1", "--branch", &repo.branch, &url, "repo"],
+        PUBLISH_TIMEOUT,
+    )
+    .await?;
+    let clone = work_dir.join("repo");
+    run_remote_git(repo, &clone, &["checkout", "-b", branch], PUBLISH_TIMEOUT).await?;
+    
+    let repo_path = match repo.path_prefix.as_deref().map(|p| p.trim_matches('/')) {
+        Some(prefix) if !prefix.is_empty() => format!("{}/{}", prefix, path.trim_matches('/')),
+        _ => path.trim_matches('/').to_string(),
+    };
+    let target = clone.join(&repo_path);
+    if target.exists() {
+        fs::remove_dir_all(&target).with_context(|| format!("Failed to replace {}", repo_path))?;
+    }
+    for relative in list_files(dir)? {
+        let dest = target.join(&relative);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(dir.join(&relative), &dest)
+            .with_context(|| format!("Failed to copy {}", relative.display()))?;
+    }
+    
+    run_remote_git(repo, &clone, &["add", "-A", "--", &repo_path], PUBLISH_TIMEOUT).await?;
+    let changes = run_remote_git(repo, &clone, &["status", "--porcelain"], PUBLISH_TIMEOUT).await?;
+    if changes.is_empty() {
+        status!("✨ {} in '{}' is already up to date, nothing to publish", repo_path, repo.name);
+        return Ok(());
+    }
+    let message = format!("Publish template {}", repo_path);
+    run_remote_git(repo, &clone, &["commit", "-m", &message], PUBLISH_TIMEOUT).await?;
+    status!("📤 Pushing branch '{}'...", branch);
+    run_remote_git(repo, &clone, &["push", "-u", "origin", branch], PUBLISH_TIMEOUT).await?;
+    
+    status!("✅ Pushed {} to branch '{}' of '{}'", repo_path, branch, repo.name);
+    match pull_request_url(repo, branch) {
+        Some(url) => output!("{}", plain_text(&format!("🔗 Open a pull request: {}", url))),
+        None => output!(
+            "{}",
+            plain_text(&format!("💡 Open a pull request from '{}' into '{}'", branch, repo.branch))
+        ),
+    }
+    Ok(())
+}
+
+/// Web page that opens a pull request for `branch`, for providers with a known URL scheme
+fn pull_request_url(repo: &Repo, branch: &str) -> Option<String> {
+    let location = RepoLocation::of(repo)?;
+    let base = format!("{}/{}/{}", location.origin, location.owner, location.name);
+    match location.provider {
+        Provider::GitHub => Some(format!("{}/compare/{}...{}?expand=1", base, repo.branch, branch)),
+        Provider::GitLab => Some(format!(
+            "{}/-/merge_requests/new?merge_request[source_branch]={}&merge_request[target_branch]={}",
+            base, branch, repo.branch
+        )),
+        Provider::Gitea => Some(format!("{}/compare/{}...{}", base, repo.branch, branch)),
+        _ => None,
+    }
+}
+
+/// POST the template as a `.tar.gz` to a registry endpoint
+async fn upload_template(dir: &Path, url: &str, token: Option<&str>) -> Result<()> {
+    ensure_online(&format!("upload to {}", url))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for relative in list_files(dir)? {
+        archive
+            .append_path_with_name(dir.join(&relative), &relative)
+            .with_context(|| format!("Failed to archive {}", relative.display()))?;
+    }
+    let content = archive.into_inner()?.finish()?;
+    
+    status!("📤 Uploading {} ({} bytes) to {}...", dir.display(), content.len(), url);
+    let mut request = http_client(PUBLISH_TIMEOUT)?
+        .post(url)
+        .header("Content-Type", "application/gzip")
+        .body(content);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Registry rejected the upload ({}): {}", status, body.trim());
+    }
+    status!("✅ Template uploaded ({})", status);
+    Ok(())
+}
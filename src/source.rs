@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::Repo;
+use crate::git::repo_credentials;
+use crate::ui::ensure_online;
+
+/// Where the files of a repository come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepoSource {
+    /// A git remote, cloned and fetched with git
+    Git,
+    /// A directory on this machine, copied as-is without git
+    Local(PathBuf),
+    /// A `.tar.gz` or `.zip` archive downloaded over HTTP, e.g. a GitHub archive link
+    Archive(ArchiveSource),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveSource {
+    pub url: String,
+    pub format: ArchiveFormat,
+}
+
+impl RepoSource {
+    pub fn of(repo: &Repo) -> Self {
+        Self::from_url(&repo.url)
+    }
+    
+    /// `file://` URLs and absolute paths are local directories, HTTP URLs ending in
+    /// `.tar.gz`, `.tgz` or `.zip` are archives, everything else is git
+    pub fn from_url(url: &str) -> Self {
+        if let Some(path) = url.strip_prefix("file://") {
+            return RepoSource::Local(file_url_path(path));
+        }
+        if url.starts_with("http://") || url.starts_with("https://") {
+            // 忽略查询参数和锚点，例如制品服务器的签名链接
+            let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+            let format = if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+                Some(ArchiveFormat::TarGz)
+            } else if path.ends_with(".zip") {
+                Some(ArchiveFormat::Zip)
+            } else {
+                None
+            };
+            if let Some(format) = format {
+                return RepoSource::Archive(ArchiveSource {
+                    url: url.to_string(),
+                    format,
+                });
+            }
+        }
+        let path = Path::new(url);
+        if path.is_absolute() && path.is_dir() {
+            return RepoSource::Local(path.to_path_buf());
+        }
+        RepoSource::Git
+    }
+    
+    pub fn is_local(&self) -> bool {
+        matches!(self, RepoSource::Local(_))
+    }
+    
+    pub fn is_git(&self) -> bool {
+        matches!(self, RepoSource::Git)
+    }
+}
+
+fn archive_request(repo: &Repo, method: reqwest::Method, url: &str, timeout: Duration) -> Result<reqwest::RequestBuilder> {
+    ensure_online(&format!("download the archive of repository '{}'", repo.name))?;
+    tracing::info!("{} {}", method, url);
+    let client = reqwest::Client::builder().timeout(timeout).build()?;
+    let mut request = client
+        .request(method, url)
+        .header("User-Agent", concat!("mammoth-cli/", env!("CARGO_PKG_VERSION")));
+    if let (username, Some(token)) = repo_credentials(repo) {
+        request = match username {
+            Some(username) => request.basic_auth(username, Some(token)),
+            None => request.bearer_auth(token),
+        };
+    }
+    Ok(request)
+}
+
+/// Download the archive of `repo`, sending the repository token when one is configured
+pub async fn download_archive(repo: &Repo, archive: &ArchiveSource) -> Result<Vec<u8>> {
+    let response = archive_request(repo, reqwest::Method::GET, &archive.url, Duration::from_secs(600))?
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Failed to download archive of repository '{}'", repo.name))?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Status code of a HEAD request for the archive, used by `repo verify`
+pub async fn probe_archive(repo: &Repo, archive: &ArchiveSource) -> Result<reqwest::StatusCode> {
+    let response = archive_request(repo, reqwest::Method::HEAD, &archive.url, Duration::from_secs(30))?
+        .send()
+        .await?;
+    Ok(response.status())
+}
+
+/// Unpack `content` into `dest` and return the directory holding the repository files.
+/// Archives with a single top-level directory (`repo-main/` in GitHub archives) resolve
+/// to that directory, so template paths stay relative to the repository root.
+pub fn extract_archive(content: &[u8], format: ArchiveFormat, dest: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dest).context("Failed to create archive directory")?;
+    match format {
+        ArchiveFormat::TarGz => {
+            let decoder = flate2::read::GzDecoder::new(Cursor::new(content));
+            tar::Archive::new(decoder)
+                .unpack(dest)
+                .context("Failed to extract tar.gz archive")?;
+        }
+        ArchiveFormat::Zip => {
+            zip::ZipArchive::new(Cursor::new(content))
+                .and_then(|mut archive| archive.extract(dest))
+                .context("Failed to extract zip archive")?;
+        }
+    }
+    
+    let entries: Vec<PathBuf> = fs::read_dir(dest)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+    match entries.as_slice() {
+        [single] if single.is_dir() => Ok(single.clone()),
+        _ => Ok(dest.to_path_buf()),
+    }
+}
+
+/// Path part of a `file://` URL; `file:///C:/templates` becomes `C:/templates` on Windows
+fn file_url_path(path: &str) -> PathBuf {
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    if cfg!(windows) {
+        let trimmed = path.trim_start_matches('/');
+        if trimmed.as_bytes().get(1) == Some(&b':') {
+            return PathBuf::from(trimmed);
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// Normalize a local repository path given to `repo add` into a `file://` URL
+pub fn local_repo_url(url: &str) -> Option<String> {
+    match RepoSource::from_url(url) {
+        RepoSource::Local(path) => {
+            let path = path.canonicalize().unwrap_or(path);
+            let path = path.to_string_lossy().replace('\\', "/");
+            let path = path.trim_start_matches("//?/");
+            if path.starts_with('/') {
+                Some(format!("file://{}", path))
+            } else {
+                Some(format!("file:///{}", path))
+            }
+        }
+        // 压缩包地址不是本地路径
+        RepoSource::Archive(_) | RepoSource::Git => None,
+    }
+}
@@ -0,0 +1,570 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Template;
+use crate::manager::TemplateManager;
+use crate::manifest::{is_manifest_file, TemplateManifest, TemplateVariable, VariableKind, MANIFEST_FILE, MANIFEST_TOML_FILE};
+use crate::output;
+use crate::theme::Themed;
+use crate::ui::{is_interactive, plain_text};
+use crate::utils::{is_binary, list_files};
+use dialoguer::{Confirm, Select};
+
+/// Directories left out of a templatized project: VCS data, dependencies and build output
+const ARTIFACT_DIRS: [&str; 13] = [
+    ".git",
+    "node_modules",
+    "dist",
+    "build",
+    "out",
+    "coverage",
+    ".next",
+    ".nuxt",
+    ".output",
+    ".svelte-kit",
+    ".turbo",
+    ".cache",
+    ".vercel",
+];
+
+/// Files left out of a templatized project
+const ARTIFACT_FILES: [&str; 2] = [".DS_Store", "Thumbs.db"];
+
+/// The only `.env*` file copied into a template, the others may hold secrets
+const ENV_EXAMPLE_FILE: &str = ".env.example";
+
+/// Framework packages used to guess the language of a project, checked in order
+const LANGUAGE_PACKAGES: [(&str, &str); 7] = [
+    ("nuxt", "vue"),
+    ("vue", "vue"),
+    ("next", "react"),
+    ("react", "react"),
+    ("svelte", "svelte"),
+    ("@angular/core", "angular"),
+    ("solid-js", "solid"),
+];
+
+/// What `templatize` learned about the source project
+#[derive(Debug, Clone, Default)]
+pub struct TemplatizedProject {
+    /// Name from package.json, replaced by `{{name}}` in generated projects
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub language: String,
+    /// Number of files copied into the template
+    pub files: usize,
+}
+
+fn is_artifact(name: &str, is_dir: bool) -> bool {
+    if is_dir {
+        ARTIFACT_DIRS.contains(&name)
+    } else {
+        ARTIFACT_FILES.contains(&name)
+            || name.ends_with(".log")
+            || (name.starts_with(".env") && name != ENV_EXAMPLE_FILE)
+    }
+}
+
+fn copy_sources(src: &Path, dst: &Path, files: &mut usize) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let is_dir = path.is_dir();
+        if is_artifact(&name, is_dir) {
+            continue;
+        }
+        
+        if is_dir {
+            copy_sources(&path, &dst.join(&name), files)?;
+        } else {
+            fs::copy(&path, dst.join(&name))?;
+            *files += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Guess the template language from the package.json dependencies
+fn detect_language(package_json: &serde_json::Value) -> String {
+    let has_package = |name: &str| {
+        ["dependencies", "devDependencies"]
+            .iter()
+            .any(|section| package_json.get(section).and_then(|deps| deps.get(name)).is_some())
+    };
+    LANGUAGE_PACKAGES
+        .iter()
+        .find(|(package, _)| has_package(package))
+        .map(|(_, language)| language.to_string())
+        .unwrap_or_else(|| {
+            if has_package("typescript") {
+                "typescript".to_string()
+            } else {
+                "javascript".to_string()
+            }
+        })
+}
+
+/// Copy a working project into `dest` as a template: build artifacts, dependencies and
+/// git data are left out, and a starter manifest is written that maps the project's
+/// package name back to `{{name}}`.
+pub fn templatize_project(source: &Path, dest: &Path) -> Result<TemplatizedProject> {
+    if !source.is_dir() {
+        anyhow::bail!("Project directory not found: {}", source.display());
+    }
+    if dest.exists() && fs::read_dir(dest)?.next().is_some() {
+        anyhow::bail!("Template directory is not empty: {}", dest.display());
+    }
+    let dest_parent = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&dest_parent)?;
+    let dest_abs = dest_parent
+        .canonicalize()?
+        .join(dest.file_name().unwrap_or_default());
+    if dest_abs.starts_with(source.canonicalize()?) {
+        anyhow::bail!("Template directory must be outside the project directory");
+    }
+    
+    let mut project = TemplatizedProject::default();
+    copy_sources(source, dest, &mut project.files)
+        .with_context(|| format!("Failed to copy project into {}", dest.display()))?;
+    
+    let package_json: serde_json::Value = match fs::read_to_string(source.join("package.json")) {
+        Ok(content) => serde_json::from_str(&content).context("Failed to parse package.json")?,
+        Err(_) => serde_json::Value::Null,
+    };
+    let field = |key: &str| {
+        package_json
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+    };
+    project.name = field("name");
+    project.description = field("description");
+    project.language = detect_language(&package_json);
+    
+    let mut placeholders = BTreeMap::new();
+    if let Some(name) = &project.name {
+        placeholders.insert(name.clone(), "{{name}}".to_string());
+    }
+    let manifest = TemplateManifest {
+        version: Some("0.1.0".to_string()),
+        placeholders,
+        ..Default::default()
+    };
+    fs::write(
+        dest.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )
+    .context("Failed to write template manifest")?;
+    
+    Ok(project)
+}
+
+/// Literal that could be turned into a template variable
+#[derive(Debug, Clone)]
+pub struct VariableCandidate {
+    pub literal: String,
+    /// Variable proposed for the literal, `None` for repeated literals of unknown meaning
+    pub variable: Option<String>,
+    pub occurrences: usize,
+    pub files: usize,
+}
+
+/// Variables a literal can be mapped to, see `ProjectConfig::variables`
+const TEMPLATE_VARIABLES: [&str; 3] = ["name", "author", "description"];
+
+/// Minimum number of files a string literal must appear in to be proposed
+const REPEATED_LITERAL_MIN_FILES: usize = 3;
+
+/// Repeated literals proposed at most
+const MAX_REPEATED_LITERALS: usize = 10;
+
+/// String literals quoted with ' or " on a line
+fn quoted_literals(line: &str) -> Vec<&str> {
+    let mut literals = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap_or('"');
+        let after = &rest[start + 1..];
+        match after.find(quote) {
+            Some(end) => {
+                literals.push(&after[..end]);
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+    literals
+}
+
+fn is_interesting_literal(literal: &str) -> bool {
+    (4..=60).contains(&literal.chars().count())
+        && literal.chars().any(|c| c.is_alphabetic())
+        && !literal.contains("{{")
+        && !literal.starts_with(['.', '/', '@', '#', '-'])
+        && !literal.contains(['/', '\\'])
+}
+
+/// Author name of a package.json `author` field, "Jane <jane@example.com>" or `{"name": ...}`
+fn package_author(package_json: &serde_json::Value) -> Option<String> {
+    let author = package_json.get("author")?;
+    let name = match author {
+        serde_json::Value::String(author) => author.split(['<', '(']).next()?.trim().to_string(),
+        _ => author.get("name")?.as_str()?.trim().to_string(),
+    };
+    (!name.is_empty()).then_some(name)
+}
+
+/// Scan a project or template for its package name, author, description and string
+/// literals repeated across files, skipping literals the manifest already maps
+pub fn find_variable_candidates(dir: &Path) -> Result<Vec<VariableCandidate>> {
+    let package_json: serde_json::Value = fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or(serde_json::Value::Null);
+    let mapped = TemplateManifest::load(dir)?.unwrap_or_default().placeholders;
+    
+    let mut known: Vec<(String, &str)> = Vec::new();
+    let text_field = |key: &str| package_json.get(key).and_then(|v| v.as_str()).map(|v| v.to_string());
+    if let Some(name) = text_field("name") {
+        known.push((name, "name"));
+    }
+    if let Some(author) = package_author(&package_json) {
+        known.push((author, "author"));
+    }
+    if let Some(description) = text_field("description") {
+        known.push((description, "description"));
+    }
+    known.retain(|(literal, _)| !literal.is_empty() && !mapped.contains_key(literal));
+    
+    // 依赖包名不是项目特有的字面量
+    let dependencies: Vec<String> = ["dependencies", "devDependencies"]
+        .iter()
+        .filter_map(|section| package_json.get(section).and_then(|v| v.as_object()))
+        .flat_map(|deps| deps.keys().cloned())
+        .collect();
+    
+    let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for relative in list_files(dir)? {
+        if relative.components().any(|c| c.as_os_str() == "node_modules") || is_manifest_file(&relative) {
+            continue;
+        }
+        let bytes = fs::read(dir.join(&relative))?;
+        if is_binary(&bytes) {
+            continue;
+        }
+        let content = String::from_utf8_lossy(&bytes);
+        
+        let mut in_file: BTreeMap<String, usize> = BTreeMap::new();
+        for (literal, _) in &known {
+            let found = content.matches(literal.as_str()).count();
+            if found > 0 {
+                in_file.insert(literal.clone(), found);
+            }
+        }
+        for line in content.lines() {
+            for literal in quoted_literals(line) {
+                let is_candidate = is_interesting_literal(literal)
+                    && !dependencies.iter().any(|d| d == literal)
+                    && !known.iter().any(|(known, _)| known == literal);
+                if is_candidate {
+                    *in_file.entry(literal.to_string()).or_default() += 1;
+                }
+            }
+        }
+        for (literal, found) in in_file {
+            let entry = counts.entry(literal.clone()).or_default();
+            entry.0 += found;
+            entry.1 += 1;
+        }
+    }
+    
+    let mut candidates: Vec<VariableCandidate> = known
+        .iter()
+        .filter_map(|(literal, variable)| {
+            counts.get(literal).map(|(occurrences, files)| VariableCandidate {
+                literal: literal.clone(),
+                variable: Some(variable.to_string()),
+                occurrences: *occurrences,
+                files: *files,
+            })
+        })
+        .collect();
+    
+    let mut repeated: Vec<VariableCandidate> = counts
+        .into_iter()
+        .filter(|(literal, (_, files))| {
+            *files >= REPEATED_LITERAL_MIN_FILES
+                && !mapped.contains_key(literal)
+                && !known.iter().any(|(known, _)| known == literal)
+        })
+        .map(|(literal, (occurrences, files))| VariableCandidate {
+            literal,
+            variable: None,
+            occurrences,
+            files,
+        })
+        .collect();
+    repeated.sort_by(|a, b| b.files.cmp(&a.files).then(b.occurrences.cmp(&a.occurrences)));
+    repeated.truncate(MAX_REPEATED_LITERALS);
+    candidates.extend(repeated);
+    
+    Ok(candidates)
+}
+
+/// `template extract-vars` assistant: propose template variables for literals found in
+/// `dir` and record the accepted ones as manifest placeholders. Non-interactive runs
+/// accept the package.json fields and skip repeated literals.
+pub fn extract_vars(dir: &Path) -> Result<()> {
+    output!("{}", "🔍 Scanning for template variables...".header());
+    let candidates = find_variable_candidates(dir)?;
+    if candidates.is_empty() {
+        output!("No candidates found, every known literal is already mapped.");
+        return Ok(());
+    }
+    
+    if dir.join(MANIFEST_TOML_FILE).exists() && !dir.join(MANIFEST_FILE).exists() {
+        anyhow::bail!(
+            "{} would be shadowed by a new {}, add the placeholders to it by hand",
+            MANIFEST_TOML_FILE,
+            MANIFEST_FILE
+        );
+    }
+    let mut manifest = TemplateManifest::load(dir)?.unwrap_or_default();
+    let mut accepted = 0;
+    for candidate in candidates {
+        let summary = format!(
+            "\"{}\" ({} occurrences in {} files)",
+            candidate.literal, candidate.occurrences, candidate.files
+        );
+        let variable = match (&candidate.variable, is_interactive()) {
+            (Some(variable), false) => Some(variable.clone()),
+            (None, false) => None,
+            (Some(variable), true) => Confirm::new()
+                .with_prompt(format!("Replace {} with {{{{{}}}}}?", summary, variable))
+                .default(true)
+                .interact()?
+                .then(|| variable.clone()),
+            (None, true) => {
+                let mut items: Vec<String> = TEMPLATE_VARIABLES.iter().map(|v| format!("{{{{{}}}}}", v)).collect();
+                items.push("Skip".to_string());
+                let selection = Select::new()
+                    .with_prompt(format!("Repeated literal {}", summary))
+                    .items(&items)
+                    .default(items.len() - 1)
+                    .interact()?;
+                TEMPLATE_VARIABLES.get(selection).map(|v| v.to_string())
+            }
+        };
+        
+        if let Some(variable) = variable {
+            output!("{}", plain_text(&format!("  ✅ {} → {{{{{}}}}}", summary, variable)));
+            manifest
+                .placeholders
+                .insert(candidate.literal, format!("{{{{{}}}}}", variable));
+            accepted += 1;
+        }
+    }
+    
+    if accepted > 0 {
+        fs::write(dir.join(MANIFEST_FILE), serde_json::to_string_pretty(&manifest)?)
+            .context("Failed to write template manifest")?;
+    }
+    output!("{}", plain_text(&format!("📝 Recorded {} placeholders in {}", accepted, MANIFEST_FILE)));
+    
+    Ok(())
+}
+
+/// `templatize` command: copy `dir` into a template layout and optionally register it
+/// as a template of `repo`
+pub fn templatize(
+    manager: &mut TemplateManager,
+    dir: &str,
+    output: Option<&str>,
+    repo: Option<&str>,
+    id: Option<&str>,
+    path: Option<&str>,
+    extract: bool,
+) -> Result<()> {
+    let source = Path::new(dir);
+    let dir_name = source
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "project".to_string());
+    let dest = match output {
+        Some(output) => PathBuf::from(output),
+        None => PathBuf::from(format!("{}-template", dir_name)),
+    };
+    
+    output!("{}", plain_text(&format!("📦 Templatizing {} into {}...", source.display(), dest.display())));
+    let project = templatize_project(source, &dest)?;
+    output!("{}", plain_text(&format!("✅ Copied {} files and wrote {}", project.files, MANIFEST_FILE)));
+    if let Some(name) = &project.name {
+        output!("{}", plain_text(&format!("🔤 \"{}\" will be replaced by the project name", name)));
+    }
+    if extract {
+        output!();
+        extract_vars(&dest)?;
+    }
+    
+    let repo = match repo {
+        Some(repo) => repo,
+        None => {
+            output!(
+                "{}",
+                plain_text("💡 Push the template to a repository and run 'template add', or use --register")
+            );
+            return Ok(());
+        }
+    };
+    
+    // 作用域包名 @scope/app 取最后一段作为模板 ID
+    let id = id
+        .map(|id| id.to_string())
+        .or_else(|| project.name.as_ref().and_then(|n| n.rsplit('/').next()).map(|n| n.to_string()))
+        .unwrap_or(dir_name);
+    let template = Template {
+        id: id.clone(),
+        name: project.name.clone().unwrap_or_else(|| id.clone()).into(),
+        repo: repo.to_string(),
+        path: path.map(|p| p.to_string()).unwrap_or_else(|| id.clone()),
+        description: project.description.clone().unwrap_or_default().into(),
+        language: project.language.clone(),
+        tags: Vec::new(),
+        branch: None,
+        git_ref: None,
+        dependencies: Vec::new(),
+        category: None,
+        color: None,
+        icon: None,
+        disabled: false,
+    };
+    let template_path = template.path.clone();
+    manager.add_template(template)?;
+    output!(
+        "{}",
+        plain_text(&format!(
+            "💡 Commit {} to '{}' under '{}' before using the template",
+            dest.display(),
+            repo,
+            template_path
+        ))
+    );
+    
+    Ok(())
+}
+
+/// .gitignore of a skeleton template
+const SKELETON_GITIGNORE: &str = "node_modules/\ndist/\n.env.local\n.DS_Store\n";
+
+/// Entry point of a skeleton template, uses the description and port placeholders
+const SKELETON_INDEX: &str = "// __DESCRIPTION__\nconst port = Number(\"__PORT__\");\n\nconsole.log(`Listening on http://localhost:${port}`);\n";
+
+/// Author README of a skeleton template, listing what each placeholder becomes
+fn skeleton_readme(name: &str, manifest: &TemplateManifest, manifest_file: &str) -> String {
+    let mut readme = format!(
+        "# {}\n\nmammoth-cli template. Files are copied into new projects and every placeholder\n\
+         below is replaced in all text files. This README is listed under `prune` in\n\
+         {} and is not copied.\n\n## Placeholders\n\n| Text | Becomes |\n| --- | --- |\n",
+        name, manifest_file
+    );
+    for (placeholder, value) in &manifest.placeholders {
+        readme.push_str(&format!("| `{}` | `{}` |\n", placeholder, value));
+    }
+    readme.push_str(
+        "\n## Variables\n\nBuilt in: `name`, `author`, `author_email`, `author_url`, `description`,\n\
+         `output_dir`, `template`, `language`.\n\nDeclared in the manifest:\n\n",
+    );
+    for variable in &manifest.variables {
+        readme.push_str(&format!(
+            "- `{}` ({}): {}, default `{}`\n",
+            variable.name,
+            format!("{:?}", variable.kind).to_lowercase(),
+            variable.prompt_text(),
+            variable.default_value().unwrap_or_default()
+        ));
+    }
+    readme.push_str(
+        "\n## Testing\n\n```bash\nmammoth-cli template lint .\n```\n\n\
+         Then push the directory to a template repository and register it with `template add`.\n",
+    );
+    readme
+}
+
+/// `template init` command: create a skeleton template in `dir` with a manifest,
+/// example placeholders and a variable, a .gitignore and an author README
+pub fn init_template(dir: &str, toml: bool) -> Result<()> {
+    let dest = Path::new(dir);
+    if dest.exists() && fs::read_dir(dest)?.next().is_some() {
+        anyhow::bail!("Template directory is not empty: {}", dest.display());
+    }
+    fs::create_dir_all(dest).with_context(|| format!("Failed to create {}", dest.display()))?;
+    let name = dest
+        .canonicalize()?
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "my-template".to_string());
+    
+    let manifest_file = if toml { MANIFEST_TOML_FILE } else { MANIFEST_FILE };
+    let manifest = TemplateManifest {
+        version: Some("0.1.0".to_string()),
+        placeholders: BTreeMap::from([
+            (name.clone(), "{{name}}".to_string()),
+            ("__DESCRIPTION__".to_string(), "{{description}}".to_string()),
+            ("__AUTHOR__".to_string(), "{{author}}".to_string()),
+            ("__PORT__".to_string(), "{{port}}".to_string()),
+        ]),
+        prune: vec!["README.md".to_string()],
+        variables: vec![TemplateVariable {
+            name: "port".to_string(),
+            prompt: Some("Dev server port".to_string()),
+            default: Some(serde_json::json!(3000)),
+            kind: VariableKind::Number,
+            choices: Vec::new(),
+        }],
+        next_steps: vec!["cd {{output_dir}}".to_string(), "npm start".to_string()],
+        ..Default::default()
+    };
+    let manifest_content = if toml {
+        toml::to_string_pretty(&manifest)?
+    } else {
+        serde_json::to_string_pretty(&manifest)?
+    };
+    let package_json = serde_json::json!({
+        "name": name,
+        "version": "0.1.0",
+        "description": "__DESCRIPTION__",
+        "author": "__AUTHOR__",
+        "main": "index.js",
+        "scripts": { "start": "node index.js" }
+    });
+    
+    let files = [
+        (manifest_file, manifest_content),
+        ("package.json", serde_json::to_string_pretty(&package_json)? + "\n"),
+        ("index.js", SKELETON_INDEX.to_string()),
+        (".gitignore", SKELETON_GITIGNORE.to_string()),
+        ("README.md", skeleton_readme(&name, &manifest, manifest_file)),
+    ];
+    for (file, content) in &files {
+        fs::write(dest.join(file), content).with_context(|| format!("Failed to write {}", file))?;
+    }
+    
+    output!("{}", plain_text(&format!("✅ Created template skeleton in {}", dest.display())));
+    for (file, _) in &files {
+        output!("  {}", file);
+    }
+    output!(
+        "{}",
+        plain_text(&format!("💡 Edit the files, then check the template with: mammoth-cli template lint {}", dir))
+    );
+    Ok(())
+}
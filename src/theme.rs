@@ -0,0 +1,148 @@
+use colored::{Color, ColoredString, Colorize};
+use std::sync::OnceLock;
+
+use crate::config::ThemeSettings;
+use crate::ui::plain_text;
+use crate::warning;
+use crate::utils::parse_color;
+
+/// Presets selectable with `theme.preset`
+pub const THEME_PRESETS: [&str; 4] = ["default", "light", "high-contrast", "monochrome"];
+
+/// Colors used for CLI output, `None` leaves the terminal color
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub header: Option<Color>,
+    pub section: Option<Color>,
+    pub success: Option<Color>,
+    pub warning: Option<Color>,
+    pub error: Option<Color>,
+    pub highlight: Option<Color>,
+}
+
+impl Theme {
+    pub fn preset(name: &str) -> anyhow::Result<Self> {
+        let theme = match name {
+            "default" => Theme {
+                header: Some(Color::Blue),
+                section: Some(Color::Yellow),
+                success: Some(Color::Green),
+                warning: Some(Color::Yellow),
+                error: Some(Color::Red),
+                highlight: Some(Color::Cyan),
+            },
+            // 浅色背景下黄色和青色难以阅读
+            "light" => Theme {
+                header: Some(Color::Blue),
+                section: Some(Color::Magenta),
+                success: Some(Color::Green),
+                warning: Some(Color::Magenta),
+                error: Some(Color::Red),
+                highlight: Some(Color::Blue),
+            },
+            "high-contrast" => Theme {
+                header: Some(Color::BrightWhite),
+                section: Some(Color::BrightYellow),
+                success: Some(Color::BrightGreen),
+                warning: Some(Color::BrightYellow),
+                error: Some(Color::BrightRed),
+                highlight: Some(Color::BrightCyan),
+            },
+            "monochrome" => Theme {
+                header: None,
+                section: None,
+                success: None,
+                warning: None,
+                error: None,
+                highlight: None,
+            },
+            other => anyhow::bail!(
+                "Unknown theme preset '{}'. Available presets: {}",
+                other,
+                THEME_PRESETS.join(", ")
+            ),
+        };
+        Ok(theme)
+    }
+    
+    /// Preset with the individual color overrides of the settings applied
+    pub fn from_settings(settings: &ThemeSettings) -> anyhow::Result<Self> {
+        let mut theme = Self::preset(settings.preset.as_deref().unwrap_or("default"))?;
+        let overrides = [
+            (&settings.header, &mut theme.header),
+            (&settings.section, &mut theme.section),
+            (&settings.success, &mut theme.success),
+            (&settings.warning, &mut theme.warning),
+            (&settings.error, &mut theme.error),
+            (&settings.highlight, &mut theme.highlight),
+        ];
+        for (setting, color) in overrides {
+            if let Some(setting) = setting {
+                *color = Some(parse_color(setting)?);
+            }
+        }
+        Ok(theme)
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Install the theme for this run; invalid settings fall back to the default theme
+pub fn init_theme(settings: &ThemeSettings) {
+    let theme = Theme::from_settings(settings).unwrap_or_else(|e| {
+        warning!("⚠️  Invalid theme, using the default: {}", e);
+        Theme::preset("default").expect("default theme exists")
+    });
+    let _ = THEME.set(theme);
+}
+
+fn current() -> &'static Theme {
+    THEME.get_or_init(|| Theme::preset("default").expect("default theme exists"))
+}
+
+fn paint(text: &str, color: Option<Color>) -> ColoredString {
+    let text = plain_text(text);
+    let text = text.as_ref();
+    match color {
+        Some(color) => text.color(color),
+        None => text.normal(),
+    }
+}
+
+/// Semantic colors for output text, resolved through the active theme
+pub trait Themed {
+    /// Command titles, e.g. "📋 Template List"
+    fn header(&self) -> ColoredString;
+    /// Section titles inside a command's output
+    fn section(&self) -> ColoredString;
+    fn success(&self) -> ColoredString;
+    fn warning(&self) -> ColoredString;
+    fn error(&self) -> ColoredString;
+    fn highlight(&self) -> ColoredString;
+}
+
+impl Themed for str {
+    fn header(&self) -> ColoredString {
+        paint(self, current().header).bold()
+    }
+    
+    fn section(&self) -> ColoredString {
+        paint(self, current().section).bold()
+    }
+    
+    fn success(&self) -> ColoredString {
+        paint(self, current().success)
+    }
+    
+    fn warning(&self) -> ColoredString {
+        paint(self, current().warning)
+    }
+    
+    fn error(&self) -> ColoredString {
+        paint(self, current().error)
+    }
+    
+    fn highlight(&self) -> ColoredString {
+        paint(self, current().highlight)
+    }
+}
@@ -0,0 +1,86 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Whether phase durations are reported, set by `--timings`
+static TIMINGS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Recorded phases in the order they first ran
+static TIMINGS: Mutex<Vec<PhaseTiming>> = Mutex::new(Vec::new());
+
+pub fn set_timings_enabled(enabled: bool) {
+    TIMINGS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn timings_enabled() -> bool {
+    TIMINGS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Total time spent in one phase of a run
+#[derive(Debug, Serialize, Clone)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub ms: u64,
+    /// How often the phase ran, e.g. one clone per template layer
+    pub count: u32,
+}
+
+/// Add the time since `started` to `phase`
+pub fn record_timing(phase: &str, started: Instant) {
+    let elapsed = started.elapsed().as_millis() as u64;
+    tracing::info!("{} took {}ms", phase, elapsed);
+    if !timings_enabled() {
+        return;
+    }
+    let mut timings = TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+    match timings.iter_mut().find(|timing| timing.phase == phase) {
+        Some(timing) => {
+            timing.ms += elapsed;
+            timing.count += 1;
+        }
+        None => timings.push(PhaseTiming {
+            phase: phase.to_string(),
+            ms: elapsed,
+            count: 1,
+        }),
+    }
+}
+
+/// Phases recorded so far
+pub fn timings() -> Vec<PhaseTiming> {
+    TIMINGS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Print the phase table to stderr so it never mixes with JSON on stdout
+pub fn print_timings() {
+    let timings = timings();
+    if timings.is_empty() {
+        return;
+    }
+    let width = timings.iter().map(|t| t.phase.len()).max().unwrap_or(0);
+    eprintln!();
+    eprintln!("⏱️  Timings:");
+    for timing in &timings {
+        let repeated = if timing.count > 1 {
+            format!(" ({}x)", timing.count)
+        } else {
+            String::new()
+        };
+        eprintln!(
+            "  {:<width$}  {:>8}{}",
+            timing.phase,
+            format_duration_ms(timing.ms),
+            repeated,
+            width = width
+        );
+    }
+}
+
+fn format_duration_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else {
+        format!("{:.2}s", ms as f64 / 1000.0)
+    }
+}
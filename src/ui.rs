@@ -0,0 +1,384 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event};
+use crossterm::terminal;
+
+use crate::errors::MammothError;
+use crate::events::{ProgressEvent, ProgressKind};
+
+/// CI mode: no progress bars or cursor movement, one timestamped line per step
+static CI_MODE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_ci_mode(enabled: bool) {
+    CI_MODE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_ci_mode() -> bool {
+    CI_MODE.load(Ordering::Relaxed)
+}
+
+/// Plain mode: no emoji, colors or progress bars, for screen readers
+static PLAIN_MODE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_plain_mode(enabled: bool) {
+    PLAIN_MODE.store(enabled, Ordering::Relaxed);
+    if enabled {
+        colored::control::set_override(false);
+    }
+}
+
+pub fn is_plain_mode() -> bool {
+    PLAIN_MODE.load(Ordering::Relaxed)
+}
+
+/// `MAMMOTH_NO_COLOR` turns colors off for mammoth only, like `NO_COLOR` does for every tool
+pub fn color_disabled_by_env() -> bool {
+    std::env::var_os("MAMMOTH_NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Apply `MAMMOTH_NO_COLOR`; `NO_COLOR` and `CLICOLOR` are handled by `colored` itself
+pub fn init_color() {
+    if color_disabled_by_env() {
+        colored::control::set_override(false);
+    }
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF // 表情与符号
+            | 0x2190..=0x21FF // 箭头
+            | 0x2300..=0x23FF // ⏳ ⏸ 等技术符号
+            | 0x2500..=0x25FF // 制表符和几何图形
+            | 0x2600..=0x27BF // ✅ ❌ ✨ 等杂项符号
+            | 0x2B00..=0x2BFF
+            | 0x2139 // ℹ
+            | 0xFE0F // 变体选择符
+            | 0x200D // 零宽连接符
+    )
+}
+
+/// `text` without emoji and box drawing characters in plain mode, unchanged otherwise.
+/// The spaces after a leading emoji go with it, indentation before it is kept.
+pub fn plain_text(text: &str) -> Cow<'_, str> {
+    if !is_plain_mode() || !text.chars().any(is_emoji) {
+        return Cow::Borrowed(text);
+    }
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !is_emoji(c) {
+            plain.push(c);
+            continue;
+        }
+        if plain.is_empty() || plain.ends_with(char::is_whitespace) {
+            while chars.next_if(|c| *c == ' ').is_some() {}
+        }
+    }
+    Cow::Owned(plain)
+}
+
+/// Interactive runs may prompt; otherwise required input must come from flags
+static INTERACTIVE: AtomicBool = AtomicBool::new(true);
+
+pub fn set_interactive(enabled: bool) {
+    INTERACTIVE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
+/// Quiet mode: only errors are printed, no status lines, warnings or progress
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(enabled: bool) {
+    QUIET.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Route `tracing` events to stderr: warnings by default, info (git commands, file
+/// operations, timings) with `-v`, debug detail with `-vv` and errors only with `--quiet`.
+/// `MAMMOTH_LOG` takes a full filter instead, e.g. "mammoth_cli::git=trace".
+pub fn init_logging(verbosity: u8, quiet: bool) {
+    set_quiet(quiet);
+    let level = match (quiet, verbosity) {
+        (true, _) => "error",
+        (false, 0) => "warn",
+        (false, 1) => "info",
+        (false, 2) => "debug",
+        _ => "trace",
+    };
+    let filter = std::env::var("MAMMOTH_LOG")
+        .ok()
+        .and_then(|filter| tracing_subscriber::EnvFilter::try_new(filter).ok())
+        .unwrap_or_else(|| tracing_subscriber::EnvFilter::new(format!("mammoth_cli={}", level)));
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(!is_plain_mode() && !color_disabled_by_env())
+        .without_time()
+        .try_init();
+}
+
+/// Offline mode: no git, HTTP or registry access, only cached and local templates
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_offline(enabled: bool) {
+    OFFLINE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Refuse `action` (e.g. "fetch branch 'main'") with an `Offline` error under `--offline`
+pub fn ensure_online(action: &str) -> anyhow::Result<()> {
+    if is_offline() {
+        return Err(MammothError::Offline {
+            action: action.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Vendor variables set by common CI services besides `CI`
+const CI_VENDOR_VARS: [&str; 9] = [
+    "GITHUB_ACTIONS",
+    "GITLAB_CI",
+    "CIRCLECI",
+    "TRAVIS",
+    "BUILDKITE",
+    "JENKINS_URL",
+    "TF_BUILD",
+    "BITBUCKET_BUILD_NUMBER",
+    "TEAMCITY_VERSION",
+];
+
+/// Whether the process runs under a CI service
+pub fn detect_ci() -> bool {
+    let ci = std::env::var("CI")
+        .map(|value| !matches!(value.to_lowercase().as_str(), "" | "0" | "false"))
+        .unwrap_or(false);
+    ci || CI_VENDOR_VARS.iter().any(|var| std::env::var_os(var).is_some())
+}
+
+/// Error for input that would be prompted for on an interactive run
+pub fn missing_input(what: &str, flag: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} is required when running non-interactively, pass {}",
+        what,
+        flag
+    )
+}
+
+/// Error listing every input a non-interactive run is missing, so a CI job can be
+/// fixed in one go instead of one failure at a time
+pub fn missing_inputs(missing: &[(String, String)]) -> anyhow::Error {
+    if let [(what, flag)] = missing {
+        return missing_input(what, flag);
+    }
+    let lines: Vec<String> = missing
+        .iter()
+        .map(|(what, flag)| format!("  - {}: pass {}", what, flag))
+        .collect();
+    anyhow::anyhow!(
+        "Missing required input when running non-interactively:\n{}",
+        lines.join("\n")
+    )
+}
+
+/// Locale used for localized template names and descriptions, e.g. "zh-CN"
+static LOCALE: OnceLock<String> = OnceLock::new();
+
+/// CLI locale from `MAMMOTH_LOCALE`, `LC_ALL`, `LC_MESSAGES` or `LANG`
+pub fn current_locale() -> &'static str {
+    LOCALE.get_or_init(|| {
+        ["MAMMOTH_LOCALE", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+            // "zh_CN.UTF-8" -> "zh-CN"
+            .map(|value| value.split('.').next().unwrap_or_default().replace('_', "-"))
+            .unwrap_or_else(|| "en".to_string())
+    })
+}
+
+/// Time a prompt waits for the user before taking its default answer
+static PROMPT_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+pub fn set_prompt_timeout(timeout: Duration) {
+    let _ = PROMPT_TIMEOUT.set(timeout);
+}
+
+/// Count down before a prompt, returning true if nobody pressed a key in time and
+/// the default answer should be taken without asking
+pub fn prompt_timed_out(prompt: &str, default: &str) -> anyhow::Result<bool> {
+    let timeout = match PROMPT_TIMEOUT.get() {
+        Some(timeout) if std::io::stdin().is_terminal() => *timeout,
+        _ => return Ok(false),
+    };
+    
+    let deadline = Instant::now() + timeout;
+    terminal::enable_raw_mode()?;
+    let pressed = wait_for_key(prompt, default, deadline);
+    // 无论等待是否出错都要恢复终端模式
+    terminal::disable_raw_mode()?;
+    let pressed = pressed?;
+    eprint!("\r{}\r", " ".repeat(prompt.len() + default.len() + 50));
+    
+    if !pressed {
+        eprintln!("⏳ {}: no answer, using '{}'", prompt, default);
+    }
+    Ok(!pressed)
+}
+
+fn wait_for_key(prompt: &str, default: &str, deadline: Instant) -> anyhow::Result<bool> {
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(false);
+        }
+        eprint!(
+            "\r⏳ {} - press any key to answer, using '{}' in {}s ",
+            prompt,
+            default,
+            remaining.as_secs() + 1
+        );
+        let _ = std::io::stderr().flush();
+        if event::poll(remaining.min(Duration::from_secs(1)))? {
+            if let Event::Key(_) = event::read()? {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// Print a line prefixed with the local time, e.g. "[12:03:45] Cloning repository..."
+fn log_line(message: &str) {
+    if !is_quiet() {
+        println!("[{}] {}", chrono::Local::now().format("%H:%M:%S"), plain_text(message));
+    }
+}
+
+/// Whether progress is reported as log lines instead of progress bars
+fn logs_progress() -> bool {
+    is_ci_mode() || is_plain_mode() || is_quiet()
+}
+
+/// Progress bars drawn for the progress events of the core
+struct ProgressBars {
+    multi: MultiProgress,
+    labels: HashMap<u64, String>,
+    bars: HashMap<u64, ProgressBar>,
+    /// Bar of the running batch download, single downloads are drawn above it
+    batch: Option<u64>,
+}
+
+static PROGRESS_BARS: OnceLock<Mutex<ProgressBars>> = OnceLock::new();
+
+fn progress_bars() -> &'static Mutex<ProgressBars> {
+    PROGRESS_BARS.get_or_init(|| {
+        Mutex::new(ProgressBars {
+            multi: MultiProgress::new(),
+            labels: HashMap::new(),
+            bars: HashMap::new(),
+            batch: None,
+        })
+    })
+}
+
+fn progress_style(kind: ProgressKind) -> ProgressStyle {
+    let template = match kind {
+        ProgressKind::Download => {
+            "{spinner:.green} {prefix:.bold} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}"
+        }
+        ProgressKind::Batch => "{prefix:.bold} [{bar:40.green/white}] {pos}/{len} {msg}",
+        ProgressKind::Generate => "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {percent:>3}% {msg}",
+    };
+    ProgressStyle::default_bar()
+        .template(template)
+        .unwrap()
+        .progress_chars("#>-")
+}
+
+fn log_progress(labels: &HashMap<u64, String>, id: u64, message: &str) {
+    match labels.get(&id).filter(|label| !label.is_empty()) {
+        Some(label) => log_line(&format!("{}: {}", label, message)),
+        None => log_line(message),
+    }
+}
+
+/// Draw a progress event as a progress bar, or log its messages as timestamped lines
+/// in CI, plain and quiet mode
+pub fn render_progress(event: &ProgressEvent) {
+    let mut guard = progress_bars().lock().unwrap();
+    let state = &mut *guard;
+    match event {
+        ProgressEvent::Started { id, kind, label, total } => {
+            state.labels.insert(*id, label.clone());
+            if logs_progress() {
+                return;
+            }
+            let bar = ProgressBar::new(*total)
+                .with_style(progress_style(*kind))
+                .with_prefix(label.clone());
+            let batch = state.batch.and_then(|batch| state.bars.get(&batch));
+            let bar = match batch {
+                Some(batch) if *kind == ProgressKind::Download => state.multi.insert_before(batch, bar),
+                _ => state.multi.add(bar),
+            };
+            if *kind == ProgressKind::Batch {
+                state.batch = Some(*id);
+            }
+            state.bars.insert(*id, bar);
+        }
+        ProgressEvent::Message { id, message } => {
+            if logs_progress() {
+                log_progress(&state.labels, *id, message);
+            } else if let Some(bar) = state.bars.get(id) {
+                bar.set_message(message.clone());
+            }
+        }
+        ProgressEvent::Position { id, position } => {
+            if let Some(bar) = state.bars.get(id) {
+                bar.set_position(*position);
+            }
+        }
+        ProgressEvent::Finished { id, message } | ProgressEvent::Failed { id, message } => {
+            if logs_progress() {
+                log_progress(&state.labels, *id, message);
+            }
+            state.labels.remove(id);
+            if state.batch == Some(*id) {
+                state.batch = None;
+            }
+            match (state.bars.remove(id), event) {
+                (Some(bar), ProgressEvent::Finished { .. }) => bar.finish_with_message(message.clone()),
+                (Some(bar), _) => bar.abandon_with_message(message.clone()),
+                (None, _) => {}
+            }
+        }
+    }
+}
+
+/// Run `print` with the progress bars cleared, so printed lines do not tear them
+pub fn suspend_progress<R>(print: impl FnOnce() -> R) -> R {
+    match PROGRESS_BARS.get() {
+        Some(state) => {
+            let multi = state.lock().unwrap().multi.clone();
+            multi.suspend(print)
+        }
+        None => print(),
+    }
+}
@@ -1,12 +1,21 @@
 use anyhow::Result;
+use colored::Color;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use serde_json;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::config::ProjectConfig;
+use crate::errors::MammothError;
+use crate::git::git_binary;
+use crate::{status, warning};
 
 pub fn copy_directory(src: &Path, dst: &Path) -> Result<()> {
+    tracing::debug!("copy {} -> {}", src.display(), dst.display());
     if src.is_file() {
         if let Some(parent) = dst.parent() {
             fs::create_dir_all(parent)?;
@@ -30,6 +39,113 @@ pub fn copy_directory(src: &Path, dst: &Path) -> Result<()> {
     Ok(())
 }
 
+/// List all files under `root` as paths relative to it, skipping `.git` directories
+pub fn list_files(root: &Path) -> Result<Vec<PathBuf>> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            
+            if path.is_dir() {
+                if entry.file_name() != ".git" {
+                    walk(root, &path, files)?;
+                }
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                files.push(relative.to_path_buf());
+            }
+        }
+        Ok(())
+    }
+    
+    let mut files = Vec::new();
+    if root.is_dir() {
+        walk(root, root, &mut files)?;
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any run of characters
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=name.len())
+                .filter(|&i| name.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &name[i..]))
+        }
+    }
+}
+
+/// Paths under `root` matching a relative pattern such as "docs", ".github/" or
+/// "screenshots/*.png", with `*` matching within one path segment
+pub fn matching_paths(root: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let pattern = pattern.trim().trim_matches('/');
+    if pattern.is_empty() || Path::new(pattern).is_absolute() || pattern.split('/').any(|s| s == "..") {
+        anyhow::bail!("Invalid path pattern '{}': must be relative and inside the template", pattern);
+    }
+    
+    let mut matches = vec![PathBuf::new()];
+    for segment in pattern.split('/').filter(|s| !s.is_empty() && *s != ".") {
+        let mut next = Vec::new();
+        for parent in &matches {
+            let dir = root.join(parent);
+            if !segment.contains('*') {
+                if dir.join(segment).exists() {
+                    next.push(parent.join(segment));
+                }
+                continue;
+            }
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if wildcard_match(segment, &name) {
+                    next.push(parent.join(name));
+                }
+            }
+        }
+        matches = next;
+    }
+    matches.retain(|path| !path.as_os_str().is_empty());
+    matches.sort();
+    Ok(matches)
+}
+
+/// Whether `path` or one of its parent directories is matched by a `matching_paths`
+/// pattern, without looking at the filesystem, e.g. for paths a template is renamed to
+pub fn path_matches(pattern: &str, path: &Path) -> bool {
+    let segments: Vec<&str> = pattern
+        .trim()
+        .trim_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect();
+    let names: Vec<String> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    !segments.is_empty()
+        && segments.len() <= names.len()
+        && segments.iter().zip(&names).all(|(segment, name)| wildcard_match(segment, name))
+}
+
+/// Split a comma-separated tag list, dropping empty entries
+pub fn parse_tags(tags: Option<&str>) -> Vec<String> {
+    tags.map(|tags_str| {
+        tags_str
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
 pub fn update_package_json(project_path: &Path, config: &ProjectConfig) -> Result<()> {
     let package_json_path = project_path.join("package.json");
     
@@ -46,10 +162,7 @@ pub fn update_package_json(project_path: &Path, config: &ProjectConfig) -> Resul
             "name".to_string(),
             serde_json::Value::String(config.name.clone()),
         );
-        obj.insert(
-            "author".to_string(),
-            serde_json::Value::String(config.author.clone()),
-        );
+        obj.insert("author".to_string(), config.author_json());
         obj.insert(
             "description".to_string(),
             serde_json::Value::String(config.description.clone()),
@@ -62,25 +175,556 @@ pub fn update_package_json(project_path: &Path, config: &ProjectConfig) -> Resul
     Ok(())
 }
 
+/// Apply a JSON merge patch (RFC 7386): objects merge recursively, `null` removes
+/// a key and any other value (including arrays) replaces the existing one
+pub fn merge_json(base: &mut serde_json::Value, patch: &serde_json::Value) {
+    let patch_obj = match patch.as_object() {
+        Some(obj) => obj,
+        None => {
+            *base = patch.clone();
+            return;
+        }
+    };
+    
+    if !base.is_object() {
+        *base = serde_json::Value::Object(serde_json::Map::new());
+    }
+    if let Some(base_obj) = base.as_object_mut() {
+        for (key, value) in patch_obj {
+            if value.is_null() {
+                base_obj.remove(key);
+            } else {
+                merge_json(
+                    base_obj.entry(key.clone()).or_insert(serde_json::Value::Null),
+                    value,
+                );
+            }
+        }
+    }
+}
+
+/// Top-level package.json fields describing the project itself, kept from the earlier layer
+const PACKAGE_IDENTITY_FIELDS: [&str; 4] = ["name", "version", "description", "author"];
+
+/// Key two package.json layers set to different values, the later layer's value wins
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonConflict {
+    /// Dotted path of the key, e.g. "scripts.dev" or "dependencies.vue"
+    pub key: String,
+    pub earlier: serde_json::Value,
+    pub later: serde_json::Value,
+}
+
+/// Deep merge the package.json of a later template layer into `base`. Objects such as
+/// `dependencies`, `devDependencies` and `scripts` are unioned key by key and arrays such
+/// as `files` or `workspaces` are unioned too. A key both layers set to different values
+/// takes the later value and is returned as a conflict, except the project's own name,
+/// version, description and author, which the earlier layer keeps.
+pub fn merge_package_json(base: &mut serde_json::Value, layer: &serde_json::Value) -> Vec<JsonConflict> {
+    let mut layer = layer.clone();
+    if let (Some(base_obj), Some(layer_obj)) = (base.as_object(), layer.as_object_mut()) {
+        layer_obj.retain(|key, _| !(PACKAGE_IDENTITY_FIELDS.contains(&key.as_str()) && base_obj.contains_key(key)));
+    }
+    
+    let mut conflicts = Vec::new();
+    deep_merge_json(base, &layer, "", &mut conflicts);
+    conflicts
+}
+
+fn deep_merge_json(
+    base: &mut serde_json::Value,
+    layer: &serde_json::Value,
+    path: &str,
+    conflicts: &mut Vec<JsonConflict>,
+) {
+    match (base, layer) {
+        (serde_json::Value::Object(base_obj), serde_json::Value::Object(layer_obj)) => {
+            for (key, value) in layer_obj {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match base_obj.get_mut(key) {
+                    Some(existing) => deep_merge_json(existing, value, &key_path, conflicts),
+                    None => {
+                        base_obj.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (serde_json::Value::Array(base_items), serde_json::Value::Array(layer_items)) => {
+            for item in layer_items {
+                if !base_items.contains(item) {
+                    base_items.push(item.clone());
+                }
+            }
+        }
+        (base, layer) => {
+            if *base != *layer {
+                conflicts.push(JsonConflict {
+                    key: path.to_string(),
+                    earlier: base.clone(),
+                    later: layer.clone(),
+                });
+                *base = layer.clone();
+            }
+        }
+    }
+}
+
+/// Merge `patch` into the project's package.json, creating the file if needed
+pub fn patch_package_json(project_path: &Path, patch: &serde_json::Value) -> Result<()> {
+    let package_json_path = project_path.join("package.json");
+    let mut package_json: serde_json::Value = if package_json_path.exists() {
+        serde_json::from_str(&fs::read_to_string(&package_json_path)?)?
+    } else {
+        serde_json::Value::Object(serde_json::Map::new())
+    };
+    
+    merge_json(&mut package_json, patch);
+    fs::write(&package_json_path, serde_json::to_string_pretty(&package_json)?)?;
+    
+    Ok(())
+}
+
+/// Add packages to package.json. Existing entries are overridden by the new spec, both
+/// sections are written sorted, and a package listed in `dependencies` is dropped from
+/// `devDependencies` so each package appears once.
+pub fn add_package_dependencies(
+    project_path: &Path,
+    dependencies: &BTreeMap<String, String>,
+    dev_dependencies: &BTreeMap<String, String>,
+) -> Result<()> {
+    if dependencies.is_empty() && dev_dependencies.is_empty() {
+        return Ok(());
+    }
+    
+    let package_json_path = project_path.join("package.json");
+    let mut package_json: serde_json::Value = if package_json_path.exists() {
+        serde_json::from_str(&fs::read_to_string(&package_json_path)?)?
+    } else {
+        serde_json::json!({})
+    };
+    
+    let section = |value: &serde_json::Value, key: &str| -> BTreeMap<String, serde_json::Value> {
+        value
+            .get(key)
+            .and_then(|v| v.as_object())
+            .map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default()
+    };
+    let mut deps = section(&package_json, "dependencies");
+    let mut dev_deps = section(&package_json, "devDependencies");
+    
+    for (name, version) in dev_dependencies {
+        dev_deps.insert(name.clone(), serde_json::Value::String(version.clone()));
+    }
+    for (name, version) in dependencies {
+        deps.insert(name.clone(), serde_json::Value::String(version.clone()));
+    }
+    dev_deps.retain(|name, _| !deps.contains_key(name));
+    
+    let obj = package_json
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("package.json is not a JSON object"))?;
+    for (key, entries) in [("dependencies", deps), ("devDependencies", dev_deps)] {
+        if entries.is_empty() {
+            obj.remove(key);
+        } else {
+            obj.insert(key.to_string(), serde_json::Value::Object(entries.into_iter().collect()));
+        }
+    }
+    
+    fs::write(&package_json_path, serde_json::to_string_pretty(&package_json)?)?;
+    Ok(())
+}
+
 pub fn init_git_repository(project_path: &Path) -> Result<()> {
     // Change to project directory
     let current_dir = std::env::current_dir()?;
     std::env::set_current_dir(project_path)?;
     
     // Initialize git repository
-    let status = Command::new("git").args(["init"]).status();
+    let status = Command::new(git_binary()).args(["init"]).status();
     
     // Restore original directory
     std::env::set_current_dir(current_dir)?;
     
     match status {
         Ok(_) => {
-            println!("🔧 Git repository initialized");
+            status!("🔧 Git repository initialized");
         }
         Err(_) => {
-            println!("⚠️  Git not available, skipping repository initialization");
+            warning!("⚠️  Git not available, skipping repository initialization");
+        }
+    }
+    
+    Ok(())
+}
+
+/// Replace `{{variable}}` placeholders with their values, unknown variables are left as-is
+pub fn interpolate(text: &str, vars: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let key = after[..end].trim();
+                match vars.get(key) {
+                    Some(value) => result.push_str(value),
+                    None => result.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    result.push_str(rest);
+    
+    result
+}
+
+/// Whether file content looks binary: a NUL byte near the start or invalid UTF-8
+pub fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(8000).any(|b| *b == 0) || std::str::from_utf8(content).is_err()
+}
+
+/// Substitutions made in one file by the placeholder pass
+#[derive(Debug, Serialize, Clone)]
+pub struct FileReplacements {
+    pub path: String,
+    pub substitutions: usize,
+}
+
+/// Outcome of the placeholder pass, for template authors hunting missing placeholders
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct RenderReport {
+    pub modified: Vec<FileReplacements>,
+    /// Files left untouched because they look binary
+    pub skipped_binary: Vec<String>,
+}
+
+impl RenderReport {
+    pub fn total_substitutions(&self) -> usize {
+        self.modified.iter().map(|file| file.substitutions).sum()
+    }
+}
+
+/// Replace placeholders with their values in every text file under `root`, longest
+/// placeholder first so "my-app-admin" is not split by a shorter "my-app"
+pub fn replace_placeholders(root: &Path, replacements: &[(String, String)]) -> Result<RenderReport> {
+    let mut report = RenderReport::default();
+    let mut replacements: Vec<&(String, String)> = replacements
+        .iter()
+        .filter(|(placeholder, value)| !placeholder.is_empty() && placeholder != value)
+        .collect();
+    if replacements.is_empty() {
+        return Ok(report);
+    }
+    replacements.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    
+    for relative in list_files(root)? {
+        let path = root.join(&relative);
+        let bytes = fs::read(&path)?;
+        if is_binary(&bytes) {
+            report.skipped_binary.push(relative.display().to_string());
+            continue;
+        }
+        let (content, substitutions) = substitute(&String::from_utf8(bytes)?, &replacements);
+        if substitutions > 0 {
+            tracing::debug!("render {} ({} substitutions)", relative.display(), substitutions);
+            fs::write(&path, content)?;
+            report.modified.push(FileReplacements {
+                path: relative.display().to_string(),
+                substitutions,
+            });
+        }
+    }
+    
+    Ok(report)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether `placeholder` at the start of `rest`, after `previous`, is a whole word: a
+/// placeholder starting or ending with a letter or digit does not match inside a longer
+/// word, so the package name "app" leaves "application" alone
+fn is_whole_word(placeholder: &str, previous: Option<char>, rest: &str) -> bool {
+    let starts_word = placeholder.chars().next().is_some_and(is_word_char);
+    let ends_word = placeholder.chars().last().is_some_and(is_word_char);
+    let next = rest[placeholder.len()..].chars().next();
+    !(starts_word && previous.is_some_and(is_word_char))
+        && !(ends_word && next.is_some_and(is_word_char))
+}
+
+/// `text` with the placeholders replaced in one left-to-right scan that tries the longest
+/// placeholder at each position. Inserted values are not scanned again, so a description
+/// containing the author's name is not rewritten by the author placeholder.
+fn substitute(text: &str, replacements: &[&(String, String)]) -> (String, usize) {
+    let mut result = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut rest = text;
+    let mut previous = None;
+    while !rest.is_empty() {
+        let found = replacements.iter().find(|(placeholder, _)| {
+            rest.starts_with(placeholder.as_str()) && is_whole_word(placeholder, previous, rest)
+        });
+        match found {
+            Some((placeholder, value)) => {
+                result.push_str(value);
+                rest = &rest[placeholder.len()..];
+                previous = placeholder.chars().last();
+                count += 1;
+            }
+            None => {
+                let mut chars = rest.chars();
+                if let Some(c) = chars.next() {
+                    result.push(c);
+                    previous = Some(c);
+                }
+                rest = chars.as_str();
+            }
+        }
+    }
+    (result, count)
+}
+
+/// Time allowed to connect to an HTTP server before giving up
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// HTTP client for downloads and uploads: gives up after `timeout` for the whole request
+/// and sends the mammoth-cli user agent
+pub fn http_client(timeout: Duration) -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .connect_timeout(HTTP_CONNECT_TIMEOUT)
+        .timeout(timeout)
+        .user_agent(concat!("mammoth-cli/", env!("CARGO_PKG_VERSION")))
+        .build()?)
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Parse a duration such as "30m", "12h", "7d" or "2w"
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    
+    let value: u64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid duration: '{}'", text))?;
+    let unit_seconds: u64 = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => anyhow::bail!("Invalid duration unit in '{}'. Use s, m, h, d or w", text),
+    };
+    let seconds = value
+        .checked_mul(unit_seconds)
+        .ok_or_else(|| anyhow::anyhow!("Duration '{}' is too long", text))?;
+    
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Parse a size like "500MB", "1.5GiB" or "2048" (bytes); decimal and binary units
+/// are both read as powers of 1024
+pub fn parse_size(text: &str) -> Result<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    
+    let value: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size: '{}'", text))?;
+    let multiplier: u64 = match unit.trim().to_uppercase().trim_end_matches("IB").trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => anyhow::bail!("Invalid size unit in '{}'. Use B, KB, MB, GB or TB", text),
+    };
+    
+    Ok((value * multiplier as f64) as u64)
+}
+
+/// Lowercase hex SHA-256 digest of `content`
+pub fn sha256_hex(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Refuse `content` read from `source` unless its SHA-256 matches `expected` (hex)
+pub fn verify_sha256(content: &[u8], expected: &str, source: &str) -> Result<()> {
+    let actual = sha256_hex(content);
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        anyhow::bail!(
+            "Checksum mismatch for {}: expected sha256 {}, got {}. The file may have been tampered with or truncated",
+            source,
+            expected.trim(),
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Fail early with `InsufficientDiskSpace` when the disk holding `path` has less than
+/// `required` bytes free. Unknown free space (e.g. unsupported file system) passes.
+pub fn ensure_disk_space(path: &Path, required: u64) -> Result<()> {
+    // 目录可能还不存在，检查最近的已存在上级目录
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(path);
+    let available = match fs2::available_space(existing) {
+        Ok(available) => available,
+        Err(_) => return Ok(()),
+    };
+    if available < required {
+        return Err(MammothError::InsufficientDiskSpace {
+            path: path.to_path_buf(),
+            required,
+            available,
         }
+        .into());
+    }
+    Ok(())
+}
+
+/// Format a duration with its largest whole unit, e.g. "3d" or "5h"
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 60 * 60 * 24 {
+        format!("{}d", seconds / (60 * 60 * 24))
+    } else if seconds >= 60 * 60 {
+        format!("{}h", seconds / (60 * 60))
+    } else if seconds >= 60 {
+        format!("{}m", seconds / 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Total size in bytes of all files under `path`
+pub fn dir_size(path: &Path) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
     }
     
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| dir_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Format a byte count with binary units, e.g. "1.5 MiB"
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Remove a file or directory, moving it to the OS trash instead when `use_trash` is set
+pub fn remove_path(path: &Path, use_trash: bool) -> Result<()> {
+    tracing::info!("{} {}", if use_trash { "trash" } else { "remove" }, path.display());
+    if use_trash {
+        trash::delete(path)
+            .map_err(|e| anyhow::anyhow!("Failed to move {} to trash: {}", path.display(), e))?;
+    } else if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Value of a git config key such as `user.email`, `None` when unset or git is missing
+pub fn git_config_value(key: &str) -> Option<String> {
+    let output = Command::new(git_binary())
+        .args(["config", "--get", key])
+        .output()
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+/// Executable name of a tool. npm, pnpm and yarn are batch scripts on Windows and need
+/// their `.cmd` name there.
+pub fn tool_program(tool: &str) -> String {
+    if cfg!(windows) && matches!(tool, "npm" | "pnpm" | "yarn") {
+        format!("{}.cmd", tool)
+    } else {
+        tool.to_string()
+    }
+}
+
+/// First line of `<tool> --version`, `None` when the tool is not installed
+pub fn tool_version(tool: &str) -> Option<String> {
+    let output = Command::new(tool_program(tool)).arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.lines().next()?.trim();
+    (output.status.success() && !version.is_empty()).then(|| version.to_string())
+}
+
+/// Name of the user running mammoth, for audit records
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Parse a display color: a name understood by `colored` ("cyan", "bright magenta") or "#rrggbb"
+pub fn parse_color(text: &str) -> Result<Color> {
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() == 6 {
+            if let Ok(rgb) = u32::from_str_radix(hex, 16) {
+                return Ok(Color::TrueColor {
+                    r: (rgb >> 16) as u8,
+                    g: (rgb >> 8) as u8,
+                    b: rgb as u8,
+                });
+            }
+        }
+        anyhow::bail!("Invalid color '{}', expected #rrggbb", text);
+    }
+    text.parse::<Color>()
+        .map_err(|_| anyhow::anyhow!("Unknown color '{}'", text))
+}